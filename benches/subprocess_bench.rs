@@ -0,0 +1,12 @@
+fn fibonacci(n: u64) -> u64 {
+    (1..n).fold((0, 1), |(a, b), _| (b, a + b)).1
+}
+
+subprocess_test::subprocess_test_bench! {
+    fn bench_fibonacci(b: &mut Bencher) {
+        b.iter(|| fibonacci(std::hint::black_box(30)));
+    }
+}
+
+criterion::criterion_group!(benches, bench_fibonacci);
+criterion::criterion_main!(benches);
@@ -0,0 +1,35 @@
+//! Builds preload library used by `inject_fault = "malloc_fail_after = N"` test parameter
+fn main() {
+    #[cfg(feature = "chaos-testing")]
+    build_malloc_fail_library();
+}
+
+#[cfg(feature = "chaos-testing")]
+fn build_malloc_fail_library() {
+    use std::env::var;
+    use std::path::PathBuf;
+
+    const SOURCE: &str = "src/malloc_fail.c";
+
+    println!("cargo:rerun-if-changed={SOURCE}");
+    // Library is preloaded through `LD_PRELOAD`, which is specific to Linux dynamic loader
+    if var("CARGO_CFG_TARGET_OS").as_deref() != Ok("linux") {
+        return;
+    }
+
+    let output = PathBuf::from(var("OUT_DIR").unwrap()).join("libsubprocess_test_malloc_fail.so");
+    let status = cc::Build::new()
+        .get_compiler()
+        .to_command()
+        .args(["-shared", "-fPIC", "-O2", "-o"])
+        .arg(&output)
+        .args([SOURCE, "-ldl"])
+        .status()
+        .expect("Failed to run C compiler");
+    assert!(status.success(), "Failed to build {SOURCE}");
+
+    println!(
+        "cargo:rustc-env=SUBPROCESS_TEST_MALLOC_FAIL_LIBRARY={}",
+        output.display()
+    );
+}
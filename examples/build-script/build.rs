@@ -0,0 +1,46 @@
+use std::env::var_os;
+use std::path::Path;
+
+fn generate_constants() -> String {
+    "pub const ANSWER: u32 = 42;\n".to_owned()
+}
+
+subprocess_test::build_subprocess_test! {
+    /// Checks that generated code contains expected constant
+    fn constants_are_generated() {
+        print!("{}", generate_constants());
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "pub const ANSWER: u32 = 42;\n");
+    }
+
+    /// Checks that build script environment is available in subprocess
+    fn build_environment_is_inherited() {
+        assert!(var_os("OUT_DIR").is_some());
+    }
+
+    /// Panics in subprocess are reported as unsuccessful run
+    fn failure_is_detected() {
+        panic!("Generation failed");
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Generation failed"));
+    }
+}
+
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+
+    constants_are_generated();
+    build_environment_is_inherited();
+    failure_is_detected();
+
+    let out_dir = var_os("OUT_DIR").unwrap();
+    std::fs::write(
+        Path::new(&out_dir).join("constants.rs"),
+        generate_constants(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,2 @@
+//! Crate whose build script logic is covered by `build_subprocess_test!`
+include!(concat!(env!("OUT_DIR"), "/constants.rs"));
@@ -0,0 +1,172 @@
+//! Attribute form of `subprocess_test!` macro. Use it through `subprocess_test::attr` module,
+//! with `attribute` feature of `subprocess-test` crate enabled
+use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{ToTokens, quote};
+use syn::{Attribute, Error, Expr, ExprClosure, ItemFn, LitStr, Path, Result, parse_macro_input};
+
+/// Name of sibling attribute which holds `verify` closure
+const VERIFY_ATTR_NAME: &str = "subprocess_verify";
+
+/// Turns function into subprocess test. Attribute parameters are the same as parameters
+/// of `#[test(...)]` in `subprocess_test!`, and optional `verify` block is specified
+/// as closure in `#[subprocess_verify(...)]` attribute placed anywhere after this one.
+/// Function is expanded through `subprocess_test!`, so both forms generate same code.
+/// If `subprocess-test` dependency is renamed, its new name is passed as `crate = "name"`
+#[proc_macro_attribute]
+pub fn subprocess_test(params: TokenStream, item: TokenStream) -> TokenStream {
+    let params = TokenStream2::from(params);
+    let item = parse_macro_input!(item as ItemFn);
+    expand(params, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Specifies `verify` block of `#[subprocess_test]` function as closure,
+/// like `#[subprocess_verify(|success, output| { ... })]`.
+/// Consumed by `#[subprocess_test]`, so it needn't be imported, and it's an error
+/// to use it alone
+#[proc_macro_attribute]
+pub fn subprocess_verify(_params: TokenStream, item: TokenStream) -> TokenStream {
+    let mut output = Error::new(
+        proc_macro2::Span::call_site(),
+        "`#[subprocess_verify]` must be placed after `#[subprocess_test]` attribute",
+    )
+    .into_compile_error();
+    output.extend(TokenStream2::from(item));
+    output.into()
+}
+
+fn expand(params: TokenStream2, item: ItemFn) -> Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "Subprocess test can't be generic",
+        ));
+    }
+    if !sig.inputs.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            "Subprocess test can't have parameters",
+        ));
+    }
+    // Visibility isn't supported by `subprocess_test!`, and makes no sense for test anyway
+    if !matches!(vis, syn::Visibility::Inherited) {
+        return Err(Error::new_spanned(vis, "Subprocess test can't be public"));
+    }
+
+    let mut verify = None;
+    let mut other_attrs = Vec::new();
+    for attr in attrs {
+        if attr.path().is_ident("test") {
+            return Err(Error::new_spanned(
+                attr,
+                "`#[test]` is added by `#[subprocess_test]`",
+            ));
+        }
+        if is_verify_attr(&attr) {
+            if verify.is_some() {
+                return Err(Error::new_spanned(
+                    attr,
+                    "Duplicate `#[subprocess_verify]` attribute",
+                ));
+            }
+            verify = Some(verify_block(&attr)?);
+        } else {
+            other_attrs.push(attr);
+        }
+    }
+
+    let (krate, params) = split_crate_param(params)?;
+    let test_attr = if params.is_empty() {
+        quote!(#[test])
+    } else {
+        quote!(#[test(#params)])
+    };
+    let asyncness = &sig.asyncness;
+    let name = &sig.ident;
+    let output = &sig.output;
+
+    Ok(quote! {
+        #krate::attr::__subprocess_test! {
+            #test_attr
+            #(#other_attrs)*
+            #asyncness fn #name() #output #block
+            #verify
+        }
+    })
+}
+
+/// Extracts `crate = "name"` parameter, which isn't passed to `subprocess_test!`.
+/// Parameters are split by top-level commas, since their values are arbitrary tokens
+fn split_crate_param(params: TokenStream2) -> Result<(TokenStream2, TokenStream2)> {
+    let mut split_params = vec![Vec::new()];
+    for token in params {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => split_params.push(Vec::new()),
+            _ => split_params.last_mut().unwrap().push(token),
+        }
+    }
+
+    let mut krate = None;
+    let mut other_params = Vec::new();
+    for param in split_params {
+        match param.as_slice() {
+            [TokenTree::Ident(ident), TokenTree::Punct(eq), value @ ..]
+                if ident == "crate" && eq.as_char() == '=' =>
+            {
+                if krate.is_some() {
+                    return Err(Error::new_spanned(ident, "Duplicate `crate` parameter"));
+                }
+                let name: LitStr = syn::parse2(value.iter().cloned().collect())?;
+                krate = Some(name.parse::<Path>()?);
+            }
+            [] => {}
+            _ => other_params.push(param.into_iter().collect::<TokenStream2>()),
+        }
+    }
+    let krate = krate.map_or_else(|| quote!(::subprocess_test), ToTokens::into_token_stream);
+    Ok((krate, quote!(#(#other_params),*)))
+}
+
+/// Attribute is matched by last path segment, so it can be used both imported and by full path
+fn is_verify_attr(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == VERIFY_ATTR_NAME)
+}
+
+/// Converts closure from `#[subprocess_verify(...)]` into `verify |...| { ... }` block
+fn verify_block(attr: &Attribute) -> Result<TokenStream2> {
+    let closure: ExprClosure = attr.parse_args()?;
+    let mut params = Vec::new();
+    for input in &closure.inputs {
+        match input {
+            syn::Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                params.push(&pat.ident);
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    input,
+                    "`verify` closure parameters must be plain identifiers",
+                ));
+            }
+        }
+    }
+    let asyncness = &closure.asyncness;
+    let body = match &*closure.body {
+        Expr::Block(block) if block.attrs.is_empty() && block.label.is_none() => {
+            block.block.to_token_stream()
+        }
+        body => quote!({ #body }),
+    };
+    Ok(quote!(verify #asyncness |#(#params),*| #body))
+}
@@ -0,0 +1,50 @@
+//! Pinning subprocess to CPU cores, see `cpu_affinity` parameter
+#[cfg(windows)]
+use std::process::Child;
+#[cfg(target_os = "linux")]
+use std::process::Command;
+
+/// Restricts subprocess to specified cores. Affinity is set in forked child right before `exec`
+#[cfg(target_os = "linux")]
+pub(crate) fn set_cpu_affinity(command: &mut Command, cores: &[usize]) {
+    use nix::sched::{CpuSet, sched_setaffinity};
+    use nix::unistd::Pid;
+    use std::os::unix::process::CommandExt;
+
+    let mut cpu_set = CpuSet::new();
+    for &core in cores {
+        cpu_set
+            .set(core)
+            .unwrap_or_else(|err| panic!("Invalid CPU core {core}: {err}"));
+    }
+    // SAFETY: closure only performs `sched_setaffinity` syscall, which is async-signal-safe,
+    // and doesn't allocate or touch any locks
+    unsafe {
+        command.pre_exec(move || sched_setaffinity(Pid::from_raw(0), &cpu_set).map_err(Into::into));
+    }
+}
+
+/// Restricts spawned subprocess to specified cores. Subprocess is killed if this fails
+#[cfg(windows)]
+pub(crate) fn set_cpu_affinity(child: &mut Child, cores: &[usize]) {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Threading::SetProcessAffinityMask;
+
+    let mask = cores.iter().fold(0usize, |mask, &core| {
+        assert!(
+            core < usize::BITS as usize,
+            "Invalid CPU core {core}: only first {} cores are supported",
+            usize::BITS
+        );
+        mask | (1 << core)
+    });
+    // SAFETY: handle is owned by `child` and stays valid during the call
+    let succeeded = unsafe { SetProcessAffinityMask(child.as_raw_handle(), mask) } != 0;
+
+    if !succeeded {
+        let err = std::io::Error::last_os_error();
+        let _ = child.kill();
+        let _ = child.wait();
+        panic!("Failed to set subprocess CPU affinity to {cores:?}: {err}");
+    }
+}
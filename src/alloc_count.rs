@@ -0,0 +1,95 @@
+//! Counting of heap allocations made by subprocess, see `capture_allocations` parameter
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const ALLOC_COUNT_RECORD: &str = "alloc-count";
+
+/// Global allocator which forwards all requests to system one,
+/// counting allocations and deallocations while counting is enabled
+struct CountingAllocator;
+
+thread_local! {
+    // Set only on test body's thread, so allocations made meanwhile by harness threads
+    // aren't counted. Constant initializer and no destructor mean access never allocates
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+static ALLOCS: AtomicU64 = AtomicU64::new(0);
+static DEALLOCS: AtomicU64 = AtomicU64::new(0);
+
+// SAFETY: all requests are forwarded to system allocator as is
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if is_counting() {
+            ALLOCS.fetch_add(1, Ordering::Relaxed);
+        }
+        // SAFETY: caller upholds `GlobalAlloc::alloc` contract
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if is_counting() {
+            ALLOCS.fetch_add(1, Ordering::Relaxed);
+        }
+        // SAFETY: caller upholds `GlobalAlloc::alloc_zeroed` contract
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if is_counting() {
+            DEALLOCS.fetch_add(1, Ordering::Relaxed);
+        }
+        // SAFETY: caller upholds `GlobalAlloc::dealloc` contract
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    // Reallocation resizes existing block, so it's counted neither as allocation nor deallocation
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: caller upholds `GlobalAlloc::realloc` contract
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn is_counting() -> bool {
+    COUNTING.try_with(Cell::get).unwrap_or(false)
+}
+
+/// Counts allocations from creation until drop, then sends counts to parent.
+/// Not `Send`, since counting is enabled only on thread which created it
+pub(crate) struct AllocCounter(std::marker::PhantomData<*const ()>);
+
+impl AllocCounter {
+    /// Resets counters and enables counting on current thread,
+    /// which must be the one test body runs on
+    pub(crate) fn start() -> Self {
+        ALLOCS.store(0, Ordering::Relaxed);
+        DEALLOCS.store(0, Ordering::Relaxed);
+        COUNTING.set(true);
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl Drop for AllocCounter {
+    fn drop(&mut self) {
+        // Disable counting first, so sending counts doesn't affect them
+        COUNTING.set(false);
+        let allocs = ALLOCS.load(Ordering::Relaxed);
+        let deallocs = DEALLOCS.load(Ordering::Relaxed);
+        crate::side_channel::send(ALLOC_COUNT_RECORD, &format!("{allocs}\t{deallocs}"));
+    }
+}
+
+/// Extracts allocation and deallocation counts sent by subprocess
+pub(crate) fn parse(records: &[(String, String)]) -> (Option<u64>, Option<u64>) {
+    records
+        .iter()
+        .rev()
+        .find(|(kind, _)| kind == ALLOC_COUNT_RECORD)
+        .and_then(|(_, payload)| payload.split_once('\t'))
+        .map_or((None, None), |(allocs, deallocs)| {
+            (allocs.parse().ok(), deallocs.parse().ok())
+        })
+}
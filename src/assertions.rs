@@ -0,0 +1,535 @@
+//! Assertion macros intended for use inside `verify` blocks
+
+/// Asserts that subprocess run duration lies within inclusive range `min_ms..=max_ms`
+///
+/// ```rust
+/// # use std::time::Duration;
+/// subprocess_test::assert_subprocess_duration!(Duration::from_millis(100), 50, 200);
+/// ```
+#[macro_export]
+macro_rules! assert_subprocess_duration {
+    ($duration:expr, $min_ms:expr, $max_ms:expr $(,)?) => {{
+        let duration: ::std::time::Duration = $duration;
+        let (min_ms, max_ms): (u64, u64) = ($min_ms, $max_ms);
+        assert!(
+            ::std::time::Duration::from_millis(min_ms) <= duration
+                && duration <= ::std::time::Duration::from_millis(max_ms),
+            "Subprocess took {}ms, expected between {min_ms}ms and {max_ms}ms",
+            duration.as_millis(),
+        );
+    }};
+}
+
+/// Asserts that subprocess run took less than `max_ms` milliseconds
+///
+/// ```rust
+/// # use std::time::Duration;
+/// subprocess_test::assert_subprocess_faster_than!(Duration::from_millis(50), 100);
+/// ```
+#[macro_export]
+macro_rules! assert_subprocess_faster_than {
+    ($duration:expr, $max_ms:expr $(,)?) => {{
+        let duration: ::std::time::Duration = $duration;
+        let max_ms: u64 = $max_ms;
+        assert!(
+            duration < ::std::time::Duration::from_millis(max_ms),
+            "Subprocess took {}ms, expected less than {max_ms}ms",
+            duration.as_millis(),
+        );
+    }};
+}
+
+/// Asserts that subprocess run took more than `min_ms` milliseconds
+///
+/// ```rust
+/// # use std::time::Duration;
+/// subprocess_test::assert_subprocess_slower_than!(Duration::from_millis(50), 10);
+/// ```
+#[macro_export]
+macro_rules! assert_subprocess_slower_than {
+    ($duration:expr, $min_ms:expr $(,)?) => {{
+        let duration: ::std::time::Duration = $duration;
+        let min_ms: u64 = $min_ms;
+        assert!(
+            duration > ::std::time::Duration::from_millis(min_ms),
+            "Subprocess took {}ms, expected more than {min_ms}ms",
+            duration.as_millis(),
+        );
+    }};
+}
+
+/// Asserts that first floating-point number found in output after `prefix`
+/// is within `tolerance` of `expected` value
+///
+/// ```rust
+/// let output = "iterations: 1000\nresult: 0.3333\n";
+/// subprocess_test::assert_subprocess_output_float!(output, "result: ", 1.0 / 3.0, 0.001);
+/// ```
+#[macro_export]
+macro_rules! assert_subprocess_output_float {
+    ($output:expr, $prefix:expr, $expected:expr, $tolerance:expr $(,)?) => {{
+        let output: &str = ::std::convert::AsRef::as_ref(&$output);
+        let prefix: &str = $prefix;
+        let expected = ($expected) as f64;
+        let tolerance = ($tolerance) as f64;
+        let label = $crate::assertions::float_label(prefix);
+        match $crate::assertions::find_float_after(output, prefix) {
+            Some(actual) => assert!(
+                (actual - expected).abs() <= tolerance,
+                "Expected {label} ≈ {expected} (±{tolerance}) but found {actual}",
+            ),
+            None => panic!(
+                "Expected {label} ≈ {expected} (±{tolerance}) but no number found after {prefix:?}. Output:\n{output}",
+            ),
+        }
+    }};
+}
+
+/// Finds first floating-point number which follows first occurrence of `prefix` in `output`
+#[doc(hidden)]
+pub fn find_float_after(output: &str, prefix: &str) -> Option<f64> {
+    let (_, rest) = output.split_once(prefix)?;
+    // Number starts at first digit, possibly preceded by sign and/or decimal point
+    let digit_at = rest.find(|c: char| c.is_ascii_digit())?;
+    let start = rest[..digit_at]
+        .strip_suffix('.')
+        .map_or(digit_at, |before| before.len());
+    let start = rest[..start]
+        .strip_suffix(['-', '+'])
+        .map_or(start, |before| before.len());
+    let candidate = &rest[start..];
+    let end = candidate
+        .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+        .unwrap_or(candidate.len());
+    // Candidate may capture trailing garbage like sentence's final dot, so shrink it until it parses
+    (1..=end)
+        .rev()
+        .find_map(|len| candidate[..len].parse().ok())
+}
+
+/// Human-readable name of value which follows prefix, like `result` for `"result: "`
+#[doc(hidden)]
+pub fn float_label(prefix: &str) -> &str {
+    let label = prefix.trim().trim_end_matches([':', '=']).trim_end();
+    if label.is_empty() { "value" } else { label }
+}
+
+/// Asserts that panic message reported in subprocess output equals expected one.
+/// See [`extract_panic_message`](crate::extract_panic_message) for supported formats
+///
+/// ```rust
+/// let output = "thread 'main' panicked at src/main.rs:2:5:\noops\n";
+/// subprocess_test::assert_panic_message!(output, "oops");
+/// ```
+#[macro_export]
+macro_rules! assert_panic_message {
+    ($output:expr, $expected:expr $(,)?) => {{
+        let output: &str = ::std::convert::AsRef::as_ref(&$output);
+        let expected: &str = ::std::convert::AsRef::as_ref(&$expected);
+        match $crate::extract_panic_message(output) {
+            Some(message) => assert!(
+                message == expected,
+                "Panic message {message:?} differs from expected {expected:?}",
+            ),
+            None => panic!("Output doesn't contain panic report:\n{output}"),
+        }
+    }};
+}
+
+/// Finds first panic report in subprocess output and returns its message, without test harness
+/// framing, location and backtrace. Both panic report formats are supported:
+///
+/// * `thread 'main' panicked at src/main.rs:2:5:\nmessage`, used since Rust 1.73
+/// * `thread 'main' panicked at 'message', src/main.rs:2:5`, used by earlier versions
+///
+/// ```rust
+/// use subprocess_test::extract_panic_message;
+///
+/// let output = "thread 'main' panicked at src/main.rs:2:5:\noops\nnote: run with ...\n";
+/// assert_eq!(extract_panic_message(output), Some("oops"));
+/// let output = "thread 'main' panicked at 'oops', src/main.rs:2:5\n";
+/// assert_eq!(extract_panic_message(output), Some("oops"));
+/// ```
+pub fn extract_panic_message(output: &str) -> Option<&str> {
+    const MARKER: &str = " panicked at ";
+
+    let start = output.find(MARKER)? + MARKER.len();
+    match output[start..].strip_prefix('\'') {
+        // Message itself may contain quotes, so closing one is the one followed by location
+        Some(rest) => rest
+            .match_indices("', ")
+            .map(|(end, _)| end)
+            .find(|&end| is_location(rest[end + 3..].lines().next().unwrap_or_default()))
+            .map(|end| &rest[..end]),
+        None => crate::subprocess_panic(output).map(|(_, message)| message),
+    }
+}
+
+/// Checks that text looks like panic location, `file:line:column`
+fn is_location(text: &str) -> bool {
+    let mut parts = text.rsplitn(3, ':');
+    let is_number = |part: Option<&str>| {
+        part.is_some_and(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    };
+    is_number(parts.next())
+        && is_number(parts.next())
+        && parts.next().is_some_and(|file| !file.is_empty())
+}
+
+/// Asserts that output contains lines with specified texts in specified order,
+/// not necessarily consecutive. Each text can match only one line, and is searched
+/// only after line matched by previous text
+///
+/// ```rust
+/// let output = "first\nnoise\nthe second one\nthird\n";
+/// subprocess_test::assert_subprocess_lines_in_order!(output, ["first", "second", "third"]);
+/// ```
+#[macro_export]
+macro_rules! assert_subprocess_lines_in_order {
+    ($output:expr, $expected:expr $(,)?) => {{
+        let output: &str = ::std::convert::AsRef::as_ref(&$output);
+        let expected: &[&str] = &$expected;
+        if let Err(message) = $crate::assertions::check_lines_in_order(output, expected) {
+            panic!("{message}");
+        }
+    }};
+}
+
+#[doc(hidden)]
+pub fn check_lines_in_order(output: &str, expected: &[&str]) -> Result<(), String> {
+    let mut lines = output.lines();
+    let mut previous = None;
+
+    for text in expected {
+        if !lines.any(|line| line.contains(text)) {
+            return Err(match previous {
+                Some(previous) => format!(
+                    "Expected '{text}' after '{previous}' but it was not found. Output:\n{output}"
+                ),
+                None => format!("Expected '{text}' but it was not found. Output:\n{output}"),
+            });
+        }
+        previous = Some(text);
+    }
+
+    Ok(())
+}
+
+/// Asserts that output is equal to expected string, and on mismatch reports each differing line
+/// with special characters escaped, so differences like `\r\n` vs `\n` line endings
+/// or trailing whitespace are clearly visible
+///
+/// ```rust
+/// let output = "first\nsecond\n";
+/// subprocess_test::assert_subprocess_eq_char_diff!(output, "first\nsecond\n");
+/// ```
+#[macro_export]
+macro_rules! assert_subprocess_eq_char_diff {
+    ($output:expr, $expected:expr $(,)?) => {{
+        let output: &str = ::std::convert::AsRef::as_ref(&$output);
+        let expected: &str = ::std::convert::AsRef::as_ref(&$expected);
+        if let Some(diff) = $crate::assertions::char_diff(output, expected) {
+            panic!("Output differs from expected (actual vs expected):\n{diff}");
+        }
+    }};
+}
+
+/// Lists lines which differ between strings, one per line, or returns `None` if strings are equal
+#[doc(hidden)]
+pub fn char_diff(actual: &str, expected: &str) -> Option<String> {
+    if actual == expected {
+        return None;
+    }
+
+    let mut actual_lines = actual.split_inclusive('\n');
+    let mut expected_lines = expected.split_inclusive('\n');
+    let mut diff = Vec::new();
+    for number in 1.. {
+        match (actual_lines.next(), expected_lines.next()) {
+            (None, None) => break,
+            (actual, expected) if actual == expected => {}
+            (actual, expected) => diff.push(format!(
+                "Line {number} differs: {} vs {}",
+                quote_line(actual),
+                quote_line(expected)
+            )),
+        }
+    }
+
+    Some(diff.join("\n"))
+}
+
+fn quote_line(line: Option<&str>) -> String {
+    let Some(line) = line else {
+        return "<missing>".to_owned();
+    };
+    let mut quoted = String::from("'");
+    for c in line.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\x{:02X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+
+    quoted
+}
+
+/// Asserts that output is equal to expected string. On mismatch, failure message
+/// contains line diff with context, where removed expected lines are marked with `-`
+/// and added actual lines with `+`, each prefixed with its line numbers
+///
+/// ```rust
+/// let output = "first\nsecond\n";
+/// subprocess_test::assert_subprocess_output_eq!(output, "first\nsecond\n");
+/// ```
+#[cfg(feature = "diff-output")]
+#[macro_export]
+macro_rules! assert_subprocess_output_eq {
+    ($output:expr, $expected:expr $(,)?) => {{
+        let output: &str = ::std::convert::AsRef::as_ref(&$output);
+        let expected: &str = ::std::convert::AsRef::as_ref(&$expected);
+        if let Some(diff) = $crate::assertions::line_diff(output, expected) {
+            panic!("Output differs from expected (-expected +actual):\n{diff}");
+        }
+    }};
+}
+
+/// Renders unified-style diff of lines with line numbers in both strings,
+/// or returns `None` if strings are equal
+#[cfg(feature = "diff-output")]
+#[doc(hidden)]
+pub fn line_diff(actual: &str, expected: &str) -> Option<String> {
+    use similar::{ChangeTag, TextDiff};
+
+    if actual == expected {
+        return None;
+    }
+
+    let diff = TextDiff::from_lines(expected, actual);
+    let mut rendered = Vec::new();
+    for (index, group) in diff.grouped_ops(3).iter().enumerate() {
+        if index > 0 {
+            rendered.push("...".to_owned());
+        }
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let (sign, old, new) = match change.tag() {
+                    ChangeTag::Equal => (' ', change.old_index(), change.new_index()),
+                    ChangeTag::Delete => ('-', change.old_index(), None),
+                    ChangeTag::Insert => ('+', None, change.new_index()),
+                };
+                let number = |index: Option<usize>| {
+                    index.map_or_else(String::new, |index| (index + 1).to_string())
+                };
+                let line = change.value();
+                let line = line.strip_suffix('\n').unwrap_or(line);
+                let newline = if change.missing_newline() {
+                    " (no newline at end)"
+                } else {
+                    ""
+                };
+                rendered.push(format!(
+                    "{sign}{:>4} {:>4} | {line}{newline}",
+                    number(old),
+                    number(new)
+                ));
+            }
+        }
+    }
+
+    Some(rendered.join("\n"))
+}
+
+/// Asserts that several fields of [`SubprocessResult`](crate::SubprocessResult)
+/// are equal to expected values, using struct-like syntax.
+/// All mismatched fields are reported together
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test]
+///     fn matched() {
+///         println!("expected");
+///     }
+///     verify |result| {
+///         subprocess_test::matches_result!(result, { success: true, output: "expected\n" });
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! matches_result {
+    ($result:expr, { $($field:ident : $expected:expr),+ $(,)? }) => {{
+        let result: &$crate::SubprocessResult = &$result;
+        let mut mismatches = ::std::string::String::new();
+        $(
+            let expected = $expected;
+            if result.$field != expected {
+                mismatches.push_str(&::std::format!(
+                    "\n  {}: expected {:?} but found {:?}",
+                    ::std::stringify!($field),
+                    expected,
+                    result.$field,
+                ));
+            }
+        )+
+        if !mismatches.is_empty() {
+            panic!("Subprocess result doesn't match:{mismatches}\nOutput:\n{}", result.output);
+        }
+    }};
+}
+
+/// Asserts that value selected from XML output by simple XPath-like query equals `expected`.
+/// Requires `xml` feature.
+///
+/// Query is absolute path of element names, like `/root/child`, which selects text content
+/// of first matching element, optionally followed by `text()` to select only its own text,
+/// or by `@name` to select its attribute
+///
+/// ```rust
+/// let output = r#"<status kind="final"><code>0</code></status>"#;
+/// subprocess_test::assert_subprocess_output_xml!(output, "/status/code/text()", "0");
+/// subprocess_test::assert_subprocess_output_xml!(output, "/status/@kind", "final");
+/// ```
+#[cfg(feature = "xml")]
+#[macro_export]
+macro_rules! assert_subprocess_output_xml {
+    ($output:expr, $xpath:expr, $expected:expr $(,)?) => {{
+        let output: &str = ::std::convert::AsRef::as_ref(&$output);
+        let xpath: &str = $xpath;
+        let expected: &str = ::std::convert::AsRef::as_ref(&$expected);
+        match $crate::assertions::query_xml(output, xpath) {
+            Ok(Some(actual)) => assert!(
+                actual == expected,
+                "XPath '{xpath}' returned '{actual}' but expected '{expected}'. Full output:\n{output}",
+            ),
+            Ok(None) => panic!(
+                "XPath '{xpath}' matched nothing but expected '{expected}'. Full output:\n{output}",
+            ),
+            Err(message) => panic!("{message}. Full output:\n{output}"),
+        }
+    }};
+}
+
+/// Selects value from XML document by simple XPath-like query, see `assert_subprocess_output_xml!`
+#[cfg(feature = "xml")]
+#[doc(hidden)]
+pub fn query_xml(output: &str, xpath: &str) -> Result<Option<String>, String> {
+    use quick_xml::escape::resolve_predefined_entity;
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::{Reader, XmlVersion};
+
+    enum Select<'a> {
+        Content,
+        Text,
+        Attribute(&'a str),
+    }
+
+    let invalid = || format!("Unsupported XPath '{xpath}'");
+    let mut steps: Vec<_> = xpath
+        .strip_prefix('/')
+        .ok_or_else(invalid)?
+        .split('/')
+        .collect();
+    let select = match steps.last() {
+        Some(&"text()") => Select::Text,
+        Some(step) => match step.strip_prefix('@') {
+            Some(name) => Select::Attribute(name),
+            None => Select::Content,
+        },
+        None => unreachable!("split always yields at least one item"),
+    };
+    if !matches!(select, Select::Content) {
+        steps.pop();
+    }
+    if steps
+        .iter()
+        .any(|step| step.is_empty() || step.contains(['(', ')', '@', '[', '*']))
+    {
+        return Err(invalid());
+    }
+
+    let attribute = |element: &BytesStart, name: &str| -> Result<Option<String>, String> {
+        for attr in element.attributes() {
+            let attr = attr.map_err(|err| format!("Failed to parse XML: {err}"))?;
+            if attr.key.as_ref() == name {
+                let value = attr
+                    .normalized_value(XmlVersion::Implicit1_0)
+                    .map_err(|err| format!("Failed to parse XML: {err}"))?;
+                return Ok(Some(value.into_owned()));
+            }
+        }
+        Ok(None)
+    };
+
+    let mut reader = Reader::from_str(output);
+    // Names of currently open elements, starting from root
+    let mut path = Vec::new();
+    // Text of selected element, collected until its end
+    let mut collected: Option<String> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|err| format!("Failed to parse XML: {err}"))?;
+        // Only text directly inside selected element is collected, unless its whole content is requested
+        let collecting =
+            collected.is_some() && (path.len() == steps.len() || matches!(select, Select::Content));
+        let text = match event {
+            Event::Start(element) => {
+                path.push(element.name().as_ref().to_owned());
+                if collected.is_none() && path == steps {
+                    match select {
+                        Select::Attribute(name) => return attribute(&element, name),
+                        Select::Content | Select::Text => collected = Some(String::new()),
+                    }
+                }
+                continue;
+            }
+            Event::Empty(element) => {
+                if collected.is_none()
+                    && path.len() + 1 == steps.len()
+                    && path.iter().zip(&steps).all(|(name, step)| name == step)
+                    && element.name().as_ref() == steps[path.len()]
+                {
+                    return match select {
+                        Select::Attribute(name) => attribute(&element, name),
+                        Select::Content | Select::Text => Ok(Some(String::new())),
+                    };
+                }
+                continue;
+            }
+            Event::End(_) => {
+                if collected.is_some() && path.len() == steps.len() {
+                    return Ok(collected);
+                }
+                path.pop();
+                continue;
+            }
+            Event::Text(text) if collecting => text.xml10_content().into_owned(),
+            Event::CData(text) if collecting => text.xml10_content().into_owned(),
+            Event::GeneralRef(reference) if collecting => {
+                let unknown = || format!("Unknown XML entity '&{};'", reference.as_ref());
+                if reference.is_char_ref() {
+                    reference
+                        .resolve_char_ref()
+                        .map_err(|err| format!("Failed to parse XML: {err}"))?
+                        .ok_or_else(unknown)?
+                        .to_string()
+                } else {
+                    resolve_predefined_entity(reference.as_ref())
+                        .ok_or_else(unknown)?
+                        .to_owned()
+                }
+            }
+            Event::Eof => return Ok(None),
+            _ => continue,
+        };
+        if let Some(collected) = &mut collected {
+            collected.push_str(&text);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Runtimes for `async fn` test bodies, see `runtime` parameter
+use std::future::Future;
+
+/// Async runtime which runs test body in subprocess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AsyncRuntime {
+    Tokio,
+    AsyncStd,
+}
+
+impl AsyncRuntime {
+    pub(crate) fn parse(name: &str) -> Self {
+        match name {
+            "tokio" => Self::Tokio,
+            "async-std" => Self::AsyncStd,
+            _ => panic!("Unknown async runtime {name:?}, expected \"tokio\" or \"async-std\""),
+        }
+    }
+
+    /// Runs future to completion on new runtime instance
+    pub(crate) fn block_on<F: Future>(self, future: F) -> F::Output {
+        match self {
+            Self::Tokio => block_on_tokio(future),
+            Self::AsyncStd => block_on_async_std(future),
+        }
+    }
+}
+
+/// Uses multi-threaded runtime with all drivers enabled by Tokio features in use
+#[cfg(feature = "tokio")]
+fn block_on_tokio<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime for async test body")
+        .block_on(future)
+}
+
+#[cfg(not(feature = "tokio"))]
+fn block_on_tokio<F: Future>(_future: F) -> F::Output {
+    panic!(
+        "`async fn` test bodies with \"tokio\" runtime require `tokio` feature of `subprocess-test` crate"
+    )
+}
+
+#[cfg(feature = "async-std")]
+fn block_on_async_std<F: Future>(future: F) -> F::Output {
+    async_std::task::block_on(future)
+}
+
+#[cfg(not(feature = "async-std"))]
+fn block_on_async_std<F: Future>(_future: F) -> F::Output {
+    panic!(
+        "`async fn` test bodies with \"async-std\" runtime require `async-std` feature of `subprocess-test` crate"
+    )
+}
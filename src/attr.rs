@@ -0,0 +1,18 @@
+//! Attribute form of [`subprocess_test!`](crate::subprocess_test!).
+//!
+//! Attribute macros can't be exported from crate root alongside `subprocess_test!`
+//! under same name, so they're imported from this module
+pub use subprocess_test_macros::{subprocess_test, subprocess_verify};
+
+#[doc(hidden)]
+pub use crate::__subprocess_test_attr as __subprocess_test;
+
+/// Target of `#[subprocess_test]` expansion. Procedural macro can't refer to its crate
+/// through `$crate`, so it calls this macro, which does
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __subprocess_test_attr {
+    ($($tokens:tt)*) => {
+        $crate::subprocess_test! { $($tokens)* }
+    };
+}
@@ -0,0 +1,103 @@
+//! Duration baselines persisted between test runs, used to detect performance regressions
+use std::env::current_exe;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{UPDATE_BASELINE_ENV_VAR_NAME, env_flag};
+
+/// Durations of last several successful runs of single test
+pub(crate) struct Baseline {
+    path: PathBuf,
+    durations_ms: Vec<u64>,
+}
+
+impl Baseline {
+    /// Loads baseline for test. Missing or malformed baseline file is treated as empty baseline
+    pub(crate) fn load(full_test_name: &str) -> Self {
+        let path = baseline_dir().join(format!("{}.json", full_test_name.replace("::", ".")));
+        let durations_ms = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| parse_durations(&text))
+            .unwrap_or_default();
+
+        Self { path, durations_ms }
+    }
+    /// Compares duration to baseline's mean, and prints warning if it's slower
+    /// than `threshold_pct` percents
+    pub(crate) fn check(&self, duration: Duration, threshold_pct: u64) {
+        if self.durations_ms.is_empty() || env_flag(UPDATE_BASELINE_ENV_VAR_NAME) {
+            return;
+        }
+        let current = duration.as_millis() as u64;
+        let baseline = self.mean_ms().max(1);
+        if current > baseline {
+            let pct = (current - baseline) * 100 / baseline;
+            if pct > threshold_pct {
+                eprintln!(
+                    "Test took {current}ms, {pct}% slower than baseline of {baseline}ms ({})",
+                    self.path.display()
+                );
+            }
+        }
+    }
+    /// Records duration of successful run and saves baseline, keeping only last `runs` durations.
+    /// When baseline update is forced, previous durations are discarded
+    pub(crate) fn record(mut self, duration: Duration, runs: usize) {
+        if env_flag(UPDATE_BASELINE_ENV_VAR_NAME) {
+            self.durations_ms.clear();
+        }
+        self.durations_ms.push(duration.as_millis() as u64);
+        let excess = self.durations_ms.len().saturating_sub(runs.max(1));
+        self.durations_ms.drain(..excess);
+
+        fs::create_dir_all(baseline_dir()).expect("Failed to create baseline directory");
+        fs::write(&self.path, self.to_json()).expect("Failed to write baseline file");
+    }
+
+    fn mean_ms(&self) -> u64 {
+        self.durations_ms.iter().sum::<u64>() / self.durations_ms.len().max(1) as u64
+    }
+
+    fn to_json(&self) -> String {
+        let mut durations = String::new();
+        for (index, duration) in self.durations_ms.iter().enumerate() {
+            if index != 0 {
+                durations.push_str(", ");
+            }
+            write!(durations, "{duration}").unwrap();
+        }
+
+        format!(
+            "{{\n  \"durations_ms\": [{durations}],\n  \"min_duration_ms\": {},\n  \"max_duration_ms\": {},\n  \"mean_duration_ms\": {}\n}}\n",
+            self.durations_ms.iter().min().unwrap_or(&0),
+            self.durations_ms.iter().max().unwrap_or(&0),
+            self.mean_ms(),
+        )
+    }
+}
+
+/// Baselines are stored in `subprocess-test-baseline` inside Cargo's target directory.
+/// Test executables reside in `<target>/<profile>/deps`, so target is found relative to them
+fn baseline_dir() -> PathBuf {
+    let exe_path = current_exe().expect("Test executable path not found");
+    let target_dir = exe_path
+        .ancestors()
+        .nth(3)
+        .expect("Test executable is expected to reside in Cargo's target directory");
+
+    target_dir.join("subprocess-test-baseline")
+}
+
+fn parse_durations(text: &str) -> Option<Vec<u64>> {
+    let (_, rest) = text.split_once("\"durations_ms\"")?;
+    let (_, rest) = rest.split_once('[')?;
+    let (list, _) = rest.split_once(']')?;
+
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| item.parse().ok())
+        .collect()
+}
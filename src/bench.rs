@@ -0,0 +1,122 @@
+//! Support for benchmarks isolated in subprocesses, see [`subprocess_test_bench!`](crate::subprocess_test_bench)
+use std::env::{args_os, var};
+use std::hint::black_box;
+use std::process::{Command, Stdio, exit};
+use std::time::{Duration, Instant};
+
+pub use criterion::Criterion;
+
+const BENCH_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_BENCH__";
+const ELAPSED_PREFIX: &str = "__SUBPROCESS_TEST_BENCH_ELAPSED__=";
+
+/// Generates criterion benchmark functions whose measured routines run in subprocess.
+///
+/// Each generated function has signature `fn(&mut Criterion)` and can be passed
+/// to `criterion::criterion_group!` as usual. Benchmark body receives [`Bencher`]
+/// provided by this crate, which mimics criterion's one. Each batch of iterations
+/// requested by criterion is executed in freshly spawned subprocess, and its timing
+/// is reported back to criterion through `Bencher::iter_custom`.
+///
+/// ```rust,no_run
+/// # fn expensive_fn() -> u64 { 42 }
+/// subprocess_test::subprocess_test_bench! {
+///     fn bench_foo(b: &mut Bencher) {
+///         b.iter(|| expensive_fn());
+///     }
+/// }
+///
+/// criterion::criterion_group!(benches, bench_foo);
+/// criterion::criterion_main!(benches);
+/// ```
+#[macro_export]
+macro_rules! subprocess_test_bench {
+    (
+        $(
+            $(#[$attrs:meta])*
+            fn $bench_name:ident ($bencher:ident : &mut Bencher) $bench_block:block
+        )*
+    ) => {
+        $(
+            $(#[$attrs])*
+            pub fn $bench_name(c: &mut $crate::bench::Criterion) {
+                $crate::bench::run_subprocess_bench(
+                    c,
+                    stringify!($bench_name),
+                    |$bencher: &mut $crate::bench::Bencher| $bench_block,
+                );
+            }
+        )*
+    };
+}
+
+/// Timer for benchmark routine, passed to benchmark body in subprocess
+pub struct Bencher {
+    iters: u64,
+    elapsed: Duration,
+}
+
+impl Bencher {
+    /// Runs routine requested number of times and measures total time taken
+    pub fn iter<O>(&mut self, mut routine: impl FnMut() -> O) {
+        let started_at = Instant::now();
+        for _ in 0..self.iters {
+            black_box(routine());
+        }
+        self.elapsed = started_at.elapsed();
+    }
+}
+
+#[doc(hidden)]
+pub fn run_subprocess_bench(
+    c: &mut Criterion,
+    bench_name: &str,
+    bench_fn: impl FnOnce(&mut Bencher),
+) {
+    // In subprocess mode, run only requested benchmark and report its timing
+    if let Ok(request) = var(BENCH_ENV_VAR_NAME) {
+        let (requested_name, iters) = request
+            .split_once(':')
+            .expect("Benchmark request must be in form `name:iterations`");
+        if requested_name == bench_name {
+            let mut bencher = Bencher {
+                iters: iters.parse().expect("Invalid number of iterations"),
+                elapsed: Duration::ZERO,
+            };
+            bench_fn(&mut bencher);
+            println!("{ELAPSED_PREFIX}{}", bencher.elapsed.as_nanos());
+            exit(0);
+        }
+        return;
+    }
+
+    c.bench_function(bench_name, |b| {
+        b.iter_custom(|iters| spawn_bench(bench_name, iters))
+    });
+}
+
+fn spawn_bench(bench_name: &str, iters: u64) -> Duration {
+    let exe_path = args_os()
+        .next()
+        .expect("Benchmark executable path not found");
+    let output = Command::new(exe_path)
+        .args(["--bench", bench_name])
+        .env(BENCH_ENV_VAR_NAME, format!("{bench_name}:{iters}"))
+        .stdin(Stdio::null())
+        .output()
+        .expect("Failed to execute benchmark as subprocess");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "Benchmark {bench_name} subprocess failed with {}:\n{stdout}{}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr),
+    );
+
+    let nanos = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix(ELAPSED_PREFIX))
+        .unwrap_or_else(|| panic!("Benchmark {bench_name} subprocess didn't report timing"));
+
+    Duration::from_nanos(nanos.parse().expect("Invalid benchmark timing"))
+}
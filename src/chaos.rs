@@ -0,0 +1,117 @@
+//! Fault injection into subprocess test body, see `inject_fault` parameter
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use nix::sys::signal::{SigHandler, Signal, kill};
+use nix::unistd::Pid;
+
+/// Fault injected into subprocess test body
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Fault {
+    /// `malloc` returns null after specified number of successful calls
+    MallocFailAfter(u64),
+    /// Signal is sent to subprocess after specified delay
+    Signal(Signal, Duration),
+}
+
+impl Fault {
+    pub(crate) fn parse(spec: &str) -> Self {
+        let (name, value) = match spec.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (spec.trim(), None),
+        };
+        let unknown = || -> ! {
+            panic!(
+                "Unknown fault {spec:?}, expected \"malloc_fail_after = N\", \"sigsegv\", \"sigfpe\", \
+                \"sigsegv_after_ms = N\" or \"sigfpe_after_ms = N\""
+            )
+        };
+        let number = || {
+            value
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| unknown())
+        };
+        let after_ms = || Duration::from_millis(number());
+
+        match (name, value) {
+            ("malloc_fail_after", Some(_)) => Self::MallocFailAfter(number()),
+            ("sigsegv", None) => Self::Signal(Signal::SIGSEGV, Duration::ZERO),
+            ("sigfpe", None) => Self::Signal(Signal::SIGFPE, Duration::ZERO),
+            ("sigsegv_after_ms", Some(_)) => Self::Signal(Signal::SIGSEGV, after_ms()),
+            ("sigfpe_after_ms", Some(_)) => Self::Signal(Signal::SIGFPE, after_ms()),
+            _ => unknown(),
+        }
+    }
+    /// Prepares subprocess command, for faults which must be set up before subprocess starts
+    pub(crate) fn prepare(&self, command: &mut Command) {
+        if let Self::MallocFailAfter(_) = self {
+            preload_malloc_fail_library(command);
+        }
+    }
+    /// Injects fault into currently running subprocess test body.
+    /// Returned guard disarms fault when dropped, so test harness isn't affected by it
+    pub(crate) fn arm(self) -> FaultGuard {
+        match self {
+            Self::MallocFailAfter(calls) => set_malloc_fail_after(calls as i64),
+            Self::Signal(signal, delay) => {
+                thread::spawn(move || {
+                    thread::sleep(delay);
+                    // Standard library handles SIGSEGV to detect stack overflows, and ignores
+                    // signals which don't come from guard page, so default action is restored
+                    if signal == Signal::SIGSEGV {
+                        // SAFETY: default disposition doesn't involve any handler code
+                        let _ = unsafe { nix::sys::signal::signal(signal, SigHandler::SigDfl) };
+                    }
+                    let _ = kill(Pid::this(), signal);
+                });
+            }
+        }
+        FaultGuard(self)
+    }
+}
+
+pub(crate) struct FaultGuard(Fault);
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        if let Fault::MallocFailAfter(_) = self.0 {
+            set_malloc_fail_after(-1);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn preload_malloc_fail_library(command: &mut Command) {
+    const LIBRARY: &str = env!("SUBPROCESS_TEST_MALLOC_FAIL_LIBRARY");
+
+    let preload = match std::env::var("LD_PRELOAD") {
+        Ok(inherited) if !inherited.is_empty() => format!("{LIBRARY}:{inherited}"),
+        _ => LIBRARY.to_owned(),
+    };
+    command.env("LD_PRELOAD", preload);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn preload_malloc_fail_library(_command: &mut Command) {
+    panic!("malloc_fail_after fault is supported only on Linux");
+}
+
+/// Sets number of `malloc` calls which still succeed, negative number disables failures
+#[cfg(target_os = "linux")]
+fn set_malloc_fail_after(calls: i64) {
+    use nix::libc::{RTLD_DEFAULT, c_long, dlsym};
+
+    // SAFETY: symbol name is valid C string
+    let symbol = unsafe { dlsym(RTLD_DEFAULT, c"subprocess_test_malloc_fail_after".as_ptr()) };
+    assert!(
+        !symbol.is_null(),
+        "Malloc failure library isn't preloaded into subprocess"
+    );
+    // SAFETY: symbol is function defined in `malloc_fail.c` with this exact signature
+    let set_fail_after: extern "C" fn(c_long) = unsafe { std::mem::transmute(symbol) };
+    set_fail_after(calls as c_long);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_malloc_fail_after(_calls: i64) {}
@@ -0,0 +1,29 @@
+//! Termination of subprocess when test runner dies, see `windows-cleanup` feature.
+//! Otherwise subprocess of runner killed by CI timeout would outlive it as an orphan.
+//! On Windows, subprocess is put into job object instead, see `job` module
+use std::process::Command;
+
+/// Makes kernel send `SIGKILL` to subprocess once parent dies. Set in forked child
+/// right before `exec`.
+///
+/// Linux delivers signal when thread which spawned subprocess exits rather than whole process,
+/// which is fine since subprocess is waited on by that same thread
+pub(crate) fn kill_on_parent_death(command: &mut Command) {
+    use nix::sys::{prctl::set_pdeathsig, signal::Signal};
+    use nix::unistd::{Pid, getppid};
+    use std::os::unix::process::CommandExt;
+
+    let parent = Pid::this();
+    // SAFETY: closure only performs `prctl` and `getppid` syscalls, which are
+    // async-signal-safe, and doesn't allocate or touch any locks
+    unsafe {
+        command.pre_exec(move || {
+            set_pdeathsig(Signal::SIGKILL)?;
+            // Parent might have died before signal was requested
+            if getppid() != parent {
+                return Err(std::io::ErrorKind::Other.into());
+            }
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,108 @@
+//! Default test parameters from `[package.metadata.subprocess-test]`
+//! or `[workspace.metadata.subprocess-test]` section of `Cargo.toml`
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use toml::Table;
+
+/// Name of metadata table which holds defaults
+const METADATA_KEY: &str = "subprocess-test";
+
+/// Defaults for test parameters, read once per test executable.
+/// Parameters which aren't specified in manifest are `None`,
+/// so hardcoded defaults are used for them
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GlobalConfig {
+    pub env_var_name: Option<String>,
+    pub output_boundary: Option<String>,
+}
+
+impl GlobalConfig {
+    /// Returns defaults for crate whose manifest contents and directory are specified.
+    /// Manifest is parsed on first call only; test executable belongs to single crate,
+    /// so subsequent calls get same arguments anyway
+    pub fn get(manifest: &str, manifest_dir: &str) -> &'static Self {
+        static CONFIG: OnceLock<GlobalConfig> = OnceLock::new();
+
+        CONFIG.get_or_init(|| Self::load(manifest, Path::new(manifest_dir)))
+    }
+    /// Reads defaults from package manifest first, then from manifest of workspace root,
+    /// which is searched in parent directories of package. Package settings take precedence
+    /// over workspace ones
+    pub fn load(manifest: &str, manifest_dir: &Path) -> Self {
+        let package = parse_manifest(manifest, "package manifest");
+        // Package may be workspace root itself
+        let workspace = if package.contains_key("workspace") {
+            Some(package.clone())
+        } else {
+            find_workspace_manifest(manifest_dir)
+        };
+
+        let mut config = Self::from_metadata(&package, "package");
+        if let Some(workspace) = workspace {
+            let defaults = Self::from_metadata(&workspace, "workspace");
+            config.env_var_name = config.env_var_name.or(defaults.env_var_name);
+            config.output_boundary = config.output_boundary.or(defaults.output_boundary);
+        }
+        config
+    }
+    /// Extracts settings from `[<section>.metadata.subprocess-test]` table
+    fn from_metadata(manifest: &Table, section: &str) -> Self {
+        let Some(metadata) = manifest
+            .get(section)
+            .and_then(|section| section.get("metadata"))
+            .and_then(|metadata| metadata.get(METADATA_KEY))
+        else {
+            return Self::default();
+        };
+        let string_value = |key: &str| {
+            metadata.get(key).map(|value| {
+                value
+                    .as_str()
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Value of `{key}` in [{section}.metadata.{METADATA_KEY}] \
+                            section of Cargo.toml must be string"
+                        )
+                    })
+                    .to_owned()
+            })
+        };
+
+        Self {
+            env_var_name: string_value("env_var_name"),
+            output_boundary: string_value("output_boundary"),
+        }
+    }
+}
+
+fn parse_manifest(manifest: &str, what: &str) -> Table {
+    manifest
+        .parse()
+        .unwrap_or_else(|e| panic!("Failed to parse {what}: {e}"))
+}
+
+/// Looks for closest parent directory whose `Cargo.toml` has `[workspace]` section
+fn find_workspace_manifest(manifest_dir: &Path) -> Option<Table> {
+    manifest_dir
+        .ancestors()
+        .skip(1)
+        .find_map(workspace_manifest)
+}
+
+/// Returns root directory of workspace which package belongs to,
+/// or package directory itself if it isn't part of any workspace
+pub(crate) fn workspace_root(manifest_dir: &Path) -> &Path {
+    manifest_dir
+        .ancestors()
+        .find(|dir| workspace_manifest(dir).is_some())
+        .unwrap_or(manifest_dir)
+}
+
+/// Reads `Cargo.toml` in specified directory, if it has `[workspace]` section
+fn workspace_manifest(dir: &Path) -> Option<Table> {
+    let manifest = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    let manifest = parse_manifest(&manifest, "workspace manifest");
+    manifest.contains_key("workspace").then_some(manifest)
+}
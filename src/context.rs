@@ -0,0 +1,130 @@
+//! Values passed from parent into test body, see `fn test(ctx: Type = value)` syntax
+use std::env::{var, var_os};
+use std::process::Command;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::SubprocessTestConfig;
+
+const CONTEXT_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_CONTEXT__";
+
+impl SubprocessTestConfig {
+    /// Creates context in parent process and passes it to subprocess serialized as JSON.
+    /// Context isn't created in subprocess mode, since it's received from parent
+    pub fn context<C: Serialize>(mut self, context_fn: impl FnOnce() -> C) -> Self {
+        if var_os(&*self.env_var_name).is_none() {
+            let context = serde_json::to_string(&context_fn())
+                .unwrap_or_else(|err| panic!("Failed to serialize test context: {err}"));
+            self.context = Some(context);
+        }
+        self
+    }
+}
+
+pub(crate) fn pass(command: &mut Command, context: &str) {
+    command.env(CONTEXT_ENV_VAR_NAME, context);
+}
+
+/// Receives context passed by parent process
+#[doc(hidden)]
+pub fn load<C: DeserializeOwned>() -> C {
+    let context = var(CONTEXT_ENV_VAR_NAME)
+        .expect("Test context is passed only to subprocess, as part of subprocess test run");
+    serde_json::from_str(&context)
+        .unwrap_or_else(|err| panic!("Failed to deserialize test context: {err}"))
+}
+
+/// Defines subprocess test which is run once per each parameter value.
+///
+/// Test function takes single parameter, and `cases` list after test specifies name and value
+/// for each case. Macro expands into module named after test, with one subprocess test
+/// per case named after it, like `my_test::small`. Each case passes its value to subprocess
+/// same way as context parameter, so parameter type must implement both `Serialize`
+/// and `Deserialize`.
+///
+/// Optional `verify` block takes parameter value along with `success` and `output`.
+/// Value expression is evaluated again for it, so it should be cheap and deterministic.
+///
+/// ```rust
+/// # fn parse(input: &str) -> usize { input.len() }
+/// subprocess_test::subprocess_test_parametrize! {
+///     #[test]
+///     fn parses_input(input: String) {
+///         print!("{}", parse(&input));
+///     }
+///     verify |input, success, output| {
+///         assert!(success);
+///         assert_eq!(output, input.len().to_string());
+///     }
+///     cases {
+///         empty: String::new(),
+///         short: "abc".to_owned(),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subprocess_test_parametrize {
+    (
+        $(#[doc = $doc_lit:literal])*
+        #[test $($test_params:tt)?]
+        $(#[$attrs:meta])*
+        fn $test_name:ident ($param_name:ident : $param_ty:ty) $test_block:block
+        $(verify |$value_param:ident, $success_param:ident, $output_param:ident| $verify_block:block)?
+        cases { $($cases:tt)+ }
+    ) => {
+        mod $test_name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::subprocess_test_parametrize! {
+                @cases
+                [$(#[doc = $doc_lit])* #[test $($test_params)?] $(#[$attrs])*]
+                [$param_name: $param_ty]
+                $test_block
+                [$($value_param $success_param $output_param $verify_block)?]
+                $($cases)+
+            }
+        }
+    };
+    (
+        @cases [$($header:tt)*] [$param_name:ident: $param_ty:ty] $test_block:block
+        [$value_param:ident $success_param:ident $output_param:ident $verify_block:block]
+        $case_name:ident : $case_value:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::subprocess_test! {
+            $($header)*
+            fn $case_name($param_name: $param_ty = $case_value) $test_block
+            verify |result| {
+                let $value_param: $param_ty = $case_value;
+                let $crate::SubprocessResult {
+                    success: $success_param,
+                    output: $output_param,
+                    ..
+                } = result;
+                $verify_block
+            }
+        }
+        $crate::subprocess_test_parametrize! {
+            @cases [$($header)*] [$param_name: $param_ty] $test_block
+            [$value_param $success_param $output_param $verify_block]
+            $($($rest)*)?
+        }
+    };
+    (
+        @cases [$($header:tt)*] [$param_name:ident: $param_ty:ty] $test_block:block []
+        $case_name:ident : $case_value:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::subprocess_test! {
+            $($header)*
+            fn $case_name($param_name: $param_ty = $case_value) $test_block
+        }
+        $crate::subprocess_test_parametrize! {
+            @cases [$($header)*] [$param_name: $param_ty] $test_block []
+            $($($rest)*)?
+        }
+    };
+    (
+        @cases [$($header:tt)*] [$param_name:ident: $param_ty:ty] $test_block:block [$($verify:tt)*]
+    ) => {};
+}
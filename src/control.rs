@@ -0,0 +1,63 @@
+//! Stopping subprocess from parent while it runs, see `control` block of `subprocess_test!`
+//!
+//! Parent creates temporary directory and passes path of stop flag file inside it
+//! to subprocess through environment variable. After test body returns, subprocess
+//! waits until flag file appears
+use std::env::var_os;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+pub(crate) const CONTROL_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_CONTROL__";
+
+/// How often subprocess checks for stop flag
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Function run in parent while subprocess runs
+pub(crate) type ControlFn = Box<dyn Fn(&SubprocessControl)>;
+
+/// Parent's handle of running subprocess, passed to `control` block
+pub struct SubprocessControl {
+    dir: TempDir,
+    pid: u32,
+}
+
+impl SubprocessControl {
+    pub(crate) fn new() -> Self {
+        let dir = TempDir::new().expect("Failed to create temporary directory for control channel");
+        Self { dir, pid: 0 }
+    }
+
+    pub(crate) fn flag_path(&self) -> PathBuf {
+        self.dir.path().join("stop")
+    }
+
+    pub(crate) fn set_pid(&mut self, pid: u32) {
+        self.pid = pid;
+    }
+    /// ID of subprocess
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+    /// Lets subprocess exit once its test body returns. Called automatically
+    /// after `control` block finishes, so calling it explicitly is needed only
+    /// to stop subprocess before end of block
+    pub fn stop(&self) {
+        let path = self.flag_path();
+        File::create(&path)
+            .unwrap_or_else(|err| panic!("Failed to create stop flag {}: {err}", path.display()));
+    }
+}
+
+/// Waits until parent stops subprocess, if it was started with control channel
+pub(crate) fn wait_for_stop() {
+    let Some(path) = var_os(CONTROL_ENV_VAR_NAME) else {
+        return;
+    };
+    while !Path::new(&path).exists() {
+        sleep(POLL_INTERVAL);
+    }
+}
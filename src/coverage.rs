@@ -0,0 +1,163 @@
+//! Propagation of LLVM coverage profile path to subprocess, and merging of subprocess
+//! profiles back into profile of current process, see `llvm-cov` feature
+use std::env::var_os;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PROFILE_FILE_ENV_VAR_NAME: &str = "LLVM_PROFILE_FILE";
+
+/// If current process writes coverage profile, i.e. `LLVM_PROFILE_FILE` is set,
+/// passes unique profile path to subprocess, so its profile doesn't overwrite
+/// or get overwritten by profile of current process
+pub(crate) fn prepare(command: &mut Command, test_name: &str) {
+    if let Some(profile) = var_os(PROFILE_FILE_ENV_VAR_NAME) {
+        command.env(
+            PROFILE_FILE_ENV_VAR_NAME,
+            subprocess_profile(Path::new(&profile), test_name),
+        );
+    }
+}
+
+/// Merges raw profiles written by subprocess into `<stem>-subprocesses.profdata` next to
+/// profile of current process, with `llvm-profdata merge`. Merged profile accumulates
+/// all subprocesses of current process. Raw profiles are kept, since tools like
+/// `cargo llvm-cov` collect them from profile directory on their own.
+///
+/// Best effort, so it's skipped if `llvm-profdata` isn't found, and failures are only reported
+pub(crate) fn merge(command: &Command, llvm_profdata: Option<PathBuf>) {
+    // Merges of concurrently run tests would overwrite each other's results
+    static MERGE_LOCK: Mutex<()> = Mutex::new(());
+
+    let (Some(profile), Some(llvm_profdata)) = (var_os(PROFILE_FILE_ENV_VAR_NAME), llvm_profdata)
+    else {
+        return;
+    };
+    let Some(Some(subprocess_profile)) = command
+        .get_envs()
+        .find(|(name, _)| *name == PROFILE_FILE_ENV_VAR_NAME)
+        .map(|(_, value)| value)
+    else {
+        return;
+    };
+    let subprocess_profile = Path::new(subprocess_profile);
+    let pattern = subprocess_profile
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dir = subprocess_profile
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut raw_profiles: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| matches_pattern(&entry.file_name().to_string_lossy(), &pattern))
+        .map(|entry| entry.path())
+        .collect();
+    if raw_profiles.is_empty() {
+        return;
+    }
+    raw_profiles.sort();
+
+    let profile = Path::new(&profile);
+    let stem = profile
+        .file_stem()
+        .map_or_else(|| "default".into(), |stem| stem.to_string_lossy());
+    let stem = literal_parts(&stem).concat();
+    let stem = stem.trim_end_matches(['_', '-']);
+    let merged = profile.with_file_name(format!("{stem}-subprocesses.profdata"));
+    let merged_tmp = merged.with_extension(format!("profdata.{}.tmp", process::id()));
+
+    let _lock = MERGE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut merge = Command::new(llvm_profdata);
+    merge.args(["merge", "-sparse"]);
+    if merged.is_file() {
+        merge.arg(&merged);
+    }
+    merge.args(&raw_profiles).arg("-o").arg(&merged_tmp);
+    let status = merge.stdin(Stdio::null()).stdout(Stdio::null()).status();
+    match status {
+        Ok(status) if status.success() => {
+            if let Err(err) = fs::rename(&merged_tmp, &merged) {
+                eprintln!("Failed to write merged profile {}: {err}", merged.display());
+            }
+        }
+        Ok(status) => eprintln!("Failed to merge subprocess profiles: llvm-profdata {status}"),
+        Err(err) => eprintln!("Failed to merge subprocess profiles: {err}"),
+    }
+    let _ = fs::remove_file(&merged_tmp);
+}
+
+/// Literal parts of profile file name pattern, i.e. parts between specifiers like `%p`,
+/// `%m` or `%4m`, which runtime replaces with values
+fn literal_parts(pattern: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = pattern;
+    while let Some(at) = rest.find('%') {
+        parts.push(&rest[..at]);
+        let specifier = rest[at + 1..].trim_start_matches(|ch: char| ch.is_ascii_digit());
+        rest = specifier
+            .char_indices()
+            .nth(1)
+            .map_or("", |(index, _)| &specifier[index..]);
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Checks that file name matches profile file name pattern, with specifiers replaced by
+/// any text
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let parts = literal_parts(pattern);
+    let (first, rest) = parts.split_first().expect("At least one part is returned");
+    let Some(mut name) = name.strip_prefix(first) else {
+        return false;
+    };
+    let Some((last, middle)) = rest.split_last() else {
+        // No specifiers at all
+        return name.is_empty();
+    };
+    for part in middle {
+        match name.find(part) {
+            Some(at) => name = &name[at + part.len()..],
+            None => return false,
+        }
+    }
+    name.ends_with(last)
+}
+
+/// Inserts test name and random suffix before extension of profile file name, like
+/// `target/cov/default-tests_nested-1a2b3c.profraw`. Profile ends up in same directory,
+/// so it's picked up by coverage tool along with other profiles. Pattern specifiers
+/// like `%p` and `%m` are left intact
+fn subprocess_profile(profile: &Path, test_name: &str) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let stem = profile
+        .file_stem()
+        .map_or_else(|| "default".into(), |stem| stem.to_string_lossy());
+    let extension = profile
+        .extension()
+        .map_or_else(|| "profraw".into(), |extension| extension.to_string_lossy());
+    let test_name: String = test_name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect();
+    // Same test may be spawned several times, e.g. with retries, so counter is mixed in
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.subsec_nanos());
+    let suffix = format!(
+        "{:x}{:x}{:x}",
+        process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+
+    profile.with_file_name(format!("{stem}-{test_name}-{suffix}.{extension}"))
+}
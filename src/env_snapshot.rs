@@ -0,0 +1,34 @@
+//! Restoration of environment variables changed by test body, see `snapshot_env` parameter
+use std::env::var_os;
+use std::ffi::OsString;
+
+/// Values of environment variables taken before test body runs, restored on drop,
+/// so they're restored even if test body panics. Variables missing at snapshot time
+/// are removed
+pub(crate) struct EnvSnapshot {
+    vars: Vec<(String, Option<OsString>)>,
+}
+
+impl EnvSnapshot {
+    pub(crate) fn take(names: &[String]) -> Self {
+        let vars = names
+            .iter()
+            .map(|name| (name.clone(), var_os(name)))
+            .collect();
+        Self { vars }
+    }
+}
+
+impl Drop for EnvSnapshot {
+    fn drop(&mut self) {
+        for (name, value) in &self.vars {
+            // SAFETY: subprocess runs only this test, so no other threads access environment
+            unsafe {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Fake executables available to subprocess through `PATH`, see `fake_binary` parameter
+use std::env::{join_paths, split_paths, var_os};
+use std::ffi::OsString;
+use std::fs;
+
+use tempfile::TempDir;
+
+/// Directory with fake executable, which is removed on drop
+pub(crate) struct FakeBinary(TempDir);
+
+impl FakeBinary {
+    /// Creates executable `name` which prints `output` and exits with `exit_code`.
+    /// Output is stored in separate file next to executable, so it's passed as is,
+    /// without any shell escaping
+    pub(crate) fn new(name: &str, output: &str, exit_code: i32) -> Self {
+        let dir = TempDir::new().expect("Failed to create temporary directory for fake binary");
+        let script_path = dir.path().join(script_name(name));
+        let mut output_path = script_path.clone().into_os_string();
+        output_path.push(".out");
+
+        fs::write(&output_path, output).expect("Failed to write fake binary output");
+        fs::write(&script_path, script(exit_code)).expect("Failed to write fake binary");
+        make_executable(&script_path);
+
+        Self(dir)
+    }
+
+    /// Value of `PATH` variable, with fake binary directory placed before directories
+    /// from `PATH` of current process
+    pub(crate) fn path_env(&self) -> OsString {
+        let inherited = var_os("PATH").unwrap_or_default();
+        let paths = std::iter::once(self.0.path().to_owned()).chain(split_paths(&inherited));
+        join_paths(paths).expect("Fake binary directory path can't be added to PATH")
+    }
+}
+
+#[cfg(not(windows))]
+fn script_name(name: &str) -> String {
+    name.to_owned()
+}
+
+#[cfg(windows)]
+fn script_name(name: &str) -> String {
+    format!("{name}.bat")
+}
+
+#[cfg(not(windows))]
+fn script(exit_code: i32) -> String {
+    format!("#!/bin/sh\ncat \"$0.out\"\nexit {exit_code}\n")
+}
+
+#[cfg(windows)]
+fn script(exit_code: i32) -> String {
+    format!("@type \"%~f0.out\"\r\n@exit /b {exit_code}\r\n")
+}
+
+#[cfg(unix)]
+fn make_executable(path: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .expect("Failed to make fake binary executable");
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &std::path::Path) {}
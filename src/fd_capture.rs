@@ -0,0 +1,70 @@
+//! Capture of output written directly to file descriptors, see `capture_fd` parameter
+use std::env::var;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem::ManuallyDrop;
+use std::os::fd::{FromRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+const CAPTURE_FDS_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_CAPTURE_FDS__";
+
+/// Writes data directly to file descriptor, bypassing Rust's stdout buffering.
+///
+/// Inside subprocess, file descriptor must be listed in `capture_fd` parameters of test,
+/// so parent connects it to captured output. Stdout is flushed before write,
+/// so data is placed in output in order it was written.
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test(capture_fd = 3)]
+///     fn raw_write() {
+///         print!("Printed ");
+///         subprocess_test::subprocess_capture_write!(3, b"and written");
+///     }
+///     verify |result| {
+///         assert_eq!(result.output, "Printed and written");
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subprocess_capture_write {
+    ($fd:expr, $data:expr $(,)?) => {
+        $crate::fd_capture::capture_write($fd, ::std::convert::AsRef::as_ref(&$data))
+    };
+}
+
+#[doc(hidden)]
+pub fn capture_write(fd: RawFd, data: &[u8]) {
+    if let Ok(fds) = var(CAPTURE_FDS_ENV_VAR_NAME) {
+        assert!(
+            fds.split(',').any(|captured| captured == fd.to_string()),
+            "File descriptor {fd} is not captured, add `capture_fd = {fd}` to test parameters"
+        );
+    }
+    io::stdout().flush().expect("Failed to flush stdout");
+    // SAFETY: descriptor isn't closed on drop, so it's only borrowed for the duration of write
+    let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    file.write_all(data)
+        .unwrap_or_else(|err| panic!("Failed to write to file descriptor {fd}: {err}"));
+}
+
+/// Makes specified file descriptors of subprocess refer to its stdout, i.e. captured output
+pub(crate) fn capture_fds(command: &mut Command, fds: &[RawFd]) {
+    let list = fds.iter().map(RawFd::to_string).collect::<Vec<_>>();
+    command.env(CAPTURE_FDS_ENV_VAR_NAME, list.join(","));
+
+    let fds = fds.to_owned();
+    // SAFETY: closure only performs `dup2` syscalls, which are async-signal-safe,
+    // and doesn't allocate or touch any locks
+    unsafe {
+        command.pre_exec(move || {
+            for &fd in &fds {
+                if nix::libc::dup2(nix::libc::STDOUT_FILENO, fd) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,56 @@
+//! Temporary directory shared by parent and subprocess, see `fixture` block
+use std::env::var_os;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+/// Environment variable which passes fixture directory path to subprocess
+pub(crate) const FIXTURE_DIR_ENV_VAR_NAME: &str = "__TEST_FIXTURE_DIR__";
+
+/// Fresh temporary directory created for single subprocess test.
+///
+/// Created in parent process before subprocess is spawned, and its path is passed
+/// to subprocess through environment variable. Directory is removed when fixture is dropped,
+/// i.e. after `verify` block, so its contents can be checked there
+#[derive(Debug)]
+pub struct SubprocessFixture {
+    // Only parent process owns directory
+    _dir: Option<TempDir>,
+    path: PathBuf,
+}
+
+impl SubprocessFixture {
+    /// Creates new temporary directory. Panics if it can't be created
+    pub fn new() -> Self {
+        let dir = TempDir::new().expect("Failed to create fixture directory");
+        Self {
+            path: dir.path().to_owned(),
+            _dir: Some(dir),
+        }
+    }
+    /// Refers to directory created by parent process, without removing it on drop.
+    /// Panics if fixture directory wasn't passed to current process
+    pub fn from_env() -> Self {
+        Self {
+            _dir: None,
+            path: Self::path_from_env(),
+        }
+    }
+    /// Path to fixture directory created by parent process, for use in subprocess.
+    /// Panics if fixture directory wasn't passed to current process
+    pub fn path_from_env() -> PathBuf {
+        var_os(FIXTURE_DIR_ENV_VAR_NAME)
+            .unwrap_or_else(|| panic!("{FIXTURE_DIR_ENV_VAR_NAME} environment variable isn't set"))
+            .into()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Default for SubprocessFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
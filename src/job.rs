@@ -0,0 +1,80 @@
+//! Windows job object holding subprocess, shared by `windows-cleanup` and `rlimit` features.
+//! Process can be assigned to several nested jobs, but all limits are put into single job,
+//! so subprocess ends up in one job no matter which features are enabled.
+//!
+//! Subprocess is assigned only after it's spawned and already running, since `std` can't
+//! create it suspended and resume it afterwards. Limits don't cover what happens before
+//! that, which is just loader startup, since test harness needs far longer to reach test body
+use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+use std::process::Child;
+
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JobObjectExtendedLimitInformation, SetInformationJobObject,
+};
+
+/// Assigns subprocess to job object with all requested limits. Returned handle must be kept
+/// open until subprocess exits, since closing it kills subprocess under `windows-cleanup`.
+///
+/// Failure is fatal only if memory limit is requested, since test would otherwise run
+/// unlimited, and subprocess is killed then. Without it, failure only disables cleanup
+pub(crate) fn assign(child: &mut Child, memory_limit: Option<u64>) -> Option<OwnedHandle> {
+    // SAFETY: structure is plain data, for which all zeroes is valid value
+    let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+    #[cfg(feature = "windows-cleanup")]
+    {
+        use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+    }
+    if let Some(limit_bytes) = memory_limit {
+        use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        info.ProcessMemoryLimit = usize::try_from(limit_bytes).unwrap_or(usize::MAX);
+    }
+    if info.BasicLimitInformation.LimitFlags == 0 {
+        return None;
+    }
+
+    match create_job(child, &info) {
+        Ok(job) => Some(job),
+        Err(err) => {
+            if let Some(limit_bytes) = memory_limit {
+                let _ = child.kill();
+                let _ = child.wait();
+                panic!("Failed to limit subprocess memory to {limit_bytes} bytes: {err}");
+            }
+            eprintln!("Failed to assign subprocess to job object: {err}");
+            None
+        }
+    }
+}
+
+fn create_job(
+    child: &Child,
+    info: &JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+) -> std::io::Result<OwnedHandle> {
+    // SAFETY: null attributes and name create anonymous job with default security
+    let job = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+    if job.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: handle was just created and isn't owned by anything else
+    let job = unsafe { OwnedHandle::from_raw_handle(job) };
+
+    // SAFETY: both handles stay valid during calls, and `info` matches information class
+    let succeeded = unsafe {
+        SetInformationJobObject(
+            job.as_raw_handle(),
+            JobObjectExtendedLimitInformation,
+            std::ptr::from_ref(info).cast(),
+            size_of_val(info) as u32,
+        ) != 0
+            && AssignProcessToJobObject(job.as_raw_handle(), child.as_raw_handle()) != 0
+    };
+    if !succeeded {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(job)
+}
@@ -0,0 +1,28 @@
+//! Deserialization of subprocess output for `verify_json` block
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Parses subprocess output as single JSON value. If output isn't single value,
+/// it's parsed as newline-delimited JSON, whose values are collected into array,
+/// so it can be deserialized into `Vec` or tuple. Panics with raw output printed
+/// if output isn't valid JSON of expected type
+#[doc(hidden)]
+pub fn parse<T: DeserializeOwned>(output: &str) -> T {
+    let parsed = serde_json::from_str(output).or_else(|err| {
+        let values: Vec<Value> = output
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|_| err)?;
+        serde_json::from_value(Value::Array(values))
+    });
+
+    parsed.unwrap_or_else(|err| {
+        eprintln!("{output}");
+        panic!(
+            "Failed to parse subprocess output as JSON of type {}: {err}",
+            std::any::type_name::<T>()
+        )
+    })
+}
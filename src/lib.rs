@@ -3,7 +3,9 @@
 //! Macro generates test function code in such a way that first test code block
 //! is executed in separate subprocess by re-invoking current test executable.
 //! Its output is captured, filtered a bit and then fed to verification function.
-//! Test decides whether it's in normal or subprocess mode through marker environment variable
+//! Test decides whether it's in normal or subprocess mode through marker environment variable,
+//! which holds name of test to run in subprocess. Thus test body can call other subprocess tests,
+//! which are run in their own nested subprocesses
 //!
 //! Used when one needs to either run some test in isolation or validate test output
 //! regardless of its proper completion, i.e. even if it aborts
@@ -46,6 +48,9 @@
 //! # Usage
 //!
 //! ```rust
+//! # use subprocess_test::SubprocessTestError;
+//! # fn setup_fn() -> Result<(), SubprocessTestError> { Ok(()) }
+//! # fn cleanup_fn() -> Result<(), SubprocessTestError> { Ok(()) }
 //! // Single macro invocation can include multiple test function definitions,
 //! // but not other functions or lang items
 //! subprocess_test::subprocess_test! {
@@ -54,9 +59,10 @@
 //!     // Mandatory test marker attribute; parens are needed
 //!     // only if any attribute parameters are specified.
 //!     //
-//!     // Please also note that this attribute must be first,
-//!     // and its optional parameters must maintain order.
+//!     // Please also note that this attribute must be first.
 //!     // This is due to limitations of Rust's macro-by-example.
+//!     // Parameters can be specified in any order, and their values can be
+//!     // any expressions of suitable type, like constants, not just literals.
 //!     #[test(     
 //!         // Optionally specify name of environment variable used to mark subprocess mode.
 //!         // Default name is "__TEST_RUN_SUBPROCESS__", so in very improbable case case
@@ -68,8 +74,155 @@
 //!         // and in the end of test's output, regardless if it succeeds or panics.
 //!         // The default boundary line is "========================================",
 //!         // so in rare case you expect conflict with actual test output, you can use
-//!         // this parameter to set custom output boundary.
+//!         // this parameter to set custom output boundary. It's checked at compile time,
+//!         // so it must be constant, single line, and different from default one.
 //!         output_boundary = "<><><><><><><><>",
+//!         // Collect output split by `subprocess_mark!` into `SubprocessResult::sections`.
+//!         // Marks are stripped from output regardless of this flag.
+//!         capture_sections = true,
+//!         // Split output by `subprocess_checkpoint!` into `SubprocessResult::boundary_sections`.
+//!         // Checkpoints print output boundary, so without this flag they do nothing
+//!         multi_boundary = true,
+//!         // Clear subprocess environment, then pass only variables whose names
+//!         // start with specified prefix. Marker variable is always passed, along with
+//!         // `CARGO_FEATURE_*` variables, which are set by Cargo for build scripts
+//!         // and may be used for runtime feature detection.
+//!         inherit_env_prefix = "MY_APP_",
+//!         // Clear subprocess environment completely. Marker variable
+//!         // and `CARGO_FEATURE_*` variables are still passed
+//!         clear_env = true,
+//!         // Variables passed from parent when environment is cleared,
+//!         // either by `clear_env` or by `inherit_env_prefix`
+//!         inherit_env = ["PATH", "LD_LIBRARY_PATH"],
+//!         // Variables passed from parent when environment is cleared, if their names start
+//!         // with specified prefix. Unlike `inherit_env_prefix`, doesn't clear environment
+//!         // by itself, e.g. to forward `INSTA_` settings of snapshot tests
+//!         forward_env_prefix = "INSTA_",
+//!         // Same as `inherit_env`
+//!         forward_env = ["INSTA_UPDATE", "INSTA_SNAPSHOT_PATH"],
+//!         // Variables set for subprocess, overriding inherited ones. Applied after
+//!         // environment is cleared and variables are forwarded from parent
+//!         env = [("RUST_LOG", "debug"), ("MY_APP_MODE", "test")],
+//!         // Extra arguments for test harness of subprocess, appended after ones which
+//!         // select this test. Test executable is run directly rather than through cargo,
+//!         // so there's no `--` separator, and any test filters passed here are combined
+//!         // with this test's name
+//!         cargo_args = ["--test-threads=1"],
+//!         // Environment variables restored after test body runs in subprocess,
+//!         // even if it panics, in case body changes them
+//!         snapshot_env = ["RUST_LOG", "MY_CONFIG"],
+//!         // Exit codes which count as success, for programs which use non-zero codes
+//!         // for expected outcomes. By default only 0 is success
+//!         success_codes = [0, 1],
+//!         // Unix only. Signals which count as success if subprocess was killed by them.
+//!         // By default subprocess killed by any signal fails
+//!         success_signals = [15],
+//!         // Network timeout in milliseconds, which subprocess body can obtain through
+//!         // `subprocess_network_timeout_ms()` to configure its connections
+//!         network_timeout_ms = 5000,
+//!         // Linux only, ignored on other platforms. Adjust OOM killer score of subprocess,
+//!         // in range -1000..=1000. Negative values make it less likely to be killed,
+//!         // but usually require elevated privileges
+//!         oom_score_adj = 500,
+//!         // Functions of type `fn() -> Result<(), SubprocessTestError>`, which are run
+//!         // in parent process right before subprocess spawn and right after subprocess exit.
+//!         // See `PreSpawnHook` and `PostSpawnHook` for details
+//!         pre_spawn_hook = setup_fn,
+//!         post_spawn_hook = cleanup_fn,
+//!         // Compare duration of successful subprocess run with mean duration of last
+//!         // `baseline_runs` successful runs (10 by default), and print warning if it's slower
+//!         // by more than specified percentage. Durations are stored in
+//!         // `subprocess-test-baseline` directory inside Cargo's target directory
+//!         regression_threshold_pct = 20,
+//!         baseline_runs = 5,
+//!         // Create executable with specified name in temporary directory, and put
+//!         // that directory first in subprocess `PATH`. When run, it prints
+//!         // `fake_binary_output` and exits with `fake_binary_exit_code` (empty and 0
+//!         // by default). It's shell script on Unix and batch file on Windows; note that
+//!         // on Windows `Command::new` needs `.bat` extension to find it
+//!         fake_binary = "mycommand",
+//!         fake_binary_output = "Fake output\n",
+//!         fake_binary_exit_code = 0,
+//!         // Linux only, ignored on other platforms. Collect number of bytes subprocess
+//!         // read and written into `SubprocessResult::io_read_bytes` and `io_write_bytes`
+//!         measure_io_bytes = true,
+//!         // Linux and macOS only. Run subprocess under syscall tracer and put its trace into
+//!         // `SubprocessResult::syscall_trace`, see `verify_syscalls` block
+//!         trace_syscalls = false,
+//!         // Closure of type `Fn(&str) + 'static` which is called for each line of output,
+//!         // without line terminator, as soon as line is printed by subprocess.
+//!         // `verify` block still receives whole output after subprocess exits
+//!         on_output_line = |line: &str| if line.contains("DONE") { println!("Done!") },
+//!         // Value implementing `SubprocessTestPlugin`, which is called around subprocess
+//!         // execution. Can be specified several times, plugins are called in order
+//!         plugin = subprocess_test::LoggingPlugin,
+//!         // Unix only, ignored on other platforms. Put warning into `SubprocessResult::warnings`
+//!         // if subprocess has more open file descriptors than specified when its body ends,
+//!         // including standard ones. With `fail_on_fd_leak = true`, test fails instead
+//!         max_open_fds = 100,
+//!         fail_on_fd_leak = false,
+//!         // Working directory for subprocess, any expression convertible to `&Path`,
+//!         // like `env!("CARGO_MANIFEST_DIR")`. Directory must exist.
+//!         // By default subprocess inherits working directory of test runner
+//!         working_dir = env!("CARGO_MANIFEST_DIR"),
+//!         // Run subprocess specified number of times sequentially. Body can obtain index
+//!         // of current run through `subprocess_phase()`, and `SubprocessResult::phases`
+//!         // holds results of all runs
+//!         phases = 1,
+//!         // Kill subprocess if it doesn't finish in specified number of seconds,
+//!         // and set `SubprocessResult::timed_out`. By default subprocess isn't limited in time
+//!         timeout_secs = 30,
+//!         // Let subprocess run to completion, but fail test if it took longer than specified
+//!         // number of seconds, even if it succeeded. Elapsed time is available to `verify`
+//!         // block as `SubprocessResult::duration`. By default duration isn't checked
+//!         max_duration_secs = 5.0,
+//!         // Unix only. On timeout, send `SIGTERM` first and kill subprocess only if it doesn't
+//!         // exit within specified number of milliseconds, so it can flush its output.
+//!         // By default, and on other platforms, subprocess is killed right away
+//!         graceful_shutdown_ms = 200,
+//!         // Keep at most specified number of bytes of each captured output stream
+//!         // and set `SubprocessResult::truncated` if there was more. Unlimited by default
+//!         max_output_bytes = 1048576,
+//!         // Re-run failed subprocess up to specified number of times, test passes if any
+//!         // attempt succeeds. Timeout applies to each attempt separately. No retries by default
+//!         retry = 3,
+//!         // Wait between retry attempts, in milliseconds. No wait by default
+//!         retry_delay_ms = 100,
+//!         // Trim whitespace from captured output, either "start", "end" or "both".
+//!         // By default output is passed as is
+//!         output_trim = "end",
+//!         // Check that subprocess succeeded and printed specified number of lines,
+//!         // so simple tests don't need `verify` block; `0` means no output at all.
+//!         // Check runs before `verify` block, if any
+//!         expect_lines = 2,
+//!         // Compare output with snapshot committed into `tests/subprocess_snapshots`,
+//!         // see `SUBPROCESS_TEST_UPDATE` variable. Check runs before `verify` block, if any
+//!         snapshot = false,
+//!         // Echo subprocess output lines to `stderr` as they arrive, prefixed with test name
+//!         // like `[dummy] Foo`. Test harness captures this echo like any other test output,
+//!         // so it's shown only for failed tests unless `--nocapture` is used.
+//!         // If output can't be streamed, like with `split_output`, it's echoed after exit
+//!         verbose = true,
+//!         // Expect subprocess to fail, either in any way with `true`, or with specified
+//!         // exit code, like `101` for panic. Test fails if subprocess has other outcome,
+//!         // so `verify` block isn't required, and `success` means expected failure happened
+//!         expect_failure = true,
+//!         // Replace `\r\n` line endings in captured output with `\n`.
+//!         // Enabled by default on Windows and disabled on other platforms
+//!         normalize_line_endings = true,
+//!         // Remove panic backtraces and `RUST_BACKTRACE` hints from captured output,
+//!         // so assertions don't depend on addresses and line numbers in them.
+//!         // Also sets `RUST_BACKTRACE=0` for subprocess
+//!         strip_backtrace = true,
+//!         // Make absolute paths inside workspace, like ones in panic messages,
+//!         // relative to workspace root, so output doesn't depend on its location
+//!         normalize_paths = true,
+//!         // Replace runs of consecutive identical lines in captured output with single line,
+//!         // like `uniq` does, so noisy progress logging doesn't bloat it
+//!         deduplicate_output = true,
+//!         // Join threads spawned by `subprocess_spawn` after test body finishes, before
+//!         // closing output boundary is printed, so their output is captured in full
+//!         join_threads = true,
 //!     )]
 //!     // Any other attributes are allowed, yet are optional
 //!     #[ignore]
@@ -80,17 +233,191 @@
 //!         println!("Foo");
 //!         eprintln!("Bar");
 //!     }
+//!     // Optional block which evaluates to data fed into subprocess `stdin`, anything which
+//!     // implements `AsRef<[u8]>`. Without it, subprocess `stdin` is empty
+//!     stdin { b"Input\n" }
+//!     // Optional block run in parent process, which evaluates to `bool`. If it's `true`,
+//!     // e.g. some external tool isn't installed, test passes without spawning subprocess
+//!     // and prints skip message to `stderr`, bypassing output capture of test harness.
+//!     // libtest has no notion of skipped tests, so test is still reported as passed.
+//!     // Under nextest, which has none either, skip line in nextest format goes to `stdout`
+//!     // instead, and test name is appended to file named by `NEXTEST_SKIP`, if it's set
+//!     skip_if { std::env::var_os("DUMMY_UNAVAILABLE").is_some() }
+//!     // Optional block run in parent process right before subprocess is spawned, never
+//!     // in subprocess itself. Its `SubprocessContext` parameter overrides environment
+//!     // and working directory of subprocess, and keeps fixtures alive until test ends.
+//!     // Context is also available to `verify` block under same name
+//!     setup |ctx| {
+//!         ctx.env("DUMMY_MODE", "fast");
+//!     }
+//!     // Optional block run in parent process right before subprocess is spawned, after `setup`.
+//!     // Its parameter is `SubprocessFixture` with fresh temporary directory, whose path
+//!     // is passed to subprocess, and is retrieved there with `SubprocessFixture::path_from_env()`.
+//!     // Like setup context, fixture is available to `verify` block under same name,
+//!     // and directory is removed after test ends
+//!     fixture |fix| {
+//!         std::fs::write(fix.path().join("input.txt"), "Input").unwrap();
+//!     }
+//!     // Optional `normalize` blocks, which transform output in parent process before
+//!     // it's passed to `verify` block, like replace random ports or timestamps.
+//!     // Blocks can be repeated, and are applied in order they're written. They're closures
+//!     // which must not capture variables of test function
+//!     normalize |output| {
+//!         output.replace("Input", "Normalized")
+//!     }
+//!     // Optional block run in parent process while subprocess runs, e.g. to send requests
+//!     // to server started by test body. After test body returns, subprocess keeps running
+//!     // until block returns or calls `SubprocessControl::stop`, and timeout isn't enforced
+//!     // until then. Like `normalize` block, it must not capture variables of test function
+//!     control |ctl| {
+//!         println!("Subprocess {} is running", ctl.pid());
+//!     }
 //!     // `verify` block is optional;
 //!     // if absent, it's substituted with block which just asserts that subprocess succeeded
-//!     // and prints test output in case of failure
+//!     // and prints test output in case of failure. If subprocess panicked, failure message
+//!     // includes location and message of that panic
 //!     //
 //!     // Parameters can be any names. Their meanings:
 //!     // * `success` - boolean which is `true` if subprocess succeeded
 //!     // * `output` - subprocess output collected into string, both `stdout` and `stderr`
+//!     //
+//!     // If first parameter is named `exit_code`, it receives `Option<i32>` exit code instead,
+//!     // which is `None` if subprocess was killed by signal on Unix or killed on timeout,
+//!     // like `verify |exit_code, output| { assert_eq!(exit_code, Some(2)); }`.
+//!     // To migrate from `success`, compare exit code with `Some(0)`, or with one of
+//!     // `success_codes` if they're specified
 //!     verify |success, output| {
 //!         // This block is run as normal part of test and in general must succeed
 //!         assert!(success);
-//!         assert_eq!(output, "Foo\nBar\n");
+//!         assert_eq!(output, "Foo\nBar");
+//!     }
+//!     // Optional block run in parent process after `verify` block, even if it panicked,
+//!     // to release resources. If both blocks panic, test fails with panic of `verify` block.
+//!     // Like `verify` block, it can access context of `setup` block
+//!     teardown {
+//!         println!("Cleaning up");
+//!     }
+//!
+//!     #[test]
+//!     fn full_result() {
+//!         println!("Baz");
+//!     }
+//!     // `verify` block can also take single parameter, which receives whole `SubprocessResult`.
+//!     // Its `exit_code` is `None` if subprocess was killed by signal on Unix
+//!     verify |result| {
+//!         assert!(result.success);
+//!         assert_eq!(result.exit_code, Some(0));
+//!         assert_eq!(result.output, "Baz\n");
+//!     }
+//!
+//!     #[test]
+//!     fn fallible_verify() {
+//!         print!("42");
+//!     }
+//!     // `?` operator can be used in `verify` block. Test fails if it returns error,
+//!     // same as on panic. For tests with result type, error is returned as test result instead
+//!     verify |success, output| {
+//!         assert!(success);
+//!         assert_eq!(output.parse::<u32>()?, 42);
+//!     }
+//!
+//!     #[test]
+//!     fn separate_streams() {
+//!         println!("Result");
+//!         eprintln!("Diagnostic");
+//!     }
+//!     // With three parameters, `stdout` and `stderr` are captured separately and passed
+//!     // as second and third ones. Their relative order is lost in this mode
+//!     verify |success, stdout, stderr| {
+//!         assert!(success);
+//!         assert_eq!(stdout, "Result\n");
+//!         assert_eq!(stderr, "Diagnostic\n");
+//!     }
+//!
+//!     #[test]
+//!     fn binary_output() {
+//!         use std::io::Write;
+//!         std::io::stdout().write_all(&[0xFF, 0x00]).unwrap();
+//!     }
+//!     // Output which isn't valid UTF-8 can be checked as raw bytes with `verify_bytes` block,
+//!     // which is used instead of `verify` block
+//!     verify_bytes |success, output_bytes| {
+//!         assert!(success);
+//!         assert_eq!(output_bytes, [0xFF, 0x00]);
+//!     }
+//!
+//!     #[test]
+//!     fn aborts() {
+//!         std::process::abort();
+//!     }
+//!     // Subprocess killed by signal has no exit code. Signal number can be checked with
+//!     // `verify_unix` block, which is used instead of `verify` block. Signal is always `None`
+//!     // on non-Unix platforms
+//!     verify_unix |exit_code, signal, _output| {
+//!         assert_eq!(exit_code, None);
+//!         assert_eq!(signal, Some(6));
+//!     }
+//!
+//!     #[test]
+//!     fn unordered_lines() {
+//!         println!("Worker 2 done");
+//!         println!("Worker 1 done");
+//!     }
+//!     // Output can be checked line by line with `verify_lines` block, which is used instead
+//!     // of `verify` block and receives `OutputLines` iterator
+//!     verify_lines |success, lines| {
+//!         assert!(success);
+//!         assert!(lines.find("Worker 1").is_some());
+//!     }
+//!
+//!     #[test]
+//!     fn checkpoints() {
+//!         println!("Before");
+//!         subprocess_test::subprocess_checkpoint!();
+//!         println!("After");
+//!     }
+//!     // Output split by `subprocess_checkpoint!` can be checked with `verify_sections` block,
+//!     // which is used instead of `verify` block and receives parts as `Vec<String>`.
+//!     // It implies `multi_boundary = true`
+//!     verify_sections |success, sections| {
+//!         assert!(success);
+//!         assert_eq!(sections, ["Before\n", "After\n"]);
+//!     }
+//!
+//!     #[test]
+//!     fn separate_process() {
+//!         print!("{}", std::process::id());
+//!     }
+//!     // Subprocess ID can be checked with `verify_with_pid` block, which is used instead
+//!     // of `verify` block. It's `None` only in dry run
+//!     verify_with_pid |success, output, pid| {
+//!         assert!(success);
+//!         assert_eq!(Some(output.parse().unwrap()), pid);
+//!     }
+//!
+//!     #[test(trace_syscalls = true)]
+//!     fn no_file_access() {
+//!         println!("{}", 2 + 2);
+//!     }
+//!     // With `trace_syscalls = true`, subprocess runs under `strace` on Linux or `dtrace`
+//!     // on macOS, and test is skipped if tracer isn't found in `PATH`. Trace goes into
+//!     // separate file, and can be checked with `verify_syscalls` block, which is used
+//!     // instead of `verify` block. Trace is `None` without `trace_syscalls`
+//!     verify_syscalls |success, output, trace| {
+//!         assert!(success);
+//!         assert_eq!(output, "4\n");
+//!         assert!(trace.is_some_and(|trace| !trace.contains("/etc/secret")));
+//!     }
+//!
+//!     #[test]
+//!     fn golden_output() {
+//!         println!("Usage: tool [OPTIONS]");
+//!     }
+//!     // `verify_snapshot` block receives output only if subprocess succeeded, and is intended
+//!     // for snapshot assertions, like `insta::assert_snapshot!(output)`.
+//!     // It's used instead of `verify` block
+//!     verify_snapshot |output| {
+//!         assert!(output.starts_with("Usage:"));
 //!     }
 //!
 //!     #[test]
@@ -110,11 +437,218 @@
 //! }
 //! ```
 //!
+//! # Subtests
+//!
+//! Subprocess test body can be split into named steps using [`subtest!`] macro.
+//! Failure of one step doesn't prevent following steps from running, and all failures
+//! are reported together after subprocess exits.
+//!
+//! Status messages sent with [`subprocess_print_to_parent!`] are printed by parent
+//! to its stderr, without being mixed into captured output.
+//!
+//! # Attribute macro
+//!
+//! With `attribute` feature, tests can be declared with `#[subprocess_test]` attribute
+//! from `attr` module instead, which takes same parameters as `#[test(...)]`.
+//! Other attributes may go in any order, and `verify` block is specified as closure
+//! in `#[subprocess_verify]` attribute. Attribute is expanded through `subprocess_test!`,
+//! so both forms can be used simultaneously. Other blocks are available only with macro.
+//! `#[subprocess_verify]` is consumed by `#[subprocess_test]`, so it needn't be imported.
+//! If `subprocess-test` dependency is renamed, its name is passed as `crate = "name"` parameter
+//!
+//! ```rust,ignore
+//! use subprocess_test::attr::subprocess_test;
+//!
+//! #[subprocess_test(timeout_secs = 10)]
+//! #[subprocess_verify(|success, output| {
+//!     assert!(success);
+//!     assert_eq!(output, "2\n");
+//! })]
+//! fn one_plus_one() {
+//!     println!("{}", 1 + 1);
+//! }
+//! ```
+//!
+//! # Build scripts
+//!
+//! Build script logic can be tested in isolation too, using [`build_subprocess_test!`].
+//! See `examples/build-script` in crate repository for complete example.
+//!
+//! # External binaries
+//!
+//! Tests which need to run separate binary, like CLI tool built by same package,
+//! rather than their own body, are declared with [`subprocess_test_external!`].
+//! Such tests specify binary with `binary` parameter and its arguments with `args` block.
+//!
+//! # Custom output file
+//!
+//! Some test runners expect output to be written into file whose path is passed through
+//! environment variable, rather than to stdout and stderr. With `output_from_env = "VAR_NAME"`
+//! parameter, subprocess stdout and stderr are discarded; instead, output is read from file
+//! at path stored in `VAR_NAME` after subprocess exits. If variable is set externally,
+//! its path is used as is. Otherwise, runner creates temporary file and passes its path
+//! to subprocess through `VAR_NAME`.
+//!
+//! # Assertions
+//!
+//! Crate provides several assertion macros to simplify common checks in `verify` blocks:
+//!
+//! * [`assert_subprocess_duration!`], [`assert_subprocess_faster_than!`],
+//!   [`assert_subprocess_slower_than!`] - check [`SubprocessResult::duration`]
+//! * [`assert_subprocess_output_float!`] - check floating-point number printed in output,
+//!   with specified tolerance
+//! * [`assert_subprocess_lines_in_order!`] - check that certain lines are present in output
+//!   in specified order, with any other lines between them
+//! * [`assert_subprocess_eq_char_diff!`] - check that output equals expected string,
+//!   reporting differing lines with whitespace and control characters escaped
+//! * [`matches_result!`] - check several fields of [`SubprocessResult`] at once
+//! * [`assert_panic_message!`] - check message of panic reported in output, extracted
+//!   by [`extract_panic_message`] from test harness framing and backtrace
+//! * `assert_subprocess_output_xml!` - check value selected from XML output
+//!   by simple XPath-like query, requires `xml` feature
+//!
+//! # Cargo features
+//!
+//! * `criterion` - enables `subprocess_test_bench!` macro, which creates
+//!   [criterion](https://docs.rs/criterion) benchmarks with routines isolated in subprocesses
+//! * `chaos-testing` - Unix only, enables `inject_fault = "<fault>"` test parameter, which
+//!   injects fault into subprocess test body to check error handling. Supported faults are
+//!   `"sigsegv"` and `"sigfpe"`, which send signal right after body starts, `"sigsegv_after_ms = N"`
+//!   and `"sigfpe_after_ms = N"`, which send signal after delay, and `"malloc_fail_after = N"`,
+//!   which makes all `malloc` calls on test body's thread fail after `N` successful ones.
+//!   Note that `SIGSEGV` handler installed by standard library is reset to default before
+//!   signal is sent.
+//!   Also note that only `malloc` is affected, not `calloc` or `realloc`. Malloc failure is
+//!   supported only on Linux, and requires C compiler to build library preloaded into subprocess
+//! * `context` - allows test function to declare single parameter with default value,
+//!   like `fn test(ctx: MyContext = MyContext::new())`. Value is created in parent process,
+//!   passed to subprocess serialized with [serde](https://docs.rs/serde) as JSON, and received
+//!   by test body as parameter. So context type must implement both `Serialize` and
+//!   `Deserialize`. Since value expression runs only in parent, it can prepare resources
+//!   for subprocess, like find free TCP port. Also enables `subprocess_test_parametrize!` macro,
+//!   which defines one subprocess test per each parameter value from list of cases
+//! * `count-allocations` - enables `capture_allocations = true` test parameter, which counts
+//!   heap allocations and deallocations made by test body into `SubprocessResult::alloc_count`
+//!   and `dealloc_count`. Only allocations made on test body's thread are counted, so threads
+//!   spawned by body and harness threads aren't. Reallocations aren't counted either.
+//!   **Note that feature installs counting `#[global_allocator]` inside this crate, so it
+//!   replaces allocator of every binary linking it, and can't be used together with other
+//!   global allocators.** Enable it only in `[dev-dependencies]`, never in `[dependencies]`
+//! * `cpu-affinity` - enables `cpu_affinity = [<core>, ...]` test parameter, which pins
+//!   subprocess to specified CPU cores to reduce timing variability. Supported on Linux
+//!   and Windows, ignored on other platforms. List of cores is passed to subprocess
+//!   through `SUBPROCESS_TEST_CPU_AFFINITY` variable, as comma-separated numbers
+//! * `low-level-io` - Unix only, enables `capture_fd = <fd>` test parameter,
+//!   which connects specified file descriptor of subprocess to its captured output,
+//!   so direct writes to it aren't lost. Parameter can be specified several times.
+//!   Also enables `subprocess_capture_write!` macro to write to such descriptors
+//! * `tokio` - allows `verify` block to be async, like `verify async |result| { ... }`.
+//!   Such block is run to completion on single-threaded [Tokio](https://tokio.rs) runtime,
+//!   with all drivers enabled by Tokio features in use. Also allows test body to be `async fn`,
+//!   which is run in subprocess on multi-threaded Tokio runtime. Tokio is used for such bodies
+//!   by default, or when test is declared with `runtime = "tokio"`
+//! * `async-std` - allows `async fn` test bodies to be run on
+//!   [async-std](https://docs.rs/async-std) runtime, with `runtime = "async-std"` test parameter
+//! * `regex` - enables `verify_matches = r"<pattern>"` test parameter, which checks that
+//!   subprocess succeeded and its output matches [regex](https://docs.rs/regex) pattern,
+//!   so simple tests don't need `verify` block. Check runs before `verify` block, if any
+//! * `serde-json` - allows `verify_json |success, value: Type| { ... }` block, which is used
+//!   instead of `verify` block. Output is deserialized into `Type` with
+//!   [serde_json](https://docs.rs/serde_json), either as single JSON value or as
+//!   newline-delimited JSON, whose values are collected into array. Test fails
+//!   with raw output printed if output can't be deserialized
+//! * `strip-ansi` - enables `strip_ansi = true` test parameter, which removes ANSI escape
+//!   sequences, like color codes, from captured output passed to `verify` block.
+//!   Raw `output_bytes` are left intact
+//! * `attribute` - enables `#[subprocess_test]` attribute macro, see
+//!   [Attribute macro](#attribute-macro) section
+//! * `diff-output` - enables `assert_subprocess_output_eq!` macro, which shows line diff
+//!   of actual and expected output on mismatch, computed with [similar](https://docs.rs/similar).
+//!   Also enables `verify_golden = "<path>"` test parameter, which checks that subprocess
+//!   succeeded and its output equals contents of golden file, with path relative to crate
+//!   directory, and shows same diff otherwise. Check runs before `verify` block, if any
+//! * `llvm-cov` - when `LLVM_PROFILE_FILE` variable is set, e.g. by `cargo llvm-cov`,
+//!   passes unique profile path to each subprocess, with test name and random suffix
+//!   appended to file name. Otherwise subprocess and parent may write to same profile file,
+//!   and coverage of test bodies gets lost. Profiles of subprocesses are placed near profile
+//!   of parent process, so coverage tool which collects profiles from that directory,
+//!   like `cargo llvm-cov`, merges them together. Other tools read only profile of parent
+//!   process, so after each test subprocess profiles are also merged into
+//!   `<name>-subprocesses.profdata` next to it with `llvm-profdata merge`. This step is
+//!   skipped if `llvm-profdata` isn't found in `PATH`
+//! * `json-report` - enables JSON report of subprocess results, see
+//!   [Environment variables](#environment-variables) section
+//! * `xml` - enables `assert_subprocess_output_xml!` macro, which checks values
+//!   in XML output selected by simple XPath-like queries
+//! * `rlimit` - enables `rlimit_as = <bytes>` test parameter, which limits subprocess memory,
+//!   and `memory_limit_mb = <megabytes>` parameter, which is same limit in megabytes.
+//!   On Unix, it limits virtual address space through `setrlimit(RLIMIT_AS)`, so allocations
+//!   beyond limit fail. On Windows, subprocess is assigned to job object which limits
+//!   its committed memory, so allocations beyond limit fail too. Job is assigned right
+//!   after subprocess starts, so limit doesn't cover its loader startup.
+//!   Same limit can be applied by `ResourceLimitPlugin`
+//! * `windows-cleanup` - enabled by default, kills subprocess if test runner dies, e.g. when
+//!   it's killed by CI timeout, so subprocess doesn't linger as orphan. On Windows, subprocess
+//!   is assigned to job object with `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE`. On Linux, it gets
+//!   `SIGKILL` as parent death signal through `prctl(PR_SET_PDEATHSIG)`. Ignored elsewhere
+//!
+//! # Environment variables
+//!
+//! Behavior of all subprocess tests can be tweaked at runtime through environment variables
+//! set for test runner:
+//!
+//! * `SUBPROCESS_TEST_DRY_RUN=1` - don't spawn any subprocesses; instead, print command line
+//!   which would be executed to `stderr` and call `verify` block as if subprocess succeeded
+//!   with empty output. Useful for diagnosing subprocess invocation issues
+//! * `SUBPROCESS_TEST_FLAKINESS_DETECT=N` - run each subprocess `N` times. If some runs succeed
+//!   while others fail, test panics with "Test is flaky" message and outputs of failed runs.
+//!   Otherwise, `verify` block is called with result of last run
+//! * `SUBPROCESS_TEST_UPDATE_BASELINE=1` - for tests with `regression_threshold_pct` specified,
+//!   discard previously recorded durations and start baseline anew from current run
+//! * `SUBPROCESS_TEST_VERBOSE=1` - enable `verbose` parameter for all tests
+//! * `SUBPROCESS_TEST_JSON_REPORT=<path>` - requires `json-report` feature. Append JSON record
+//!   per each subprocess test run to specified file, one per line, like
+//!   `{"test": "crate::mod::name", "success": true, "duration_ms": 42, "output": "..."}`.
+//!   `success` tells whether subprocess succeeded, regardless of `verify` block outcome.
+//!   Output is truncated to 4096 bytes, or to number of bytes specified by
+//!   `SUBPROCESS_TEST_JSON_REPORT_MAX_OUTPUT` variable
+//! * `SUBPROCESS_TEST_UPDATE=1` - for tests with `snapshot = true` parameter, write output
+//!   of subprocess into `tests/subprocess_snapshots/<test path>.txt` snapshot inside crate
+//!   directory instead of running `verify` block. When variable isn't set, such tests fail
+//!   if snapshot is missing or their output doesn't match it. Snapshots are meant
+//!   to be committed into version control
+//! * `SUBPROCESS_TEST_UPDATE_GOLDEN=1` - requires `diff-output` feature. For tests with
+//!   `verify_golden` parameter, overwrite golden file with output of successful subprocess
+//!   instead of comparing them. Missing golden file is created
+//! * `SUBPROCESS_TEST_VALGRIND=1` - run subprocesses of `subprocess_test!` tests under
+//!   `valgrind --error-exitcode=1 --leak-check=full`, so memory errors and leaks fail them.
+//!   Valgrind report doesn't go into output; it's available as `SubprocessResult::diagnostics`
+//!   and printed to `stderr` if subprocess failed. Tests are skipped if `valgrind`
+//!   isn't found in `PATH`
+//!
+//! # Manifest defaults
+//!
+//! Defaults for `env_var_name` and `output_boundary` parameters can be set once for all tests
+//! of crate in its `Cargo.toml`, instead of repeating them in each `#[test(...)]` attribute:
+//!
+//! ```toml
+//! [package.metadata.subprocess-test]
+//! env_var_name = "MY_VAR"
+//! output_boundary = "CUSTOM"
+//! ```
+//!
+//! Same settings in `[workspace.metadata.subprocess-test]` section of workspace root manifest
+//! apply to all crates of workspace. Package settings take precedence over workspace ones,
+//! and parameters specified in `#[test(...)]` attribute take precedence over both.
+//! Manifest of crate is embedded into test executable, while workspace root manifest
+//! is read when first test runs
+//!
 //! # Limitations
 //!
 //! Macro doesn't work well with `#[should_panic]` attribute because there's only one test function
 //! which runs in two modes. If subprocess test panics as expected, subprocess succeeds, and
-//! `verify` block must panic too. Just use `verify` block and do any checks you need there.
+//! `verify` block must panic too. Use `expect_failure` parameter instead, or `verify` block
+//! with any checks you need there.
 //!
 //! Another minor limitation, as described in [#Usage] section, is that first goes doc comment,
 //! then mandatory `#[test]` attribute with extensions, then any other attributes,
@@ -123,166 +657,3486 @@
 //! If test writer uses explicit result type and forgets to write `verify` block, he'll get error
 //! like "expected return value `Result<_, _>`, got `()" instead of possibly more comprehensive
 //! "missing `verify` block". Again, this is due to limitations of macro-by-example
+//!
+//! Failed subprocess test panics in parent at location of macro invocation, because actual panic
+//! happened in another process. Default `verify` block copies location of subprocess panic
+//! into its own message. To make it point to test body rather than some assertion helper,
+//! mark such helper with `#[track_caller]`. Putting `#[track_caller]` on test function itself
+//! isn't useful, since its caller is test harness
+//!
+//! Subprocess tests can't run on WebAssembly targets like `wasm32-wasip1`, even under runtime
+//! like `wasmtime`. WASI doesn't support spawning processes, so test running inside WASM module
+//! can't start neither its own subprocess nor another runtime instance. Crate fails
+//! to compile for such targets with explanatory message, rather than panic in every test
+// Doc examples declare tests through macro, they're never meant to be run as doctest's tests
+#![allow(clippy::test_attr_in_doctest)]
 use std::borrow::Cow;
-use std::env::{args_os, var_os};
+use std::env::{args_os, var, var_os, vars_os};
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Display, Write};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
-use std::process::{Command, Stdio};
+use std::io::{self, Read, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio, exit};
+use std::time::{Duration, Instant};
 
 use defer::defer;
-use tempfile::tempfile;
+pub use tempfile::TempDir;
+use tempfile::{NamedTempFile, TempPath};
+
+#[cfg(feature = "cpu-affinity")]
+mod affinity;
+#[cfg(feature = "count-allocations")]
+mod alloc_count;
+#[doc(hidden)]
+pub mod assertions;
+mod async_runtime;
+#[cfg(feature = "attribute")]
+pub mod attr;
+mod baseline;
+#[cfg(feature = "criterion")]
+pub mod bench;
+#[cfg(all(unix, feature = "chaos-testing"))]
+mod chaos;
+#[cfg(all(target_os = "linux", feature = "windows-cleanup"))]
+mod cleanup;
+#[doc(hidden)]
+pub mod config;
+#[cfg(feature = "context")]
+#[doc(hidden)]
+pub mod context;
+mod control;
+#[cfg(feature = "llvm-cov")]
+mod coverage;
+mod env_snapshot;
+mod fake_binary;
+#[cfg(all(unix, feature = "low-level-io"))]
+#[doc(hidden)]
+pub mod fd_capture;
+mod fixture;
+#[cfg(all(windows, any(feature = "windows-cleanup", feature = "rlimit")))]
+mod job;
+#[cfg(feature = "serde-json")]
+#[doc(hidden)]
+pub mod json;
+#[cfg(all(unix, feature = "rlimit"))]
+mod limits;
+mod open_fds;
+mod output_lines;
+mod pipe;
+mod plugin;
+mod postprocess;
+#[cfg(feature = "json-report")]
+mod report;
+mod runner;
+mod setup;
+mod side_channel;
+#[doc(hidden)]
+pub mod skip;
+mod snapshot;
+mod streaming;
+#[doc(hidden)]
+pub mod subtest;
+mod syscalls;
+mod threads;
+mod valgrind;
+
+pub use assertions::extract_panic_message;
+pub use control::SubprocessControl;
+pub use fixture::SubprocessFixture;
+pub use output_lines::OutputLines;
+#[cfg(feature = "rlimit")]
+pub use plugin::ResourceLimitPlugin;
+pub use plugin::{LoggingPlugin, PluginChain, SubprocessTestPlugin, TimingPlugin};
+pub use setup::SubprocessContext;
+pub use subtest::SubtestResult;
+pub use threads::subprocess_spawn;
+
+use async_runtime::AsyncRuntime;
+use baseline::Baseline;
+use control::ControlFn;
+use fake_binary::FakeBinary;
+use pipe::PipeReader;
+use side_channel::{SIDE_CHANNEL_ENV_VAR_NAME, SideChannel};
+use streaming::{LineCallback, LineStream};
+use syscalls::SyscallTrace;
+
 /// Implementation of `subprocess_test` macro. See crate-level documentation for details and usage examples
 #[macro_export]
 macro_rules! subprocess_test {
+    // All tests are synchronous, which is most common case. Tests are expanded directly,
+    // without recursion, so number of tests per invocation isn't limited by recursion limit
     (
         $(
             $(#[doc = $doc_lit:literal])*
-            #[test $((
-                $(env_var_name = $subp_var_name:literal $(,)?)?
-                $(output_boundary = $subp_output_boundary:literal $(,)?)?
-            ))?]
+            #[test $(($($params:tt)*))?]
             $(#[$attrs:meta])*
-            fn $test_name:ident () $(-> $test_result:ty)? $test_block:block
-            $(verify |$success_param:ident, $stdout_param:ident| $verify_block:block)?
+            fn $test_name:ident ($($ctx:tt)*)
+                $(-> $test_result:ty)? $test_block:block
+            $(stdin $stdin_block:block)?
+            $(skip_if $skip_block:block)?
+            $(setup |$setup_ctx:ident| $setup_block:block)?
+            $(fixture |$fixture_name:ident| $fixture_block:block)?
+            $(normalize |$normalize_output:ident| $normalize_block:block)*
+            $(control |$control_ctl:ident| $control_block:block)?
+            $(verify $($verify_async:ident)? |$($verify_param:ident),+| $verify_block:block)?
+            $(verify_bytes |$bytes_success:ident, $bytes_output:ident| $bytes_block:block)?
+            $(verify_unix |$unix_exit_code:ident, $unix_signal:ident, $unix_output:ident| $unix_block:block)?
+            $(verify_lines |$lines_success:ident, $lines_output:ident| $lines_block:block)?
+            $(verify_json |$json_success:ident, $json_value:ident: $json_ty:ty| $json_block:block)?
+            $(verify_snapshot |$snapshot_output:ident| $snapshot_block:block)?
+            $(verify_sections |$sections_success:ident, $sections_output:ident| $sections_block:block)?
+            $(verify_with_pid |$pid_success:ident, $pid_output:ident, $pid_value:ident| $pid_block:block)?
+            $(verify_syscalls |$syscalls_success:ident, $syscalls_output:ident, $syscalls_trace:ident| $syscalls_block:block)?
+            $(teardown $teardown_block:block)?
         )*
     ) => {
         $(
+            $crate::subprocess_test! {
+                @test [] [$($test_result)?]
+                $(#[doc = $doc_lit])*
+                #[test $(($($params)*))?]
+                $(#[$attrs])*
+                fn $test_name ($($ctx)*)
+                    $(-> $test_result)? $test_block
+                $(stdin $stdin_block)?
+                $(skip_if $skip_block)?
+                $(setup |$setup_ctx| $setup_block)?
+                $(fixture |$fixture_name| $fixture_block)?
+                $(normalize |$normalize_output| $normalize_block)*
+                $(control |$control_ctl| $control_block)?
+                $(verify $($verify_async)? |$($verify_param),+| $verify_block)?
+                $(verify_bytes |$bytes_success, $bytes_output| $bytes_block)?
+                $(verify_unix |$unix_exit_code, $unix_signal, $unix_output| $unix_block)?
+                $(verify_lines |$lines_success, $lines_output| $lines_block)?
+                $(verify_json |$json_success, $json_value: $json_ty| $json_block)?
+                $(verify_snapshot |$snapshot_output| $snapshot_block)?
+                $(verify_sections |$sections_success, $sections_output| $sections_block)?
+                $(verify_with_pid |$pid_success, $pid_output, $pid_value| $pid_block)?
+                $(verify_syscalls |$syscalls_success, $syscalls_output, $syscalls_trace| $syscalls_block)?
+                $(teardown $teardown_block)?
+            }
+        )*
+    };
+    // Some test is `async fn`. Macro-by-example can't match optional `async` keyword
+    // before `fn` inside repetition, so tests are taken one by one instead
+    (
+        $(#[doc = $doc_lit:literal])*
+        #[test $(($($params:tt)*))?]
+        $(#[$attrs:meta])*
+        async fn $test_name:ident ($($ctx:tt)*)
+            $(-> $test_result:ty)? $test_block:block
+        $(stdin $stdin_block:block)?
+        $(skip_if $skip_block:block)?
+        $(setup |$setup_ctx:ident| $setup_block:block)?
+        $(fixture |$fixture_name:ident| $fixture_block:block)?
+        $(normalize |$normalize_output:ident| $normalize_block:block)*
+        $(control |$control_ctl:ident| $control_block:block)?
+        $(verify $($verify_async:ident)? |$($verify_param:ident),+| $verify_block:block)?
+        $(verify_bytes |$bytes_success:ident, $bytes_output:ident| $bytes_block:block)?
+        $(verify_unix |$unix_exit_code:ident, $unix_signal:ident, $unix_output:ident| $unix_block:block)?
+        $(verify_lines |$lines_success:ident, $lines_output:ident| $lines_block:block)?
+        $(verify_json |$json_success:ident, $json_value:ident: $json_ty:ty| $json_block:block)?
+        $(verify_snapshot |$snapshot_output:ident| $snapshot_block:block)?
+        $(verify_sections |$sections_success:ident, $sections_output:ident| $sections_block:block)?
+        $(verify_with_pid |$pid_success:ident, $pid_output:ident, $pid_value:ident| $pid_block:block)?
+        $(verify_syscalls |$syscalls_success:ident, $syscalls_output:ident, $syscalls_trace:ident| $syscalls_block:block)?
+        $(teardown $teardown_block:block)?
+        $(# $($rest:tt)*)?
+    ) => {
+        $crate::subprocess_test! {
+            @test [async] [$($test_result)?]
             $(#[doc = $doc_lit])*
-            #[test]
+            #[test $(($($params)*))?]
             $(#[$attrs])*
-            fn $test_name() $(-> $test_result)? {
-                $crate::run_subprocess_test(
-                    concat!(module_path!(), "::", stringify!($test_name)),
-                    $crate::subprocess_test! {
-                        @tokens_or_default { $($(Some($subp_var_name))?)? }
-                        or { None }
-                    },
+            fn $test_name ($($ctx)*)
+                $(-> $test_result)? $test_block
+            $(stdin $stdin_block)?
+            $(skip_if $skip_block)?
+            $(setup |$setup_ctx| $setup_block)?
+            $(fixture |$fixture_name| $fixture_block)?
+            $(normalize |$normalize_output| $normalize_block)*
+            $(control |$control_ctl| $control_block)?
+            $(verify $($verify_async)? |$($verify_param),+| $verify_block)?
+            $(verify_bytes |$bytes_success, $bytes_output| $bytes_block)?
+            $(verify_unix |$unix_exit_code, $unix_signal, $unix_output| $unix_block)?
+            $(verify_lines |$lines_success, $lines_output| $lines_block)?
+            $(verify_json |$json_success, $json_value: $json_ty| $json_block)?
+            $(verify_snapshot |$snapshot_output| $snapshot_block)?
+            $(verify_sections |$sections_success, $sections_output| $sections_block)?
+            $(verify_with_pid |$pid_success, $pid_output, $pid_value| $pid_block)?
+            $(verify_syscalls |$syscalls_success, $syscalls_output, $syscalls_trace| $syscalls_block)?
+            $(teardown $teardown_block)?
+        }
+        $crate::subprocess_test! { $(# $($rest)*)? }
+    };
+    (
+        $(#[doc = $doc_lit:literal])*
+        #[test $(($($params:tt)*))?]
+        $(#[$attrs:meta])*
+        fn $test_name:ident ($($ctx:tt)*)
+            $(-> $test_result:ty)? $test_block:block
+        $(stdin $stdin_block:block)?
+        $(skip_if $skip_block:block)?
+        $(setup |$setup_ctx:ident| $setup_block:block)?
+        $(fixture |$fixture_name:ident| $fixture_block:block)?
+        $(normalize |$normalize_output:ident| $normalize_block:block)*
+        $(control |$control_ctl:ident| $control_block:block)?
+        $(verify $($verify_async:ident)? |$($verify_param:ident),+| $verify_block:block)?
+        $(verify_bytes |$bytes_success:ident, $bytes_output:ident| $bytes_block:block)?
+        $(verify_unix |$unix_exit_code:ident, $unix_signal:ident, $unix_output:ident| $unix_block:block)?
+        $(verify_lines |$lines_success:ident, $lines_output:ident| $lines_block:block)?
+        $(verify_json |$json_success:ident, $json_value:ident: $json_ty:ty| $json_block:block)?
+        $(verify_snapshot |$snapshot_output:ident| $snapshot_block:block)?
+        $(verify_sections |$sections_success:ident, $sections_output:ident| $sections_block:block)?
+        $(verify_with_pid |$pid_success:ident, $pid_output:ident, $pid_value:ident| $pid_block:block)?
+        $(verify_syscalls |$syscalls_success:ident, $syscalls_output:ident, $syscalls_trace:ident| $syscalls_block:block)?
+        $(teardown $teardown_block:block)?
+        $(# $($rest:tt)*)?
+    ) => {
+        $crate::subprocess_test! {
+            @test [] [$($test_result)?]
+            $(#[doc = $doc_lit])*
+            #[test $(($($params)*))?]
+            $(#[$attrs])*
+            fn $test_name ($($ctx)*)
+                $(-> $test_result)? $test_block
+            $(stdin $stdin_block)?
+            $(skip_if $skip_block)?
+            $(setup |$setup_ctx| $setup_block)?
+            $(fixture |$fixture_name| $fixture_block)?
+            $(normalize |$normalize_output| $normalize_block)*
+            $(control |$control_ctl| $control_block)?
+            $(verify $($verify_async)? |$($verify_param),+| $verify_block)?
+            $(verify_bytes |$bytes_success, $bytes_output| $bytes_block)?
+            $(verify_unix |$unix_exit_code, $unix_signal, $unix_output| $unix_block)?
+            $(verify_lines |$lines_success, $lines_output| $lines_block)?
+            $(verify_json |$json_success, $json_value: $json_ty| $json_block)?
+            $(verify_snapshot |$snapshot_output| $snapshot_block)?
+            $(verify_sections |$sections_success, $sections_output| $sections_block)?
+            $(verify_with_pid |$pid_success, $pid_output, $pid_value| $pid_block)?
+            $(verify_syscalls |$syscalls_success, $syscalls_output, $syscalls_trace| $syscalls_block)?
+            $(teardown $teardown_block)?
+        }
+        $crate::subprocess_test! { $(# $($rest)*)? }
+    };
+    (
+        @test [$($async:ident)?] $test_ret:tt
+        $(#[doc = $doc_lit:literal])*
+        #[test $((
+            $($param_name:ident = $param_value:expr),* $(,)?
+        ))?]
+        $(#[$attrs:meta])*
+        fn $test_name:ident ($($ctx_name:ident : $ctx_ty:ty = $ctx_value:expr)?)
+            $(-> $test_result:ty)? $test_block:block
+        $(stdin $stdin_block:block)?
+        $(skip_if $skip_block:block)?
+        $(setup |$setup_ctx:ident| $setup_block:block)?
+        $(fixture |$fixture_name:ident| $fixture_block:block)?
+        $(normalize |$normalize_output:ident| $normalize_block:block)*
+        $(control |$control_ctl:ident| $control_block:block)?
+        $(verify $($verify_async:ident)? |$($verify_param:ident),+| $verify_block:block)?
+        $(verify_bytes |$bytes_success:ident, $bytes_output:ident| $bytes_block:block)?
+        $(verify_unix |$unix_exit_code:ident, $unix_signal:ident, $unix_output:ident| $unix_block:block)?
+        $(verify_lines |$lines_success:ident, $lines_output:ident| $lines_block:block)?
+        $(verify_json |$json_success:ident, $json_value:ident: $json_ty:ty| $json_block:block)?
+        $(verify_snapshot |$snapshot_output:ident| $snapshot_block:block)?
+        $(verify_sections |$sections_success:ident, $sections_output:ident| $sections_block:block)?
+        $(verify_with_pid |$pid_success:ident, $pid_output:ident, $pid_value:ident| $pid_block:block)?
+        $(verify_syscalls |$syscalls_success:ident, $syscalls_output:ident, $syscalls_trace:ident| $syscalls_block:block)?
+        $(teardown $teardown_block:block)?
+    ) => {
+        $(#[doc = $doc_lit])*
+        #[test]
+        $(#[$attrs])*
+        fn $test_name() $(-> $test_result)? {
+            // Inside test function, so checked parameters follow its `cfg`
+            $($($crate::subprocess_test!(@check_param $param_name $param_value);)*)?
+            let config = $crate::SubprocessTestConfig::new(
+                concat!(module_path!(), "::", stringify!($test_name))
+            )
+            .global_config($crate::subprocess_test!(@global_config))
+            .manifest_dir(env!("CARGO_MANIFEST_DIR"))
+            $($(.$param_name($param_value))*)?;
+            // Separate capture of `stdout` and `stderr` is requested by `verify` block signature
+            let config = $crate::subprocess_test!(
+                @split_output config $(|$($verify_param),+|)?
+            );
+            $(let config = config.stdin($stdin_block);)?
+            $(let config = $crate::subprocess_test!(@multi_boundary config $sections_success);)?
+            $(
+                if !config.is_subprocess() && $skip_block {
+                    $crate::skip::report(concat!(module_path!(), "::", stringify!($test_name)));
+                    return $crate::skip::Skipped::skipped();
+                }
+            )?
+            $(
+                let mut $setup_ctx = $crate::SubprocessContext::new();
+                if !config.is_subprocess() $setup_block
+                let config = config.setup_context(&$setup_ctx);
+            )?
+            // Goes after `setup` block, since it may override `PATH`
+            if !config.is_subprocess() && config.missing_tool() {
+                $crate::skip::report(concat!(module_path!(), "::", stringify!($test_name)));
+                return $crate::skip::Skipped::skipped();
+            }
+            $(
+                // Subprocess refers to directory created by parent
+                let $fixture_name = if config.is_subprocess() {
+                    $crate::SubprocessFixture::from_env()
+                } else {
+                    $crate::SubprocessFixture::new()
+                };
+                if !config.is_subprocess() $fixture_block
+                let config = config.fixture(&$fixture_name);
+            )?
+            $(let config = config.normalize(|$normalize_output: String| $normalize_block);)*
+            $(let config = config.control(move |$control_ctl: &$crate::SubprocessControl| $control_block);)?
+            // Goes after parameters, because it depends on `env_var_name`
+            $(let config = config.context::<$ctx_ty>(|| $ctx_value);)?
+            $crate::subprocess_test!(
+                @run [$($async)?] config,
+                || {
+                    $(let $ctx_name: $ctx_ty = $crate::context::load();)?
+                    $crate::subprocess_test!(@body [$($async)?] $test_block)
+                },
+                $crate::subprocess_test! {
+                    @teardown [$($teardown_block)?]
                     $crate::subprocess_test! {
-                        @tokens_or_default { $($(Some($subp_output_boundary))?)? }
-                        or { None }
-                    },
+                        @skip_updated_snapshot
+                        $crate::subprocess_test! {
+                            @tokens_or_default {
+                                // Result type is passed as single token tree,
+                                // so it can be used inside `verify` repetition
+                                $($crate::subprocess_test! {
+                                    @verify_fn $test_ret $($verify_async)? |$($verify_param),+| $verify_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $crate::SubprocessResult {
+                                        success: $bytes_success,
+                                        output_bytes: $bytes_output,
+                                        ..
+                                    } = result;
+                                    $bytes_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $crate::SubprocessResult {
+                                        exit_code: $unix_exit_code,
+                                        signal: $unix_signal,
+                                        output: $unix_output,
+                                        ..
+                                    } = result;
+                                    $unix_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $lines_success = result.success;
+                                    // Binding is mutable, since `OutputLines::find` advances iterator
+                                    #[allow(unused_mut)]
+                                    let mut $lines_output = $crate::OutputLines::new(&result.output);
+                                    $lines_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $json_success = result.success;
+                                    let $json_value: $json_ty = $crate::json::parse(&result.output);
+                                    $json_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    if !result.success {
+                                        eprintln!("{}", result.output);
+                                        panic!("Test {} subprocess failed", stringify!($test_name));
+                                    }
+                                    let $snapshot_output = result.output;
+                                    $snapshot_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $sections_success = result.success;
+                                    let $sections_output = result.boundary_sections;
+                                    $sections_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $crate::SubprocessResult {
+                                        success: $pid_success,
+                                        output: $pid_output,
+                                        pid: $pid_value,
+                                        ..
+                                    } = result;
+                                    $pid_block
+                                })?
+                                $(|result: $crate::SubprocessResult| {
+                                    let $crate::SubprocessResult {
+                                        success: $syscalls_success,
+                                        output: $syscalls_output,
+                                        syscall_trace: $syscalls_trace,
+                                        ..
+                                    } = result;
+                                    $syscalls_block
+                                })?
+                            } or {
+                                // NB: we inject closure here, to make panic report its location
+                                // at macro expansion
+                                |result: $crate::SubprocessResult| {
+                                    if !result.success {
+                                        eprintln!("{}", result.output);
+                                        if result.timed_out {
+                                            panic!("Test {} subprocess timed out", stringify!($test_name));
+                                        }
+                                        // Panic location points to macro, so actual location
+                                        // of subprocess panic is reported in message
+                                        if let Some((location, message)) = $crate::subprocess_panic(&result.output) {
+                                            panic!(
+                                                "Test {} subprocess failed, panicked at {location}:\n{message}",
+                                                stringify!($test_name),
+                                            );
+                                        }
+                                        // In case panic location will point to whole macro start,
+                                        // you'll get at least test name
+                                        panic!("Test {} subprocess failed", stringify!($test_name));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            )
+        }
+    };
+    (
+        @run [] $config:ident, $test_fn:expr, $verify_fn:expr
+    ) => {
+        $config.run($test_fn, $verify_fn)
+    };
+    (
+        @teardown [] $verify_fn:expr
+    ) => {
+        $verify_fn
+    };
+    (
+        @skip_updated_snapshot $verify_fn:expr
+    ) => {{
+        let verify_fn = $verify_fn;
+        // Snapshot is updated instead of running `verify` block
+        move |result: $crate::SubprocessResult| {
+            if result.snapshot_updated {
+                return $crate::skip::Skipped::skipped();
+            }
+            verify_fn(result)
+        }
+    }};
+    (
+        @teardown [$teardown_block:block] $verify_fn:expr
+    ) => {{
+        let verify_fn = $verify_fn;
+        let teardown_fn = || $teardown_block;
+        move |result: $crate::SubprocessResult| {
+            $crate::run_with_teardown(|| verify_fn(result), teardown_fn)
+        }
+    }};
+    (
+        @run [async] $config:ident, $test_fn:expr, $verify_fn:expr
+    ) => {
+        $config.run_async($test_fn, $verify_fn)
+    };
+    (
+        @body [] $test_block:block
+    ) => {
+        $test_block
+    };
+    (
+        @body [async] $test_block:block
+    ) => {
+        async move $test_block
+    };
+    (
+        @verify_fn [$($test_result:ty)?] |$result_param:ident| $verify_block:block
+    ) => {
+        |$result_param: $crate::SubprocessResult| {
+            $crate::subprocess_test!(@verify_body [$($test_result)?] $verify_block)
+        }
+    };
+    // Meaning of first parameter depends on its name, so it's passed twice:
+    // once to be matched against `exit_code`, and once to be bound
+    (
+        @verify_fn [$($test_result:ty)?] $($async:ident)? |$first_param:ident, $output_param:ident| $verify_block:block
+    ) => {
+        $crate::subprocess_test!(
+            @verify_fn_pair [$($test_result)?] $($async)? $first_param |$first_param, $output_param| $verify_block
+        )
+    };
+    (
+        @verify_fn_pair [$($test_result:ty)?] exit_code |$exit_code_param:ident, $output_param:ident| $verify_block:block
+    ) => {
+        |result: $crate::SubprocessResult| {
+            let $crate::SubprocessResult {
+                exit_code: $exit_code_param,
+                output: $output_param,
+                ..
+            } = result;
+            $crate::subprocess_test!(@verify_body [$($test_result)?] $verify_block)
+        }
+    };
+    (
+        @verify_fn_pair [$($test_result:ty)?] async exit_code |$exit_code_param:ident, $output_param:ident| $verify_block:block
+    ) => {
+        |result: $crate::SubprocessResult| {
+            let $crate::SubprocessResult {
+                exit_code: $exit_code_param,
+                output: $output_param,
+                ..
+            } = result;
+            $crate::block_on_async_verify!(async move {
+                $crate::subprocess_test!(@verify_body async [$($test_result)?] $verify_block)
+            })
+        }
+    };
+    (
+        @verify_fn_pair [$($test_result:ty)?] $_first_param:ident |$success_param:ident, $output_param:ident| $verify_block:block
+    ) => {
+        |result: $crate::SubprocessResult| {
+            let $crate::SubprocessResult {
+                success: $success_param,
+                output: $output_param,
+                ..
+            } = result;
+            $crate::subprocess_test!(@verify_body [$($test_result)?] $verify_block)
+        }
+    };
+    (
+        @verify_fn [$($test_result:ty)?] |$success_param:ident, $stdout_param:ident, $stderr_param:ident| $verify_block:block
+    ) => {
+        |result: $crate::SubprocessResult| {
+            let $crate::SubprocessResult {
+                success: $success_param,
+                stdout,
+                stderr,
+                ..
+            } = result;
+            let $stdout_param = stdout.expect("Stdout should be captured separately");
+            let $stderr_param = stderr.expect("Stderr should be captured separately");
+            $crate::subprocess_test!(@verify_body [$($test_result)?] $verify_block)
+        }
+    };
+    (
+        @verify_fn [$($test_result:ty)?] async |$result_param:ident| $verify_block:block
+    ) => {
+        |$result_param: $crate::SubprocessResult| {
+            $crate::block_on_async_verify!(async move {
+                $crate::subprocess_test!(@verify_body async [$($test_result)?] $verify_block)
+            })
+        }
+    };
+    (
+        @verify_fn_pair [$($test_result:ty)?] async $_first_param:ident |$success_param:ident, $output_param:ident| $verify_block:block
+    ) => {
+        |result: $crate::SubprocessResult| {
+            let $crate::SubprocessResult {
+                success: $success_param,
+                output: $output_param,
+                ..
+            } = result;
+            $crate::block_on_async_verify!(async move {
+                $crate::subprocess_test!(@verify_body async [$($test_result)?] $verify_block)
+            })
+        }
+    };
+    (
+        @verify_fn [$($test_result:ty)?] async |$success_param:ident, $stdout_param:ident, $stderr_param:ident| $verify_block:block
+    ) => {
+        |result: $crate::SubprocessResult| {
+            let $crate::SubprocessResult {
+                success: $success_param,
+                stdout,
+                stderr,
+                ..
+            } = result;
+            let $stdout_param = stdout.expect("Stdout should be captured separately");
+            let $stderr_param = stderr.expect("Stderr should be captured separately");
+            $crate::block_on_async_verify!(async move {
+                $crate::subprocess_test!(@verify_body async [$($test_result)?] $verify_block)
+            })
+        }
+    };
+    (
+        @split_output $config:ident |$_success:ident, $_stdout:ident, $_stderr:ident|
+    ) => {
+        $config.split_output(true)
+    };
+    (
+        @split_output $config:ident $($_:tt)*
+    ) => {
+        $config
+    };
+    // `verify_sections` block requires checkpoints to be enabled
+    (
+        @multi_boundary $config:ident $_sections_success:ident
+    ) => {
+        $config.multi_boundary(true)
+    };
+    // Errors returned by `?` from `verify` block of test without result type
+    // fail test, same as panic
+    (
+        @verify_body [] $verify_block:block
+    ) => {{
+        // Block may diverge, e.g. panic unconditionally
+        #[allow(unreachable_code)]
+        let verify = || -> ::std::result::Result<_, ::std::boxed::Box<dyn ::std::error::Error>> {
+            ::std::result::Result::Ok($verify_block)
+        };
+        match verify() {
+            ::std::result::Result::Ok(value) => value,
+            ::std::result::Result::Err(err) => panic!("Verify block failed: {err}"),
+        }
+    }};
+    (
+        @verify_body async [] $verify_block:block
+    ) => {{
+        // Block may diverge, e.g. panic unconditionally
+        #[allow(unreachable_code)]
+        let verify = async move {
+            ::std::result::Result::Ok::<_, ::std::boxed::Box<dyn ::std::error::Error>>($verify_block)
+        };
+        match verify.await {
+            ::std::result::Result::Ok(value) => value,
+            ::std::result::Result::Err(err) => panic!("Verify block failed: {err}"),
+        }
+    }};
+    // Otherwise `?` returns error as test result
+    (
+        @verify_body $(async)? [$_test_result:ty] $verify_block:block
+    ) => {
+        $verify_block
+    };
+    // Parameters which are checked at compile time, so they must be constant
+    (
+        @check_param output_boundary $boundary:expr
+    ) => {
+        $crate::subprocess_test!(@check_param boundary $boundary);
+    };
+    (
+        @check_param boundary $boundary:expr
+    ) => {
+        const _: () = assert!(
+            $crate::validate_boundary($boundary),
+            "`output_boundary` must be single line different from default boundary",
+        );
+    };
+    (
+        @check_param $_param_name:ident $_param_value:expr
+    ) => {};
+    // Defaults from manifest of crate where test is defined
+    (
+        @global_config
+    ) => {
+        $crate::config::GlobalConfig::get(
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")),
+            env!("CARGO_MANIFEST_DIR"),
+        )
+    };
+    (
+        @tokens_or_default { $($tokens:tt)+ } or { $($_:tt)* }
+    ) => {
+        $($tokens)+
+    };
+    (
+        @tokens_or_default { } or { $($tokens:tt)* }
+    ) => {
+        $($tokens)*
+    };
+}
+
+/// Variant of [`subprocess_test!`] for use inside build scripts.
+///
+/// Build scripts don't use test harness, so each function defined by this macro must be
+/// called explicitly from build script's `main`. When called, function re-runs build script
+/// binary with marker environment variable `__SUBPROCESS_BUILD_TEST__` set to test name.
+/// When re-run build script reaches same function, it executes test body and exits immediately,
+/// so the rest of build script isn't run in subprocess. Then parent passes subprocess result
+/// to `verify` block, which has same syntax and defaults as in [`subprocess_test!`].
+/// Failed test panics, which fails the build.
+///
+/// Tests are run each time build script runs, so its `cargo::rerun-if-changed` directives
+/// control when they're re-run. Any directives printed by subprocess are part of its output
+/// and aren't seen by Cargo.
+///
+/// ```rust,no_run
+/// // build.rs
+/// fn generate_bindings() -> String {
+///     "pub const ANSWER: u32 = 42;".to_owned()
+/// }
+///
+/// subprocess_test::build_subprocess_test! {
+///     fn bindings_are_generated() {
+///         print!("{}", generate_bindings());
+///     }
+///     verify |success, output| {
+///         assert!(success);
+///         assert!(output.contains("ANSWER"));
+///     }
+/// }
+///
+/// fn main() {
+///     println!("cargo::rerun-if-changed=build.rs");
+///     bindings_are_generated();
+///     // ... actual build script logic
+/// }
+/// ```
+#[macro_export]
+macro_rules! build_subprocess_test {
+    (
+        $(
+            $(#[doc = $doc_lit:literal])*
+            fn $test_name:ident () $test_block:block
+            $(verify |$($verify_param:ident),+| $verify_block:block)?
+        )*
+    ) => {
+        $(
+            $(#[doc = $doc_lit])*
+            fn $test_name() {
+                $crate::SubprocessTestConfig::new(
+                    concat!(module_path!(), "::", stringify!($test_name))
+                )
+                .global_config($crate::subprocess_test!(@global_config))
+                .manifest_dir(env!("CARGO_MANIFEST_DIR"))
+                .env_var_name("__SUBPROCESS_BUILD_TEST__")
+                .run_build_script(
                     || $test_block,
                     $crate::subprocess_test! {
                         @tokens_or_default {
-                            $(|$success_param, $stdout_param| $verify_block)?
+                            $($crate::subprocess_test! {
+                                @verify_fn [] |$($verify_param),+| $verify_block
+                            })?
                         } or {
-                            // NB: we inject closure here, to make panic report its location
-                            // at macro expansion
-                            |success, output| {
-                                if !success {
-                                    eprintln!("{output}");
-                                    // In case panic location will point to whole macro start,
-                                    // you'll get at least test name
-                                    panic!("Test {} subprocess failed", stringify!($test_name));
+                            |result: $crate::SubprocessResult| {
+                                if !result.success {
+                                    eprintln!("{}", result.output);
+                                    if let Some((location, message)) = $crate::subprocess_panic(&result.output) {
+                                        panic!(
+                                            "Build script test {} subprocess failed, panicked at {location}:\n{message}",
+                                            stringify!($test_name),
+                                        );
+                                    }
+                                    panic!("Build script test {} subprocess failed", stringify!($test_name));
                                 }
                             }
                         }
                     },
                 )
             }
-        )*
+        )*
+    };
+}
+
+/// Defines tests which run external binary as subprocess, rather than test body.
+///
+/// Instead of body, each test has `args` block, which evaluates to program arguments.
+/// Binary is specified by mandatory `binary` parameter, usually with path to binary
+/// built by same package, like `env!("CARGO_BIN_EXE_<name>")`. Other parameters of
+/// `subprocess_test!` apply too, unless they require cooperation from test body,
+/// like `on_output_line`. Output of binary is passed to `verify` block as is.
+/// Without `verify` block, test just checks that binary succeeded
+///
+/// ```rust
+/// subprocess_test::subprocess_test_external! {
+///     #[test(binary = "cargo")]
+///     fn cargo_version() args { ["--version"] }
+///     verify |success, output| {
+///         assert!(success);
+///         assert!(output.starts_with("cargo "));
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subprocess_test_external {
+    (
+        $(
+            $(#[doc = $doc_lit:literal])*
+            #[test($($param_name:ident = $param_value:expr),* $(,)?)]
+            $(#[$attrs:meta])*
+            fn $test_name:ident () args $args_block:block
+            $(verify |$($verify_param:ident),+| $verify_block:block)?
+        )*
+    ) => {
+        $(
+            $(#[doc = $doc_lit])*
+            #[test]
+            $(#[$attrs])*
+            fn $test_name() {
+                $($crate::subprocess_test!(@check_param $param_name $param_value);)*
+                let config = $crate::SubprocessTestConfig::new(
+                    concat!(module_path!(), "::", stringify!($test_name))
+                )
+                .global_config($crate::subprocess_test!(@global_config))
+                .manifest_dir(env!("CARGO_MANIFEST_DIR"))
+                $(.$param_name($param_value))*
+                .args($args_block);
+                let config = $crate::subprocess_test!(
+                    @split_output config $(|$($verify_param),+|)?
+                );
+                config.run_external($crate::subprocess_test! {
+                    @skip_updated_snapshot
+                    $crate::subprocess_test! {
+                        @tokens_or_default {
+                            $($crate::subprocess_test! {
+                                @verify_fn [] |$($verify_param),+| $verify_block
+                            })?
+                        } or {
+                            |result: $crate::SubprocessResult| {
+                                if !result.success {
+                                    eprintln!("{}", result.output);
+                                    panic!("Test {} binary failed", stringify!($test_name));
+                                }
+                            }
+                        }
+                    }
+                })
+            }
+        )*
+    };
+}
+
+/// Prints section mark, which splits subprocess output into named sections.
+///
+/// Must be called from inside subprocess test body. All output after the mark and up to
+/// either next mark or end of test's output is attributed to section with specified name.
+/// Marks themselves are always stripped from resulting output. Sections are available
+/// through [`SubprocessResult::sections`] if test is declared with `capture_sections = true`
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test(capture_sections = true)]
+///     fn sectioned() {
+///         subprocess_test::subprocess_mark!("setup");
+///         println!("Preparing");
+///         subprocess_test::subprocess_mark!("run");
+///         println!("Running");
+///     }
+///     verify |result| {
+///         assert!(result.success);
+///         assert_eq!(result.output, "Preparing\nRunning\n");
+///         assert_eq!(result.sections, [
+///             ("setup".to_owned(), "Preparing\n".to_owned()),
+///             ("run".to_owned(), "Running\n".to_owned()),
+///         ]);
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subprocess_mark {
+    ($section_name:expr) => {
+        $crate::print_section_mark($section_name)
+    };
+}
+
+/// Prints output boundary, which splits subprocess output into unnamed parts.
+///
+/// Must be called from inside subprocess test body of test declared with
+/// `multi_boundary = true` or having `verify_sections` block, does nothing otherwise.
+/// Parts are available through [`SubprocessResult::boundary_sections`], while
+/// [`SubprocessResult::output`] holds them joined together
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test]
+///     fn checkpointed() {
+///         println!("Before");
+///         subprocess_test::subprocess_checkpoint!();
+///         println!("After");
+///     }
+///     verify_sections |success, sections| {
+///         assert!(success);
+///         assert_eq!(sections, ["Before\n", "After\n"]);
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subprocess_checkpoint {
+    () => {
+        $crate::print_checkpoint()
+    };
+}
+
+/// Prints status message from subprocess test body to stderr of parent process.
+///
+/// Messages are passed to parent out of band, so they don't appear
+/// in [`SubprocessResult::output`]. Parent prints them after subprocess exits,
+/// in order they were sent, right before running `verify` block.
+/// This is useful to get progress report of long-running test, even if its output
+/// is checked in `verify` block.
+///
+/// When called outside of subprocess, message is printed to stderr directly.
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test]
+///     fn progress() {
+///         for step in 1..=3 {
+///             subprocess_test::subprocess_print_to_parent!(format!("step {step} complete"));
+///         }
+///         println!("Done");
+///     }
+///     verify |result| {
+///         assert_eq!(result.output, "Done\n");
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subprocess_print_to_parent {
+    ($message:expr) => {
+        $crate::print_to_parent(::std::convert::AsRef::as_ref(&$message))
+    };
+}
+
+/// Creates temporary directory, which is removed when returned [`TempDir`] is dropped.
+///
+/// Panics with descriptive message if directory can't be created.
+/// Optional `prefix` is prepended to directory name, which simplifies finding it while debugging.
+///
+/// ```rust
+/// let dir = subprocess_test::subprocess_tempdir!(prefix = "my_test_");
+/// assert!(dir.path().file_name().unwrap().to_str().unwrap().starts_with("my_test_"));
+/// ```
+#[macro_export]
+macro_rules! subprocess_tempdir {
+    () => {
+        $crate::create_tempdir(None)
+    };
+    (prefix = $prefix:expr $(,)?) => {
+        $crate::create_tempdir(Some($prefix))
+    };
+}
+
+#[doc(hidden)]
+pub fn create_tempdir(prefix: Option<&str>) -> TempDir {
+    let mut builder = tempfile::Builder::new();
+    if let Some(prefix) = prefix {
+        builder.prefix(prefix);
+    }
+    builder.tempdir().unwrap_or_else(|err| {
+        panic!(
+            "Failed to create temporary directory{} in {}: {err}",
+            prefix.map_or(String::new(), |prefix| format!(" with prefix {prefix:?}")),
+            std::env::temp_dir().display(),
+        )
+    })
+}
+
+/// Runs future returned by `verify async` block to completion
+#[cfg(feature = "tokio")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! block_on_async_verify {
+    ($future:expr) => {
+        $crate::block_on($future)
+    };
+}
+
+/// Runs future returned by `verify async` block to completion
+#[cfg(not(feature = "tokio"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! block_on_async_verify {
+    ($future:expr) => {
+        compile_error!("`verify async` blocks require `tokio` feature of `subprocess-test` crate")
+    };
+}
+
+#[cfg(feature = "tokio")]
+#[doc(hidden)]
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime for async verify block")
+        .block_on(future)
+}
+
+/// Runs `verify_fn`, then `teardown_fn`, even if `verify_fn` panicked.
+/// If both panic, panic of `verify_fn` is propagated, since it's the actual test failure
+#[doc(hidden)]
+pub fn run_with_teardown<R>(verify_fn: impl FnOnce() -> R, teardown_fn: impl FnOnce()) -> R {
+    use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+
+    let verified = catch_unwind(AssertUnwindSafe(verify_fn));
+    let torn_down = catch_unwind(AssertUnwindSafe(teardown_fn));
+    match (verified, torn_down) {
+        (Ok(result), Ok(())) => result,
+        (Err(payload), Ok(())) | (Ok(_), Err(payload)) => resume_unwind(payload),
+        (Err(payload), Err(_)) => {
+            eprintln!("Teardown block panicked too, while handling panic of verify block");
+            resume_unwind(payload)
+        }
+    }
+}
+
+/// Finds first panic report in subprocess output, like
+/// `thread 'name' (id) panicked at src/lib.rs:10:5:\nmessage`, and returns its location
+/// `src/lib.rs:10:5` along with message. Message ends before default hook's `note:` lines,
+/// backtrace or end of output
+#[doc(hidden)]
+pub fn subprocess_panic(output: &str) -> Option<(&str, &str)> {
+    const MARKER: &str = " panicked at ";
+
+    let start = output.find(MARKER)? + MARKER.len();
+    let (location, rest) = output[start..].split_once(":\n")?;
+    if location.contains('\n') {
+        return None;
+    }
+    let mut message_len = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.starts_with("note: ") || line.starts_with("stack backtrace:") {
+            break;
+        }
+        message_len += line.len();
+    }
+
+    Some((location, rest[..message_len].trim_end()))
+}
+
+const PRINT_TO_PARENT_RECORD: &str = "print-to-parent";
+
+#[doc(hidden)]
+pub fn print_to_parent(message: &str) {
+    if !side_channel::send(PRINT_TO_PARENT_RECORD, message) {
+        eprintln!("{message}");
+    }
+}
+
+const DRY_RUN_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_DRY_RUN";
+
+const VERBOSE_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_VERBOSE";
+
+const FLAKINESS_DETECT_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_FLAKINESS_DETECT";
+
+#[cfg(feature = "cpu-affinity")]
+const CPU_AFFINITY_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_CPU_AFFINITY";
+
+const NETWORK_TIMEOUT_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_NETWORK_TIMEOUT_MS";
+
+const PHASE_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_PHASE__";
+
+const TEMP_DIR_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_TEMPDIR";
+
+const UPDATE_BASELINE_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_UPDATE_BASELINE";
+
+const SNAPSHOT_UPDATE_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_UPDATE";
+
+/// Variables which tell enabled features, forwarded even when environment is cleared
+const CARGO_FEATURE_ENV_VAR_PREFIX: &str = "CARGO_FEATURE_";
+
+#[cfg(feature = "diff-output")]
+const GOLDEN_UPDATE_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_UPDATE_GOLDEN";
+
+const SECTION_MARK_PREFIX: &str = "\n---------------- subprocess_test section: ";
+
+/// Boundary printed by `subprocess_checkpoint!`, set only for tests with `multi_boundary = true`
+static CHECKPOINT_BOUNDARY: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+#[doc(hidden)]
+pub fn print_checkpoint() {
+    if let Some(boundary) = CHECKPOINT_BOUNDARY.get() {
+        print!("{boundary}");
+    }
+}
+
+#[doc(hidden)]
+pub fn print_section_mark(section_name: &str) {
+    assert!(
+        !section_name.contains('\n'),
+        "Section name must not contain newlines"
+    );
+    println!("{SECTION_MARK_PREFIX}{section_name}");
+}
+
+/// Returns network timeout configured for current subprocess test through
+/// `network_timeout_ms` parameter, in milliseconds.
+///
+/// Crate doesn't interfere with subprocess networking by itself. Instead, subprocess body
+/// is expected to use this value to configure its own connection and I/O timeouts.
+/// Returns `None` if parameter wasn't specified, or if called outside of subprocess
+///
+/// ```rust
+/// # use std::{net::TcpStream, time::Duration};
+/// subprocess_test::subprocess_test! {
+///     #[test(network_timeout_ms = 5000)]
+///     fn connects() {
+///         let timeout = subprocess_test::subprocess_network_timeout_ms()
+///             .map(Duration::from_millis)
+///             .unwrap();
+///         let _ = TcpStream::connect_timeout(&"127.0.0.1:8080".parse().unwrap(), timeout);
+///     }
+/// }
+/// ```
+pub fn subprocess_network_timeout_ms() -> Option<u64> {
+    var(NETWORK_TIMEOUT_ENV_VAR_NAME).ok()?.parse().ok()
+}
+
+/// Returns zero-based index of current phase for test declared with `phases = N` parameter.
+///
+/// Such test is run in subprocess `N` times sequentially, and body uses phase index
+/// to decide what to do, e.g. persist some state in first phase and check it in second one.
+/// Returns `0` for single-phase test, or if called outside of subprocess
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test(phases = 2)]
+///     fn state_persists() {
+///         let path = std::env::temp_dir().join("state_persists.txt");
+///         match subprocess_test::subprocess_phase() {
+///             0 => std::fs::write(&path, "state").unwrap(),
+///             _ => assert_eq!(std::fs::read_to_string(&path).unwrap(), "state"),
+///         }
+///     }
+///     verify |result| {
+///         assert!(result.success);
+///         assert_eq!(result.phases.len(), 2);
+///     }
+/// }
+/// ```
+pub fn subprocess_phase() -> usize {
+    var(PHASE_ENV_VAR_NAME)
+        .ok()
+        .and_then(|phase| phase.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Returns path of temporary directory shared by subprocess and `verify` block.
+///
+/// Directory is created by parent before subprocess is spawned, and its path is passed
+/// to subprocess through `SUBPROCESS_TEST_TEMPDIR` variable. Same directory is used
+/// by all phases and retries of subprocess, and `verify` block can find it
+/// as `SubprocessResult::temp_dir`. It's removed after `verify` block finishes.
+/// Panics if called outside of subprocess
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test]
+///     fn writes_report() {
+///         let path = subprocess_test::subprocess_temp_dir().join("report.txt");
+///         std::fs::write(path, "report").unwrap();
+///     }
+///     verify |result| {
+///         assert!(result.success);
+///         let path = result.temp_dir.join("report.txt");
+///         assert_eq!(std::fs::read_to_string(path).unwrap(), "report");
+///     }
+/// }
+/// ```
+pub fn subprocess_temp_dir() -> PathBuf {
+    var_os(TEMP_DIR_ENV_VAR_NAME)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| panic!("{TEMP_DIR_ENV_VAR_NAME} isn't set, not running in subprocess"))
+}
+
+/// Error reported by subprocess test hooks
+#[derive(Debug)]
+pub struct SubprocessTestError(Box<dyn Error + Send + Sync>);
+
+impl SubprocessTestError {
+    /// Creates error with specified message
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into().into())
+    }
+}
+
+impl Display for SubprocessTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for SubprocessTestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl From<io::Error> for SubprocessTestError {
+    fn from(err: io::Error) -> Self {
+        Self(err.into())
+    }
+}
+
+/// Hook which runs in parent process right before subprocess is spawned,
+/// specified through `pre_spawn_hook` parameter.
+///
+/// Useful for setup which requires parent's privileges, which subprocess may not have.
+/// If hook fails, subprocess isn't spawned and test panics
+pub type PreSpawnHook = fn() -> Result<(), SubprocessTestError>;
+
+/// Hook which runs in parent process after subprocess exits and before `verify` block,
+/// specified through `post_spawn_hook` parameter.
+///
+/// Usually used to clean up after [`PreSpawnHook`]. If hook fails, test panics
+pub type PostSpawnHook = fn() -> Result<(), SubprocessTestError>;
+
+/// Outcome of subprocess test execution, passed to `verify` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SubprocessResult {
+    /// `true` if subprocess exited with one of `success_codes`, only 0 by default,
+    /// or was killed by one of `success_signals`
+    pub success: bool,
+    /// Exit code of subprocess. `None` means subprocess was terminated by signal on Unix,
+    /// so it has no exit code, e.g. when it aborted or crashed, or it was killed on timeout
+    pub exit_code: Option<i32>,
+    /// Number of signal which terminated subprocess, like `6` for `SIGABRT` raised by
+    /// `std::process::abort()`. Always `None` on non-Unix platforms
+    pub signal: Option<i32>,
+    /// `true` if subprocess was killed because it exceeded `timeout_secs`
+    pub timed_out: bool,
+    /// `true` if subprocess printed more than `max_output_bytes`, so its output was cut
+    pub truncated: bool,
+    /// Subprocess output, both `stdout` and `stderr`, with boundaries and section marks removed.
+    /// If streams are captured separately, it's `stdout` followed by `stderr`,
+    /// since their relative order is lost
+    pub output: String,
+    /// Raw subprocess output, same as `output` but not converted from UTF-8 and without
+    /// section marks removal or trimming. Invalid UTF-8 is replaced in `output`
+    /// with replacement character
+    pub output_bytes: Vec<u8>,
+    /// Subprocess `stdout`, filled only when `verify` block takes three parameters
+    /// `|success, stdout, stderr|`, which requests separate capture of streams
+    pub stdout: Option<String>,
+    /// Subprocess `stderr`, same as `stdout`
+    pub stderr: Option<String>,
+    /// Pairs of section name and section output, in order of appearance.
+    /// Filled only when test is declared with `capture_sections = true`;
+    /// output which precedes first mark doesn't belong to any section
+    pub sections: Vec<(String, String)>,
+    /// Parts of output separated by `subprocess_checkpoint!`, in order of appearance.
+    /// Filled only when test is declared with `multi_boundary = true`
+    /// or uses `verify_sections` block. Parts are only converted from UTF-8,
+    /// without any other processing like trimming
+    pub boundary_sections: Vec<String>,
+    /// Wall-clock time subprocess took to run, from spawn until exit
+    pub duration: Duration,
+    /// Process ID of subprocess, `None` in dry run. Subprocess has already exited,
+    /// so ID may be reused by OS; it's only useful for after-the-fact analysis,
+    /// like looking for `core.<pid>` dump file
+    pub pid: Option<u32>,
+    /// Outcomes of all `subtest!` invocations, in order of execution
+    pub subtests: Vec<SubtestResult>,
+    /// Number of bytes subprocess read, filled only when test is declared with
+    /// `measure_io_bytes = true`. Linux only, `None` on other platforms
+    pub io_read_bytes: Option<u64>,
+    /// Number of bytes subprocess written, same as `io_read_bytes`
+    pub io_write_bytes: Option<u64>,
+    /// Non-fatal problems detected during run, like too many open file descriptors.
+    /// Each warning is also printed to `stderr`
+    pub warnings: Vec<String>,
+    /// Report of memory checker subprocess ran under, see `SUBPROCESS_TEST_VALGRIND` variable.
+    /// Empty if subprocess didn't run under checker. Report is printed to `stderr`
+    /// if subprocess failed
+    pub diagnostics: String,
+    /// System calls made by subprocess, filled only when test is declared with
+    /// `trace_syscalls = true`. Format is one of tracer, `strace` on Linux
+    /// or `dtrace` on macOS
+    pub syscall_trace: Option<String>,
+    /// Temporary directory shared by subprocess and `verify` block, see `subprocess_temp_dir`.
+    /// It's removed after `verify` block finishes
+    pub temp_dir: PathBuf,
+    /// Number of heap allocations made on test body's thread, filled only when test
+    /// is declared with `capture_allocations = true`. Requires `count-allocations` feature
+    pub alloc_count: Option<u64>,
+    /// Number of heap deallocations made by test body, same as `alloc_count`
+    pub dealloc_count: Option<u64>,
+    /// `true` if subprocess wasn't actually spawned because of `SUBPROCESS_TEST_DRY_RUN`,
+    /// so other fields describe fake successful run with empty output
+    pub dry_run: bool,
+    /// `true` if output was written into snapshot because of `SUBPROCESS_TEST_UPDATE`,
+    /// in which case `verify` block isn't run
+    pub snapshot_updated: bool,
+    /// Results of all subprocess runs, in order, for test declared with `phases = N`.
+    /// Other fields are taken from last phase, except `success`,
+    /// which is `true` only if all phases succeeded. Empty for single-phase test
+    pub phases: Vec<SubprocessResult>,
+}
+
+#[doc(hidden)]
+#[deprecated(note = "use `SubprocessTestConfig` builder instead")]
+pub fn run_subprocess_test<R>(
+    full_test_name: &str,
+    var_name: Option<&str>,
+    boundary: Option<&str>,
+    working_dir: Option<&Path>,
+    extra_args: &[&str],
+    test_fn: impl FnOnce() -> R,
+    verify_fn: impl FnOnce(Option<i32>, String) -> R,
+) -> R {
+    legacy_config(full_test_name, var_name, boundary, working_dir, extra_args)
+        .run(test_fn, |result| verify_fn(result.exit_code, result.output))
+}
+
+/// Same as `run_subprocess_test`, but `verify_fn` also receives subprocess ID
+#[doc(hidden)]
+#[deprecated(note = "use `SubprocessTestConfig` builder instead")]
+pub fn run_subprocess_test_with_pid<R>(
+    full_test_name: &str,
+    var_name: Option<&str>,
+    boundary: Option<&str>,
+    working_dir: Option<&Path>,
+    extra_args: &[&str],
+    test_fn: impl FnOnce() -> R,
+    verify_fn: impl FnOnce(Option<i32>, String, Option<u32>) -> R,
+) -> R {
+    legacy_config(full_test_name, var_name, boundary, working_dir, extra_args)
+        .run(test_fn, |result| {
+            verify_fn(result.exit_code, result.output, result.pid)
+        })
+}
+
+/// Same as `run_subprocess_test`, but `verify_fn` receives output as iterator over lines
+#[doc(hidden)]
+#[deprecated(note = "use `SubprocessTestConfig` builder instead")]
+pub fn run_subprocess_test_lines<R>(
+    full_test_name: &str,
+    var_name: Option<&str>,
+    boundary: Option<&str>,
+    working_dir: Option<&Path>,
+    extra_args: &[&str],
+    test_fn: impl FnOnce() -> R,
+    verify_fn: impl FnOnce(bool, OutputLines<'_>) -> R,
+) -> R {
+    legacy_config(full_test_name, var_name, boundary, working_dir, extra_args)
+        .run(test_fn, |result| {
+            verify_fn(result.success, OutputLines::new(&result.output))
+        })
+}
+
+/// Same as `run_subprocess_test`, but `line_fn` receives each output line while subprocess runs
+#[doc(hidden)]
+#[deprecated(note = "use `SubprocessTestConfig` builder instead")]
+#[allow(clippy::too_many_arguments)]
+pub fn run_subprocess_test_streaming<R>(
+    full_test_name: &str,
+    var_name: Option<&str>,
+    boundary: Option<&str>,
+    working_dir: Option<&Path>,
+    extra_args: &[&str],
+    line_fn: impl FnMut(&str) + 'static,
+    test_fn: impl FnOnce() -> R,
+    verify_fn: impl FnOnce(Option<i32>, String) -> R,
+) -> R {
+    // Callback is called only from thread which waits for subprocess
+    let line_fn = std::cell::RefCell::new(line_fn);
+    legacy_config(full_test_name, var_name, boundary, working_dir, extra_args)
+        .on_output_line(move |line| (line_fn.borrow_mut())(line))
+        .run(test_fn, |result| verify_fn(result.exit_code, result.output))
+}
+
+fn legacy_config(
+    full_test_name: &str,
+    var_name: Option<&str>,
+    boundary: Option<&str>,
+    working_dir: Option<&Path>,
+    extra_args: &[&str],
+) -> SubprocessTestConfig {
+    let mut config = SubprocessTestConfig::new(full_test_name);
+    if let Some(var_name) = var_name {
+        config = config.env_var_name(var_name);
+    }
+    if let Some(boundary) = boundary {
+        config = config.output_boundary(boundary);
+    }
+    if let Some(working_dir) = working_dir {
+        config = config.working_dir(working_dir);
+    }
+    config.cargo_args(extra_args)
+}
+
+const DEFAULT_OUTPUT_BOUNDARY: &str = "\n========================================\n";
+
+/// Checks that custom output boundary can be told apart from output. Boundary is surrounded
+/// with newlines when printed, so it can't contain them itself, and it must differ from
+/// default boundary. Checked by `subprocess_test!` at compile time, inside test function:
+///
+/// ```compile_fail,E0080
+/// subprocess_test::subprocess_test!(
+///     @check_param output_boundary "========================================\n"
+/// );
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail,E0080
+/// subprocess_test::subprocess_test!(
+///     @check_param output_boundary "========================================"
+/// );
+/// # fn main() {}
+/// ```
+///
+/// ```
+/// subprocess_test::subprocess_test!(@check_param output_boundary "CUSTOM");
+/// # fn main() {}
+/// ```
+#[doc(hidden)]
+pub const fn validate_boundary(boundary: &str) -> bool {
+    let boundary = boundary.as_bytes();
+    let mut i = 0;
+    while i < boundary.len() {
+        if boundary[i] == b'\n' || boundary[i] == b'\r' {
+            return false;
+        }
+        i += 1;
+    }
+    // Default boundary without its surrounding newlines
+    let default = DEFAULT_OUTPUT_BOUNDARY.as_bytes();
+    if boundary.len() != default.len() - 2 {
+        return true;
+    }
+    let mut i = 0;
+    while i < boundary.len() {
+        if boundary[i] != default[i + 1] {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Subprocess test parameters. Each parameter specified in `#[test(...)]` attribute
+/// is passed to same-named method, so new method becomes macro parameter right away.
+/// Can also be used directly from normal test function, in place of `run_subprocess_test`:
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use subprocess_test::SubprocessTestConfig;
+///
+/// #[test]
+/// fn direct() {
+///     SubprocessTestConfig::new(concat!(module_path!(), "::direct"))
+///         .env_var("DIRECT_SUBPROCESS")
+///         .boundary("<direct>")
+///         .timeout(Duration::from_secs(10))
+///         .clear_env(true)
+///         .run(
+///             || println!("Body"),
+///             |result| {
+///                 assert!(result.success);
+///                 assert_eq!(result.output, "Body\n");
+///             },
+///         );
+/// }
+/// ```
+pub struct SubprocessTestConfig {
+    full_test_name: String,
+    env_var_name: Cow<'static, str>,
+    output_boundary: Cow<'static, str>,
+    // Full test path including crate name, for JSON report and snapshot file name
+    test_path: String,
+    capture_sections: bool,
+    multi_boundary: bool,
+    expect_failure: ExpectFailure,
+    expect_lines: Option<usize>,
+    verbose: bool,
+    inherit_env_prefix: Option<String>,
+    clear_env: bool,
+    inherit_env: Vec<String>,
+    forward_env_prefix: Option<String>,
+    cargo_args: Vec<String>,
+    snapshot_env: Vec<String>,
+    success_codes: Vec<i32>,
+    success_signals: Vec<i32>,
+    binary: Option<PathBuf>,
+    binary_args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    network_timeout_ms: Option<u64>,
+    oom_score_adj: Option<i32>,
+    pre_spawn_hook: Option<PreSpawnHook>,
+    post_spawn_hook: Option<PostSpawnHook>,
+    regression_threshold_pct: Option<u64>,
+    baseline_runs: usize,
+    build_script: bool,
+    output_trim: OutputTrim,
+    normalize_line_endings: bool,
+    strip_backtrace: bool,
+    normalize_paths: bool,
+    deduplicate_output: bool,
+    manifest_dir: Option<&'static str>,
+    join_threads: bool,
+    split_output: bool,
+    timeout: Option<Duration>,
+    max_duration: Option<Duration>,
+    graceful_shutdown: Option<Duration>,
+    max_output_bytes: Option<u64>,
+    retry: usize,
+    retry_delay: Duration,
+    working_dir: Option<PathBuf>,
+    stdin: Option<Vec<u8>>,
+    runtime: AsyncRuntime,
+    phases: usize,
+    output_from_env: Option<String>,
+    measure_io_bytes: bool,
+    trace_syscalls: bool,
+    max_open_fds: Option<usize>,
+    fail_on_fd_leak: bool,
+    plugins: PluginChain,
+    on_output_line: Option<LineCallback<'static>>,
+    normalizers: Vec<Box<dyn Fn(String) -> String>>,
+    control: Option<ControlFn>,
+    fake_binary: Option<String>,
+    fake_binary_output: String,
+    fake_binary_exit_code: i32,
+    #[cfg(feature = "regex")]
+    verify_matches: Option<regex::Regex>,
+    #[cfg(feature = "diff-output")]
+    verify_golden: Option<PathBuf>,
+    snapshot: bool,
+    #[cfg(feature = "strip-ansi")]
+    strip_ansi: bool,
+    #[cfg(feature = "rlimit")]
+    rlimit_as: Option<u64>,
+    #[cfg(feature = "count-allocations")]
+    capture_allocations: bool,
+    #[cfg(all(unix, feature = "low-level-io"))]
+    capture_fds: Vec<std::os::fd::RawFd>,
+    #[cfg(feature = "cpu-affinity")]
+    cpu_affinity: Vec<usize>,
+    #[cfg(feature = "context")]
+    context: Option<String>,
+    #[cfg(all(unix, feature = "chaos-testing"))]
+    fault: Option<chaos::Fault>,
+}
+
+/// Expected outcome of subprocess, see `expect_failure` parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectFailure {
+    /// Subprocess is expected to succeed, default
+    No,
+    /// Subprocess is expected to fail in any way
+    Any,
+    /// Subprocess is expected to exit with specified code
+    ExitCode(i32),
+}
+
+impl From<bool> for ExpectFailure {
+    fn from(expect: bool) -> Self {
+        if expect { Self::Any } else { Self::No }
+    }
+}
+
+impl From<i32> for ExpectFailure {
+    fn from(code: i32) -> Self {
+        Self::ExitCode(code)
+    }
+}
+
+/// Whitespace trimming applied to captured output, see `output_trim` parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputTrim {
+    None,
+    Start,
+    End,
+    Both,
+}
+
+impl OutputTrim {
+    fn apply(self, output: String) -> String {
+        match self {
+            Self::None => output,
+            Self::Start => output.trim_start().to_owned(),
+            Self::End => output.trim_end().to_owned(),
+            Self::Both => output.trim().to_owned(),
+        }
+    }
+}
+
+impl SubprocessTestConfig {
+    /// Creates config with default parameters for test with full path `crate::module::name`,
+    /// as produced by `concat!(module_path!(), "::", stringify!(name))`
+    pub fn new(full_test_name: &str) -> Self {
+        const DEFAULT_SUBPROCESS_ENV_VAR_NAME: &str = "__TEST_RUN_SUBPROCESS__";
+
+        let test_path = full_test_name.to_owned();
+        let full_test_name = &full_test_name[full_test_name
+            .find("::")
+            .expect("Full test path is expected to include crate name")
+            + 2..];
+
+        Self {
+            full_test_name: full_test_name.to_owned(),
+            test_path,
+            env_var_name: DEFAULT_SUBPROCESS_ENV_VAR_NAME.into(),
+            output_boundary: DEFAULT_OUTPUT_BOUNDARY.into(),
+            capture_sections: false,
+            multi_boundary: false,
+            expect_failure: ExpectFailure::No,
+            expect_lines: None,
+            verbose: false,
+            inherit_env_prefix: None,
+            clear_env: false,
+            inherit_env: Vec::new(),
+            forward_env_prefix: None,
+            cargo_args: Vec::new(),
+            snapshot_env: Vec::new(),
+            success_codes: vec![0],
+            success_signals: Vec::new(),
+            binary: None,
+            binary_args: Vec::new(),
+            envs: Vec::new(),
+            network_timeout_ms: None,
+            oom_score_adj: None,
+            pre_spawn_hook: None,
+            post_spawn_hook: None,
+            regression_threshold_pct: None,
+            baseline_runs: 10,
+            build_script: false,
+            output_trim: OutputTrim::None,
+            normalize_line_endings: cfg!(windows),
+            strip_backtrace: false,
+            normalize_paths: false,
+            deduplicate_output: false,
+            manifest_dir: None,
+            join_threads: false,
+            split_output: false,
+            timeout: None,
+            max_duration: None,
+            graceful_shutdown: None,
+            max_output_bytes: None,
+            retry: 0,
+            retry_delay: Duration::ZERO,
+            working_dir: None,
+            stdin: None,
+            runtime: AsyncRuntime::Tokio,
+            phases: 1,
+            output_from_env: None,
+            measure_io_bytes: false,
+            trace_syscalls: false,
+            max_open_fds: None,
+            fail_on_fd_leak: false,
+            plugins: PluginChain::new(),
+            on_output_line: None,
+            normalizers: Vec::new(),
+            control: None,
+            fake_binary: None,
+            fake_binary_output: String::new(),
+            fake_binary_exit_code: 0,
+            #[cfg(feature = "regex")]
+            verify_matches: None,
+            #[cfg(feature = "diff-output")]
+            verify_golden: None,
+            snapshot: false,
+            #[cfg(feature = "strip-ansi")]
+            strip_ansi: false,
+            #[cfg(feature = "rlimit")]
+            rlimit_as: None,
+            #[cfg(feature = "count-allocations")]
+            capture_allocations: false,
+            #[cfg(all(unix, feature = "low-level-io"))]
+            capture_fds: Vec::new(),
+            #[cfg(feature = "cpu-affinity")]
+            cpu_affinity: Vec::new(),
+            #[cfg(feature = "context")]
+            context: None,
+            #[cfg(all(unix, feature = "chaos-testing"))]
+            fault: None,
+        }
+    }
+
+    /// Applies defaults from `Cargo.toml` metadata section. Called by macro
+    /// before test parameters, so they override defaults
+    #[doc(hidden)]
+    pub fn global_config(mut self, global: &config::GlobalConfig) -> Self {
+        if let Some(name) = &global.env_var_name {
+            self = self.env_var_name(name);
+        }
+        if let Some(boundary) = &global.output_boundary {
+            self = self.output_boundary(boundary);
+        }
+        self
+    }
+
+    /// Name of environment variable which marks subprocess mode,
+    /// `__TEST_RUN_SUBPROCESS__` by default
+    pub fn env_var_name(mut self, name: &str) -> Self {
+        self.env_var_name = name.to_owned().into();
+        self
+    }
+
+    /// Line printed before and after test output to tell it apart from test harness output.
+    /// Must be single line different from default boundary
+    pub fn output_boundary(mut self, boundary: &str) -> Self {
+        self.output_boundary = format!("\n{boundary}\n").into();
+        self
+    }
+
+    /// Same as `env_var_name`
+    pub fn env_var(self, name: &str) -> Self {
+        self.env_var_name(name)
+    }
+
+    /// Same as `output_boundary`
+    pub fn boundary(self, line: &str) -> Self {
+        self.output_boundary(line)
+    }
+
+    /// Collects output split by `subprocess_mark!` into `SubprocessResult::sections`
+    pub fn capture_sections(mut self, capture: bool) -> Self {
+        self.capture_sections = capture;
+        self
+    }
+
+    /// Expects subprocess to fail, either in any way with `true`, or with specified exit code
+    pub fn expect_failure(mut self, expect: impl Into<ExpectFailure>) -> Self {
+        self.expect_failure = expect.into();
+        self
+    }
+
+    /// Echoes subprocess output lines to `stderr` as they arrive, prefixed with test name
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Checks that subprocess succeeded and printed specified number of lines,
+    /// before `verify_fn` is called
+    pub fn expect_lines(mut self, count: usize) -> Self {
+        self.expect_lines = Some(count);
+        self
+    }
+
+    /// Splits output by `subprocess_checkpoint!` into `SubprocessResult::boundary_sections`
+    pub fn multi_boundary(mut self, multi: bool) -> Self {
+        self.multi_boundary = multi;
+        self
+    }
+
+    /// Clears subprocess environment, except variables whose names start with `prefix`
+    pub fn inherit_env_prefix(mut self, prefix: &str) -> Self {
+        self.inherit_env_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Clears subprocess environment completely. Marker variable and `CARGO_FEATURE_*`
+    /// variables are still passed
+    pub fn clear_env(mut self, clear: bool) -> Self {
+        self.clear_env = clear;
+        self
+    }
+
+    /// Variables passed from parent when environment is cleared
+    pub fn inherit_env<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.inherit_env
+            .extend(names.into_iter().map(|name| name.as_ref().to_owned()));
+        self
+    }
+
+    /// Passes variables whose names start with `prefix` when environment is cleared.
+    /// Unlike `inherit_env_prefix`, doesn't clear environment by itself
+    pub fn forward_env_prefix(mut self, prefix: &str) -> Self {
+        self.forward_env_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Same as `inherit_env`
+    pub fn forward_env<S: AsRef<str>>(self, names: impl IntoIterator<Item = S>) -> Self {
+        self.inherit_env(names)
+    }
+
+    /// Variables set for subprocess, applied after environment is cleared
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(
+        mut self,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.envs.extend(
+            vars.into_iter()
+                .map(|(key, value)| (key.as_ref().to_owned(), value.as_ref().to_owned())),
+        );
+        self
+    }
+
+    /// Extra arguments for test harness of subprocess, appended after ones which select test
+    pub fn cargo_args<S: AsRef<str>>(mut self, args: impl IntoIterator<Item = S>) -> Self {
+        self.cargo_args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// Variables restored after test body runs in subprocess, even if it panics
+    pub fn snapshot_env<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.snapshot_env
+            .extend(names.into_iter().map(|name| name.as_ref().to_owned()));
+        self
+    }
+
+    /// Exit codes which count as success, only 0 by default
+    pub fn success_codes(mut self, codes: impl IntoIterator<Item = i32>) -> Self {
+        self.success_codes = codes.into_iter().collect();
+        self
+    }
+
+    /// Unix only. Signals which count as success if subprocess was killed by them
+    pub fn success_signals(mut self, signals: impl IntoIterator<Item = i32>) -> Self {
+        self.success_signals = signals.into_iter().collect();
+        self
+    }
+
+    /// External binary run instead of test executable, see [`run_external`](Self::run_external)
+    pub fn binary(mut self, path: impl AsRef<Path>) -> Self {
+        self.binary = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Arguments of external binary specified by `binary`
+    pub fn args<S: AsRef<OsStr>>(mut self, args: impl IntoIterator<Item = S>) -> Self {
+        self.binary_args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// `true` if test runs in subprocess mode, i.e. its body should be executed.
+    /// Marker variable set for some other test means that this test is nested into
+    /// that test's body, so it spawns its own subprocess
+    pub fn is_subprocess(&self) -> bool {
+        var_os(&*self.env_var_name).is_some_and(|name| name == *self.full_test_name)
+    }
+
+    /// `true` if test should be skipped, because tool it should run under isn't found.
+    /// Such tools are Valgrind, requested by `SUBPROCESS_TEST_VALGRIND`,
+    /// and syscall tracer, requested by `trace_syscalls`
+    #[doc(hidden)]
+    pub fn missing_tool(&self) -> bool {
+        if self.binary.is_none()
+            && !self.build_script
+            && env_flag(valgrind::VALGRIND_ENV_VAR_NAME)
+            && self.valgrind().is_none()
+        {
+            eprintln!(
+                "{} is set, but valgrind isn't found in PATH",
+                valgrind::VALGRIND_ENV_VAR_NAME
+            );
+            return true;
+        }
+        if self.trace_syscalls && self.syscall_tracer().is_none() {
+            match syscalls::TRACER {
+                Some(tracer) => {
+                    eprintln!("`trace_syscalls` requires {tracer}, which isn't found in PATH")
+                }
+                None => eprintln!("`trace_syscalls` isn't supported on this platform"),
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Applies overrides made by `setup` block
+    pub fn setup_context(mut self, context: &SubprocessContext) -> Self {
+        self.envs.extend(context.envs.iter().cloned());
+        if let Some(working_dir) = &context.working_dir {
+            self.working_dir = Some(working_dir.clone());
+        }
+        self
+    }
+
+    /// Passes fixture directory path to subprocess, see `fixture` block
+    pub fn fixture(mut self, fixture: &SubprocessFixture) -> Self {
+        self.envs.push((
+            fixture::FIXTURE_DIR_ENV_VAR_NAME.into(),
+            fixture.path().into(),
+        ));
+        self
+    }
+
+    /// Network timeout passed to subprocess, see [`subprocess_network_timeout_ms`]
+    pub fn network_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.network_timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Linux only. OOM killer score adjustment of subprocess, in range -1000..=1000
+    pub fn oom_score_adj(mut self, score_adj: i32) -> Self {
+        assert!(
+            (-1000..=1000).contains(&score_adj),
+            "oom_score_adj must be in range -1000..=1000, got {score_adj}"
+        );
+        self.oom_score_adj = Some(score_adj);
+        self
+    }
+
+    /// Function run in parent right before subprocess is spawned
+    pub fn pre_spawn_hook(mut self, hook: PreSpawnHook) -> Self {
+        self.pre_spawn_hook = Some(hook);
+        self
+    }
+
+    /// Function run in parent right after subprocess exits
+    pub fn post_spawn_hook(mut self, hook: PostSpawnHook) -> Self {
+        self.post_spawn_hook = Some(hook);
+        self
+    }
+
+    /// Prints warning if successful run is slower than mean of previous runs
+    /// by more than specified percentage
+    pub fn regression_threshold_pct(mut self, threshold_pct: u64) -> Self {
+        self.regression_threshold_pct = Some(threshold_pct);
+        self
+    }
+
+    /// Number of previous successful runs kept for `regression_threshold_pct`, 10 by default
+    pub fn baseline_runs(mut self, runs: usize) -> Self {
+        self.baseline_runs = runs;
+        self
+    }
+
+    /// Replaces `\r\n` in captured output with `\n`. Enabled by default on Windows only
+    pub fn normalize_line_endings(mut self, normalize: bool) -> Self {
+        self.normalize_line_endings = normalize;
+        self
+    }
+
+    /// Removes panic backtraces and `RUST_BACKTRACE` hints from captured output
+    pub fn strip_backtrace(mut self, strip: bool) -> Self {
+        self.strip_backtrace = strip;
+        self
+    }
+
+    /// Makes absolute paths inside workspace relative to workspace root in captured output
+    pub fn normalize_paths(mut self, normalize: bool) -> Self {
+        self.normalize_paths = normalize;
+        self
+    }
+
+    /// Replaces runs of consecutive identical lines in captured output with single line
+    pub fn deduplicate_output(mut self, deduplicate: bool) -> Self {
+        self.deduplicate_output = deduplicate;
+        self
+    }
+
+    /// Directory of crate where test is defined. Set by macro
+    #[doc(hidden)]
+    pub fn manifest_dir(mut self, dir: &'static str) -> Self {
+        self.manifest_dir = Some(dir);
+        self
+    }
+
+    /// Joins threads spawned by [`subprocess_spawn`] after test body finishes
+    pub fn join_threads(mut self, join: bool) -> Self {
+        self.join_threads = join;
+        self
+    }
+
+    /// Trims whitespace from captured output, either `"start"`, `"end"` or `"both"`
+    pub fn output_trim(mut self, trim: &str) -> Self {
+        self.output_trim = match trim {
+            "start" => OutputTrim::Start,
+            "end" => OutputTrim::End,
+            "both" => OutputTrim::Both,
+            _ => panic!("output_trim must be one of \"start\", \"end\", \"both\", got {trim:?}"),
+        };
+        self
+    }
+
+    /// Runs subprocess specified number of times sequentially, see [`subprocess_phase`]
+    pub fn phases(mut self, phases: usize) -> Self {
+        assert!(phases > 0, "Test must have at least one phase");
+        self.phases = phases;
+        self
+    }
+
+    /// Async runtime which runs test body for [`run_async`](Self::run_async)
+    pub fn runtime(mut self, runtime: &str) -> Self {
+        self.runtime = AsyncRuntime::parse(runtime);
+        self
+    }
+
+    /// Data fed into subprocess `stdin`, which is empty by default
+    pub fn stdin(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.stdin = Some(data.as_ref().to_owned());
+        self
+    }
+
+    /// Working directory of subprocess, which must exist
+    pub fn working_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.working_dir = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Same as `timeout`, in seconds
+    pub fn timeout_secs(self, timeout_secs: u64) -> Self {
+        self.timeout(Duration::from_secs(timeout_secs))
+    }
+
+    /// Kills subprocess if it doesn't finish in time, and sets `SubprocessResult::timed_out`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Same as `max_duration`, in seconds
+    pub fn max_duration_secs(self, max_secs: f64) -> Self {
+        self.max_duration(Duration::from_secs_f64(max_secs))
+    }
+
+    /// Fails test if subprocess took longer than specified, even if it succeeded
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Unix only. On timeout, sends `SIGTERM` and waits specified time before killing
+    pub fn graceful_shutdown_ms(mut self, grace_ms: u64) -> Self {
+        self.graceful_shutdown = Some(Duration::from_millis(grace_ms));
+        self
+    }
+
+    /// Keeps at most specified number of bytes of each captured output stream
+    pub fn max_output_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_output_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Re-runs failed subprocess up to specified number of times
+    pub fn retry(mut self, retries: usize) -> Self {
+        self.retry = retries;
+        self
+    }
+
+    /// Wait between retry attempts
+    pub fn retry_delay_ms(mut self, delay_ms: u64) -> Self {
+        self.retry_delay = Duration::from_millis(delay_ms);
+        self
+    }
+
+    /// Captures `stdout` and `stderr` separately, into `SubprocessResult::stdout` and `stderr`
+    pub fn split_output(mut self, split: bool) -> Self {
+        self.split_output = split;
+        self
+    }
+
+    /// Creates executable with specified name in temporary directory first in `PATH`
+    pub fn fake_binary(mut self, name: &str) -> Self {
+        self.fake_binary = Some(name.to_owned());
+        self
+    }
+
+    /// Output printed by `fake_binary`, empty by default
+    pub fn fake_binary_output(mut self, output: &str) -> Self {
+        self.fake_binary_output = output.to_owned();
+        self
+    }
+
+    /// Exit code of `fake_binary`, 0 by default
+    pub fn fake_binary_exit_code(mut self, exit_code: i32) -> Self {
+        self.fake_binary_exit_code = exit_code;
+        self
+    }
+
+    /// Callback called in parent for each line of output as soon as it's printed
+    pub fn on_output_line(mut self, callback: impl Fn(&str) + 'static) -> Self {
+        self.on_output_line = Some(Box::new(callback));
+        self
+    }
+
+    /// Adds function which transforms captured output before it's passed to `verify` block,
+    /// see `normalize` block. Functions are applied in order they're added
+    pub fn normalize(mut self, normalize: impl Fn(String) -> String + 'static) -> Self {
+        self.normalizers.push(Box::new(normalize));
+        self
+    }
+
+    /// Sets function run in parent while subprocess runs, see `control` block.
+    /// Subprocess doesn't exit after test body returns until it's stopped
+    pub fn control(mut self, control: impl Fn(&SubprocessControl) + 'static) -> Self {
+        self.control = Some(Box::new(control));
+        self
+    }
+
+    /// Adds plugin called around subprocess execution, see [`SubprocessTestPlugin`]
+    pub fn plugin(mut self, plugin: impl SubprocessTestPlugin + 'static) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
+    /// Same as `plugin`
+    pub fn with_plugin(self, plugin: impl SubprocessTestPlugin + 'static) -> Self {
+        self.plugin(plugin)
+    }
+
+    /// Lets plugins adjust parameters before subprocess command is prepared
+    fn pre_spawn_plugins(mut self) -> Self {
+        let plugins = std::mem::take(&mut self.plugins);
+        let mut config = plugins.pre_spawn(self);
+        let added = std::mem::replace(&mut config.plugins, plugins);
+        config.plugins.append(added);
+        config
+    }
+
+    /// Unix only. Warns if subprocess has more open file descriptors when test body ends
+    pub fn max_open_fds(mut self, max_fds: usize) -> Self {
+        self.max_open_fds = Some(max_fds);
+        self
+    }
+
+    /// Fails test instead of warning when `max_open_fds` is exceeded
+    pub fn fail_on_fd_leak(mut self, fail: bool) -> Self {
+        self.fail_on_fd_leak = fail;
+        self
+    }
+
+    /// Linux only. Collects number of bytes subprocess read and written
+    pub fn measure_io_bytes(mut self, measure: bool) -> Self {
+        self.measure_io_bytes = measure;
+        self
+    }
+
+    /// Linux and macOS only. Runs subprocess under syscall tracer,
+    /// see `SubprocessResult::syscall_trace`
+    pub fn trace_syscalls(mut self, trace: bool) -> Self {
+        self.trace_syscalls = trace;
+        self
+    }
+
+    /// Subprocess writes its output into file whose path is passed through specified variable,
+    /// instead of `stdout` and `stderr`
+    pub fn output_from_env(mut self, var_name: &str) -> Self {
+        self.output_from_env = Some(var_name.to_owned());
+        self
+    }
+
+    /// Counts heap allocations made by test body. Requires `count-allocations` feature
+    #[cfg(feature = "count-allocations")]
+    pub fn capture_allocations(mut self, capture: bool) -> Self {
+        self.capture_allocations = capture;
+        self
+    }
+
+    /// Checks that subprocess succeeded and its output matches regular expression.
+    /// Requires `regex` feature
+    #[cfg(feature = "regex")]
+    pub fn verify_matches(mut self, pattern: &str) -> Self {
+        let regex = regex::Regex::new(pattern).unwrap_or_else(|err| {
+            panic!(
+                "Invalid `verify_matches` pattern for test {}: {err}",
+                self.full_test_name
+            )
+        });
+        self.verify_matches = Some(regex);
+        self
+    }
+
+    /// Compares output with snapshot in `tests/subprocess_snapshots`, see `SUBPROCESS_TEST_UPDATE`
+    pub fn snapshot(mut self, enabled: bool) -> Self {
+        self.snapshot = enabled;
+        self
+    }
+
+    /// Path to file with expected output, relative to directory of crate where test is defined
+    #[cfg(feature = "diff-output")]
+    pub fn verify_golden(mut self, path: &str) -> Self {
+        self.verify_golden = Some(path.into());
+        self
+    }
+
+    /// Removes ANSI escape sequences from captured output. Requires `strip-ansi` feature
+    #[cfg(feature = "strip-ansi")]
+    pub fn strip_ansi(mut self, strip: bool) -> Self {
+        self.strip_ansi = strip;
+        self
+    }
+
+    /// Limits memory subprocess can use, in bytes. Requires `rlimit` feature
+    #[cfg(feature = "rlimit")]
+    pub fn rlimit_as(mut self, limit_bytes: u64) -> Self {
+        self.rlimit_as = Some(limit_bytes);
+        self
+    }
+
+    /// Same as `rlimit_as`, in megabytes
+    #[cfg(feature = "rlimit")]
+    pub fn memory_limit_mb(self, limit_mb: u64) -> Self {
+        self.rlimit_as(limit_mb.saturating_mul(1024 * 1024))
+    }
+
+    /// Unix only. Captures output written to specified non-standard file descriptor.
+    /// Requires `low-level-io` feature
+    #[cfg(all(unix, feature = "low-level-io"))]
+    pub fn capture_fd(mut self, fd: std::os::fd::RawFd) -> Self {
+        assert!(
+            fd > 2,
+            "capture_fd is intended for non-standard descriptors, got {fd}"
+        );
+        self.capture_fds.push(fd);
+        self
+    }
+
+    /// Pins subprocess to specified CPU cores. Requires `cpu-affinity` feature
+    #[cfg(feature = "cpu-affinity")]
+    pub fn cpu_affinity(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.cpu_affinity = cores.into_iter().collect();
+        assert!(
+            !self.cpu_affinity.is_empty(),
+            "cpu_affinity must list at least one core"
+        );
+        self
+    }
+
+    /// Unix only. Injects fault into subprocess, like failing allocations.
+    /// Requires `chaos-testing` feature
+    #[cfg(all(unix, feature = "chaos-testing"))]
+    pub fn inject_fault(mut self, fault: &str) -> Self {
+        self.fault = Some(chaos::Fault::parse(fault));
+        self
+    }
+
+    /// Runs test body in subprocess mode, otherwise spawns subprocess and passes its result
+    /// to `verify_fn`
+    pub fn run<R>(
+        mut self,
+        test_fn: impl FnOnce() -> R,
+        verify_fn: impl FnOnce(SubprocessResult) -> R,
+    ) -> R {
+        let boundary = &self.output_boundary;
+        // If test phase is requested, execute it and bail immediately
+        if self.is_subprocess() {
+            // Background work started by test body, like server, runs until parent stops it
+            let controlled = self.control.is_some();
+            let test_fn = move || {
+                let result = test_fn();
+                if controlled {
+                    control::wait_for_stop();
+                }
+                result
+            };
+            let measure_io_bytes = self.measure_io_bytes;
+            let check_open_fds = self.max_open_fds.is_some();
+            defer! {{
+                if measure_io_bytes {
+                    report_io_bytes();
+                }
+                if check_open_fds {
+                    open_fds::report();
+                }
+            }};
+            // With `panic = "abort"`, finisher isn't printed on panic by `defer!`, so it's printed
+            // by panic hook instead, to keep output of threads which outlive test body.
+            // Installed before fault is armed, since it allocates
+            #[cfg(panic = "abort")]
+            if self.output_from_env.is_none() {
+                let finisher = boundary.repeat(if self.multi_boundary { 2 } else { 1 });
+                let hook = std::panic::take_hook();
+                std::panic::set_hook(Box::new(move |info| {
+                    hook(info);
+                    print!("{finisher}");
+                    let _ = io::stdout().flush();
+                }));
+            }
+            #[cfg(all(unix, feature = "chaos-testing"))]
+            let _fault_guard = self.fault.map(chaos::Fault::arm);
+            let _env_snapshot = env_snapshot::EnvSnapshot::take(&self.snapshot_env);
+            // Output is collected from file, so stdout is left clean for custom runner
+            if self.output_from_env.is_some() {
+                #[cfg(feature = "count-allocations")]
+                let _alloc_counter = self
+                    .capture_allocations
+                    .then(alloc_count::AllocCounter::start);
+                return test_fn();
+            }
+            print!("{boundary}");
+            // Checkpoints print same boundary, so finisher is doubled to tell it apart
+            let multi_boundary = self.multi_boundary;
+            if multi_boundary {
+                let _ = CHECKPOINT_BOUNDARY.set(boundary.to_string());
+            }
+            // We expect that in case of panic we'll get test harness footer,
+            // but in case of abort we won't get it, so finisher won't be needed
+            defer! {{
+                if multi_boundary {
+                    print!("{boundary}");
+                }
+                print!("{boundary}");
+            }};
+            // Goes after finisher, so threads are joined before it's printed.
+            // Panic here unwinds through finisher, so it's still printed
+            let join_threads = self.join_threads;
+            defer! {{
+                if join_threads {
+                    let panicked = threads::join_all();
+                    if panicked > 0 && !std::thread::panicking() {
+                        panic!("{panicked} thread(s) spawned by test body panicked");
+                    }
+                }
+            }};
+            // Counter is dropped before finisher is printed, so only test body is accounted
+            #[cfg(feature = "count-allocations")]
+            let _alloc_counter = self
+                .capture_allocations
+                .then(alloc_count::AllocCounter::start);
+            return test_fn();
+        }
+        // Otherwise, perform main runner phase.
+        self = self.pre_spawn_plugins();
+        let (result, _temp_dir) = self.run_parent();
+        let result = verify_fn(result);
+        self.plugins.post_verify();
+        result
+    }
+
+    /// Same as `run`, but test body is `async fn`, which is run on runtime
+    /// specified by `runtime` parameter in subprocess
+    pub fn run_async<R, F: std::future::Future<Output = R>>(
+        self,
+        test_fn: impl FnOnce() -> F,
+        verify_fn: impl FnOnce(SubprocessResult) -> R,
+    ) -> R {
+        let runtime = self.runtime;
+        self.run(|| runtime.block_on(test_fn()), verify_fn)
+    }
+
+    /// Runs external binary specified by `binary` parameter instead of re-running test
+    /// executable, see `subprocess_test_external!`
+    pub fn run_external<R>(mut self, verify_fn: impl FnOnce(SubprocessResult) -> R) -> R {
+        assert!(
+            self.binary.is_some(),
+            "Test {} must specify `binary` parameter",
+            self.full_test_name
+        );
+        self = self.pre_spawn_plugins();
+        let (result, _temp_dir) = self.run_parent();
+        let result = verify_fn(result);
+        self.plugins.post_verify();
+        result
+    }
+
+    /// Runs test from inside build script, see `build_subprocess_test!`
+    pub fn run_build_script(
+        mut self,
+        test_fn: impl FnOnce(),
+        verify_fn: impl FnOnce(SubprocessResult),
+    ) {
+        self.build_script = true;
+        let boundary = &self.output_boundary;
+        // Build script is re-run as a whole, so subprocess exits right after test function
+        // to skip the rest of build script
+        if self.is_subprocess() {
+            print!("{boundary}");
+            test_fn();
+            print!("{boundary}");
+            io::stdout().flush().expect("Failed to flush stdout");
+            exit(0);
+        }
+
+        self = self.pre_spawn_plugins();
+        let (result, _temp_dir) = self.run_parent();
+        verify_fn(result);
+        self.plugins.post_verify();
+    }
+
+    /// Returns result along with directory shared with subprocess,
+    /// which must be kept until `verify` block finishes
+    fn run_parent(&self) -> (SubprocessResult, TempDir) {
+        let syscall_trace = self.syscall_tracer().map(SyscallTrace::new);
+        // Just run same executable but with different options
+        let mut command = self.command(syscall_trace.as_ref());
+        let temp_dir = TempDir::new().expect("Failed to create temporary directory for test");
+        command.env(TEMP_DIR_ENV_VAR_NAME, temp_dir.path());
+
+        if env_flag(DRY_RUN_ENV_VAR_NAME) {
+            eprintln!("[dry-run] Would execute: {}", command_line(&command));
+            return (
+                SubprocessResult {
+                    success: true,
+                    exit_code: Some(0),
+                    signal: None,
+                    timed_out: false,
+                    truncated: false,
+                    output: String::new(),
+                    output_bytes: Vec::new(),
+                    stdout: None,
+                    stderr: None,
+                    sections: Vec::new(),
+                    boundary_sections: Vec::new(),
+                    duration: Duration::ZERO,
+                    pid: None,
+                    subtests: Vec::new(),
+                    io_read_bytes: None,
+                    io_write_bytes: None,
+                    warnings: Vec::new(),
+                    diagnostics: String::new(),
+                    syscall_trace: None,
+                    temp_dir: temp_dir.path().to_owned(),
+                    alloc_count: None,
+                    dealloc_count: None,
+                    dry_run: true,
+                    snapshot_updated: false,
+                    phases: Vec::new(),
+                },
+                temp_dir,
+            );
+        }
+
+        let mut run_phase = |phase: usize| {
+            if self.phases > 1 {
+                command.env(PHASE_ENV_VAR_NAME, phase.to_string());
+            }
+            if let Some(runs) = flakiness_detect_runs() {
+                FlakinessResult::detect(runs, || self.execute_with_retries(&mut command))
+                    .into_result()
+            } else {
+                self.execute_with_retries(&mut command)
+            }
+        };
+        let mut result = if self.phases > 1 {
+            let phases: Vec<_> = (0..self.phases).map(run_phase).collect();
+            let mut result = phases.last().expect("At least one phase is run").clone();
+            result.success = phases.iter().all(|phase| phase.success);
+            result.phases = phases;
+            result
+        } else {
+            run_phase(0)
+        };
+        result.syscall_trace = syscall_trace.map(|trace| trace.read());
+        result.temp_dir = temp_dir.path().to_owned();
+
+        #[cfg(feature = "llvm-cov")]
+        coverage::merge(&command, self.find_executable("llvm-profdata"));
+
+        // Goes before any checks, so failed tests are reported too
+        #[cfg(feature = "json-report")]
+        report::append(&self.test_path, &result);
+
+        subtest::check(&result.subtests);
+
+        if !result.success {
+            match self.expect_failure {
+                ExpectFailure::No => {}
+                ExpectFailure::Any => panic!(
+                    "Expected subprocess of test {} to fail but it succeeded:\n{}",
+                    self.full_test_name, result.output
+                ),
+                ExpectFailure::ExitCode(code) => panic!(
+                    "Expected subprocess of test {} to exit with code {code}, got {}:\n{}",
+                    self.full_test_name,
+                    exit_status_description(result.exit_code, result.signal),
+                    result.output
+                ),
+            }
+        }
+
+        if let Some(max_duration) = self.max_duration {
+            assert!(
+                result.duration <= max_duration,
+                "Test {} subprocess took {:?}, which exceeds maximum duration {max_duration:?}:\n{}",
+                self.full_test_name,
+                result.duration,
+                result.output
+            );
+        }
+
+        if let (Some(threshold_pct), true) = (self.regression_threshold_pct, result.success) {
+            let baseline = Baseline::load(&self.full_test_name);
+            baseline.check(result.duration, threshold_pct);
+            baseline.record(result.duration, self.baseline_runs);
+        }
+
+        if let Some(count) = self.expect_lines {
+            assert!(
+                result.success,
+                "Test {} subprocess failed:\n{}",
+                self.full_test_name, result.output
+            );
+            assert_eq!(
+                result.output.lines().count(),
+                count,
+                "Test {} subprocess printed unexpected number of lines:\n{}",
+                self.full_test_name,
+                result.output
+            );
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(regex) = &self.verify_matches {
+            assert!(
+                result.success,
+                "Test {} subprocess failed:\n{}",
+                self.full_test_name, result.output
+            );
+            assert!(
+                regex.is_match(&result.output),
+                "Output of test {} doesn't match pattern {regex}:\n{}",
+                self.full_test_name,
+                result.output
+            );
+        }
+
+        if self.snapshot {
+            let manifest_dir = self
+                .manifest_dir
+                .expect("`snapshot` requires test defined by `subprocess_test!` macro");
+            if env_flag(SNAPSHOT_UPDATE_ENV_VAR_NAME) {
+                snapshot::update(manifest_dir, &self.test_path, &result.output);
+                result.snapshot_updated = true;
+            } else if let Err(message) =
+                snapshot::check(manifest_dir, &self.test_path, &result.output)
+            {
+                panic!("{message}");
+            }
+        }
+
+        #[cfg(feature = "diff-output")]
+        if let Some(path) = &self.verify_golden {
+            assert!(
+                result.success,
+                "Test {} subprocess failed:\n{}",
+                self.full_test_name, result.output
+            );
+            let manifest_dir = self
+                .manifest_dir
+                .expect("`verify_golden` requires test defined by `subprocess_test!` macro");
+            let path = Path::new(manifest_dir).join(path);
+            if let Err(message) = snapshot::check_golden(&path, &result.output) {
+                panic!("Output of test {} {message}", self.full_test_name);
+            }
+        }
+
+        (result, temp_dir)
+    }
+
+    fn command(&self, syscall_trace: Option<&SyscallTrace>) -> Command {
+        let mut command = match &self.binary {
+            Some(binary) => {
+                let mut command = Command::new(binary);
+                command.args(&self.binary_args);
+                command
+            }
+            None => self.test_command(syscall_trace),
+        };
+
+        if let Some(working_dir) = &self.working_dir {
+            assert!(
+                working_dir.is_dir(),
+                "Working directory {} for test {} doesn't exist or isn't a directory",
+                working_dir.display(),
+                self.full_test_name,
+            );
+            command.current_dir(working_dir);
+        }
+
+        if self.clear_env || self.inherit_env_prefix.is_some() {
+            let prefixes = [&self.inherit_env_prefix, &self.forward_env_prefix];
+            command.env_clear().envs(vars_os().filter(|(name, _)| {
+                prefixes
+                    .iter()
+                    .filter_map(|prefix| prefix.as_deref())
+                    .chain([CARGO_FEATURE_ENV_VAR_PREFIX])
+                    .any(|prefix| name.as_encoded_bytes().starts_with(prefix.as_bytes()))
+                    || self
+                        .inherit_env
+                        .iter()
+                        .any(|inherited| *name == **inherited)
+            }));
+        }
+
+        // Backtrace is stripped from output anyway, so don't waste time collecting it
+        if self.strip_backtrace {
+            command.env("RUST_BACKTRACE", "0");
+        }
+
+        // Goes before explicitly specified variables, so they can override profile path
+        #[cfg(feature = "llvm-cov")]
+        coverage::prepare(&mut command, &self.full_test_name);
+
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+
+        // External binary isn't test executable, so it's never run in subprocess mode
+        if self.binary.is_none() {
+            command.env(&*self.env_var_name, &*self.full_test_name);
+        }
+
+        if let Some(timeout_ms) = self.network_timeout_ms {
+            command.env(NETWORK_TIMEOUT_ENV_VAR_NAME, timeout_ms.to_string());
+        }
+
+        #[cfg(feature = "context")]
+        if let Some(context) = &self.context {
+            context::pass(&mut command, context);
+        }
+
+        #[cfg(all(unix, feature = "chaos-testing"))]
+        if let Some(fault) = &self.fault {
+            fault.prepare(&mut command);
+        }
+
+        #[cfg(all(unix, feature = "rlimit"))]
+        if let Some(limit_bytes) = self.rlimit_as {
+            limits::set_address_space_limit(&mut command, limit_bytes);
+        }
+
+        #[cfg(all(target_os = "linux", feature = "windows-cleanup"))]
+        cleanup::kill_on_parent_death(&mut command);
+
+        #[cfg(all(unix, feature = "low-level-io"))]
+        if !self.capture_fds.is_empty() {
+            fd_capture::capture_fds(&mut command, &self.capture_fds);
+        }
+
+        #[cfg(feature = "cpu-affinity")]
+        if !self.cpu_affinity.is_empty() {
+            let cores: Vec<_> = self.cpu_affinity.iter().map(usize::to_string).collect();
+            command.env(CPU_AFFINITY_ENV_VAR_NAME, cores.join(","));
+            #[cfg(target_os = "linux")]
+            affinity::set_cpu_affinity(&mut command, &self.cpu_affinity);
+        }
+
+        command
+    }
+
+    /// Command which re-runs test executable in subprocess mode
+    fn test_command(&self, syscall_trace: Option<&SyscallTrace>) -> Command {
+        let mut exe_path = PathBuf::from(args_os().next().expect("Test executable path not found"));
+        // Relative executable path would be resolved against new working directory
+        if self.working_dir.is_some() {
+            exe_path = std::path::absolute(exe_path).expect("Failed to get test executable path");
+        }
+        let mut command_line = vec![exe_path.into_os_string()];
+        if !self.build_script {
+            command_line.extend(
+                runner::Runner::detect()
+                    .args(&self.full_test_name)
+                    .into_iter()
+                    .chain(self.cargo_args.iter().map(String::as_str))
+                    .map(OsString::from),
+            );
+        }
+        if let Some(valgrind) = self.valgrind() {
+            command_line = valgrind::wrap(valgrind, command_line);
+        }
+        // Tracer goes outermost, so it traces Valgrind too if both are used
+        if let Some(syscall_trace) = syscall_trace {
+            command_line = syscall_trace.wrap(command_line);
+        }
+        let mut command = Command::new(&command_line[0]);
+        command.args(&command_line[1..]);
+        command
+    }
+
+    /// Valgrind executable to run subprocess under, if requested by `SUBPROCESS_TEST_VALGRIND`
+    /// and found. External binaries and build scripts always run as is
+    fn valgrind(&self) -> Option<PathBuf> {
+        if self.binary.is_some() || self.build_script || !env_flag(valgrind::VALGRIND_ENV_VAR_NAME)
+        {
+            return None;
+        }
+        self.find_executable("valgrind")
+    }
+
+    /// Tracer to run subprocess under, if requested by `trace_syscalls` and found
+    fn syscall_tracer(&self) -> Option<PathBuf> {
+        if !self.trace_syscalls {
+            return None;
+        }
+        syscalls::TRACER.and_then(|tracer| self.find_executable(tracer))
+    }
+
+    /// Looks for executable in `PATH` of subprocess
+    fn find_executable(&self, name: &str) -> Option<PathBuf> {
+        let path = self
+            .envs
+            .iter()
+            .rev()
+            .find(|(name, _)| name == "PATH")
+            .map(|(_, path)| path.clone())
+            .or_else(|| var_os("PATH"))?;
+        let name = format!("{name}{}", std::env::consts::EXE_SUFFIX);
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(&name))
+            .find(|path| path.is_file())
+    }
+
+    /// External binary doesn't print boundaries, so its output is taken as is
+    fn cut_boundaries(&self, output: Vec<u8>, incomplete: bool, failed: bool) -> Vec<u8> {
+        if self.binary.is_some() {
+            return output;
+        }
+        cut_boundaries(
+            output,
+            &self.output_boundary,
+            self.multi_boundary,
+            incomplete,
+            failed,
+        )
+    }
+
+    /// Re-runs failed subprocess up to `retry` times, returns first successful attempt
+    /// or last failed one
+    fn execute_with_retries(&self, command: &mut Command) -> SubprocessResult {
+        let attempts = self.retry + 1;
+        let mut attempt = 1;
+        loop {
+            let result = self.execute(command);
+            if result.success || attempt == attempts {
+                return result;
+            }
+            eprintln!(
+                "Test {} attempt {attempt} of {attempts} failed, retrying",
+                self.full_test_name
+            );
+            std::thread::sleep(self.retry_delay);
+            attempt += 1;
+        }
+    }
+
+    fn execute(&self, command: &mut Command) -> SubprocessResult {
+        #[cfg(target_family = "wasm")]
+        compile_error!(
+            "Subprocess tests can't run on WebAssembly targets, because WASI doesn't support spawning processes"
+        );
+        let boundary = &*self.output_boundary;
+        let side_channel = SideChannel::new();
+        // Kept alive until subprocess exits
+        let fake_binary = self.fake_binary.as_deref().map(|name| {
+            FakeBinary::new(name, &self.fake_binary_output, self.fake_binary_exit_code)
+        });
+        if let Some(fake_binary) = &fake_binary {
+            command.env("PATH", fake_binary.path_env());
+        }
+        // Verbose output is echoed as it arrives, along with `on_output_line`,
+        // if it's captured through single file with boundaries
+        let verbose = self.verbose || env_flag(VERBOSE_ENV_VAR_NAME);
+        let streamed =
+            self.output_from_env.is_none() && !self.split_output && self.binary.is_none();
+        let echo_line = |line: &str| eprintln!("[{}] {line}", self.full_test_name);
+        let line_callback: Option<LineCallback<'_>> =
+            match (self.on_output_line.as_ref(), verbose && streamed) {
+                (Some(callback), false) => Some(Box::new(callback)),
+                (callback, true) => Some(Box::new(move |line: &str| {
+                    echo_line(line);
+                    if let Some(callback) = callback {
+                        callback(line);
+                    }
+                })),
+                (None, false) => None,
+            };
+        let limit = self.max_output_bytes;
+        let mut line_stream = None;
+        let (capture, stdout, stderr) = match &self.output_from_env {
+            // Subprocess writes its output on its own, and stdio would only clutter
+            // test harness output of parent process
+            Some(var_name) => (
+                OutputCapture::from_env(var_name, command),
+                Stdio::null(),
+                Stdio::null(),
+            ),
+            None if self.split_output => {
+                assert!(
+                    self.on_output_line.is_none(),
+                    "`on_output_line` can't be used when stdout and stderr are captured separately"
+                );
+                let (stdout_reader, stdout) = PipeReader::new(limit);
+                let (stderr_reader, stderr) = PipeReader::new(limit);
+                (
+                    OutputCapture::Split(stdout_reader, stderr_reader),
+                    stdout.into(),
+                    stderr.into(),
+                )
+            }
+            None if line_callback.is_some() => {
+                assert!(
+                    self.binary.is_none(),
+                    "`on_output_line` can't be used with external binary"
+                );
+                let (reader, stdout, stderr) = merged_pipe(limit);
+                line_stream = Some(LineStream::new(boundary, reader.clone()));
+                (OutputCapture::Stdio(reader), stdout.into(), stderr.into())
+            }
+            None => {
+                let (reader, stdout, stderr) = merged_pipe(limit);
+                (OutputCapture::Stdio(reader), stdout.into(), stderr.into())
+            }
+        };
+        command.env(SIDE_CHANNEL_ENV_VAR_NAME, side_channel.path());
+        let mut control = self.control.as_ref().map(|control_fn| {
+            let control = SubprocessControl::new();
+            command.env(control::CONTROL_ENV_VAR_NAME, control.flag_path());
+            (control, control_fn)
+        });
+        let valgrind_log = self.valgrind().map(|_| valgrind::ValgrindLog::new(command));
+
+        if let Some(hook) = self.pre_spawn_hook {
+            hook().unwrap_or_else(|err| panic!("Pre-spawn hook failed: {err}"));
+        }
+        self.plugins.pre_spawn_command(command);
+
+        let started_at = Instant::now();
+        let spawn_guard = pipe::spawn_guard();
+        let mut child = command
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn()
+            .expect("Failed to execute test as subprocess");
+        drop(spawn_guard);
+        let pid = child.id();
+        // Command keeps write ends of output pipes, which must be closed
+        // for output to end when subprocess exits
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+
+        // Kept open until subprocess exits
+        #[cfg(all(windows, any(feature = "windows-cleanup", feature = "rlimit")))]
+        let _job = {
+            #[cfg(feature = "rlimit")]
+            let memory_limit = self.rlimit_as;
+            #[cfg(not(feature = "rlimit"))]
+            let memory_limit = None;
+            job::assign(&mut child, memory_limit)
+        };
+
+        if let Some(score_adj) = self.oom_score_adj {
+            set_oom_score_adj(&mut child, score_adj);
+        }
+
+        #[cfg(all(windows, feature = "cpu-affinity"))]
+        if !self.cpu_affinity.is_empty() {
+            affinity::set_cpu_affinity(&mut child, &self.cpu_affinity);
+        }
+        self.plugins.post_spawn(&child);
+
+        // Written from separate thread, so subprocess which doesn't read its input
+        // or produces lots of output before reading doesn't block parent
+        let stdin_writer = self.stdin.clone().map(|data| {
+            let mut stdin = child
+                .stdin
+                .take()
+                .expect("Subprocess stdin should be piped");
+            std::thread::spawn(move || {
+                // Subprocess may exit without reading whole input, which isn't an error
+                let _ = stdin.write_all(&data);
+            })
+        });
+
+        // Output is read by background threads meanwhile, so subprocess doesn't block on it.
+        // Timeout isn't enforced until block returns
+        if let Some((control, control_fn)) = &mut control {
+            use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+
+            control.set_pid(pid);
+            let outcome = catch_unwind(AssertUnwindSafe(|| control_fn(control)));
+            control.stop();
+            // Subprocess isn't left running after failed block
+            if let Err(panic) = outcome {
+                let _ = child.kill();
+                let _ = child.wait();
+                resume_unwind(panic);
+            }
+        }
+
+        let mut line_stream = line_stream.zip(line_callback.as_deref());
+        let deadline = self.timeout.map(|timeout| started_at + timeout);
+        let mut timed_out = false;
+        // Deadline of graceful shutdown, after which subprocess is killed
+        let mut kill_at = None;
+        let status = if line_stream.is_none() && deadline.is_none() {
+            child.wait().expect("Failed to wait for test subprocess")
+        } else {
+            loop {
+                // Subprocess exit is checked first, so output is read completely after exit
+                let status = child
+                    .try_wait()
+                    .expect("Failed to wait for test subprocess");
+                if let Some(status) = status {
+                    if let Some((stream, callback)) = line_stream {
+                        stream.finish(callback);
+                    }
+                    break status;
+                }
+                if !timed_out && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    timed_out = true;
+                    match self.graceful_shutdown {
+                        Some(grace) if request_termination(&child) => {
+                            kill_at = Some(Instant::now() + grace);
+                        }
+                        // Subprocess might exit on its own right before kill,
+                        // it's reaped on next poll anyway
+                        _ => {
+                            let _ = child.kill();
+                        }
+                    }
+                }
+                if kill_at.is_some_and(|kill_at| Instant::now() >= kill_at) {
+                    let _ = child.kill();
+                    kill_at = None;
+                }
+                if let Some((stream, callback)) = &mut line_stream {
+                    stream.poll(callback);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+        let duration = started_at.elapsed();
+        if let Some(writer) = stdin_writer {
+            writer.join().expect("Subprocess stdin writer panicked");
+        }
+
+        if let Some(hook) = self.post_spawn_hook {
+            hook().unwrap_or_else(|err| panic!("Post-spawn hook failed: {err}"));
+        }
+
+        let diagnostics = valgrind_log.map_or_else(String::new, |log| log.read());
+        if !status.success() && !diagnostics.is_empty() {
+            eprintln!(
+                "Valgrind report for test {}:\n{diagnostics}",
+                self.full_test_name
+            );
+        }
+
+        let records = side_channel.records();
+        let mut warnings = Vec::new();
+        let fd_warning = self
+            .max_open_fds
+            .and_then(|max_fds| open_fds::check(&records, max_fds));
+        if let Some(warning) = fd_warning {
+            if self.fail_on_fd_leak {
+                panic!("{warning}");
+            }
+            eprintln!("{warning}");
+            warnings.push(warning);
+        }
+        let (io_read_bytes, io_write_bytes) = records
+            .iter()
+            .rev()
+            .find(|(kind, _)| kind == IO_BYTES_RECORD)
+            .and_then(|(_, payload)| payload.split_once('\t'))
+            .map_or((None, None), |(read, written)| {
+                (read.parse().ok(), written.parse().ok())
+            });
+        #[cfg(feature = "count-allocations")]
+        let (alloc_count, dealloc_count) = alloc_count::parse(&records);
+        #[cfg(not(feature = "count-allocations"))]
+        let (alloc_count, dealloc_count) = (None, None);
+        for (_, message) in records
+            .iter()
+            .filter(|(kind, _)| kind == PRINT_TO_PARENT_RECORD)
+        {
+            eprintln!("{message}");
+        }
+
+        let (output, stderr, truncated) = match capture {
+            OutputCapture::Stdio(reader) => {
+                let (output, truncated) = reader.finish();
+                (
+                    self.cut_boundaries(output, timed_out || truncated, !status.success()),
+                    None,
+                    truncated,
+                )
+            }
+            // Boundaries are printed to stdout, while stderr contains only what test wrote there
+            OutputCapture::Split(stdout, stderr) => {
+                let (stdout, stdout_truncated) = stdout.finish();
+                let (stderr, stderr_truncated) = stderr.finish();
+                (
+                    self.cut_boundaries(stdout, timed_out || stdout_truncated, !status.success()),
+                    Some(stderr),
+                    stdout_truncated || stderr_truncated,
+                )
+            }
+            OutputCapture::File(path) => {
+                let (output, truncated) = read_output_file(&path, limit);
+                (output, None, truncated)
+            }
+            OutputCapture::TempFile(path) => {
+                let (output, truncated) = read_output_file(&path, limit);
+                (output, None, truncated)
+            }
+        };
+
+        // Checkpoints are removed from output, so it's processed same way as without them
+        let (output, boundary_sections) = if self.multi_boundary && self.binary.is_none() {
+            let parts = split_boundaries(&output, self.output_boundary.as_bytes());
+            let sections = parts
+                .iter()
+                .map(|part| String::from_utf8_lossy(part).into_owned())
+                .collect();
+            (parts.concat(), sections)
+        } else {
+            (output, Vec::new())
+        };
+        let output_bytes = match &stderr {
+            Some(stderr) => [output.as_slice(), stderr].concat(),
+            None => output.clone(),
+        };
+        let output = String::from_utf8_lossy(&output).into_owned();
+        let stderr = stderr.map(|stderr| String::from_utf8_lossy(&stderr).into_owned());
+        let (output, stderr) = if self.normalize_line_endings {
+            (
+                postprocess::normalize_line_endings(output),
+                stderr.map(postprocess::normalize_line_endings),
+            )
+        } else {
+            (output, stderr)
+        };
+        let (output, stderr) = if self.strip_backtrace {
+            (
+                postprocess::strip_backtrace(&output),
+                stderr.map(|stderr| postprocess::strip_backtrace(&stderr)),
+            )
+        } else {
+            (output, stderr)
+        };
+        // Goes before trimming, since escape sequences may enclose whitespace
+        #[cfg(feature = "strip-ansi")]
+        let (output, stderr) = if self.strip_ansi {
+            (
+                postprocess::strip_ansi_escapes(&output),
+                stderr.map(|stderr| postprocess::strip_ansi_escapes(&stderr)),
+            )
+        } else {
+            (output, stderr)
+        };
+        let (output, stderr) = if self.normalize_paths {
+            let manifest_dir = self
+                .manifest_dir
+                .expect("`normalize_paths` requires test defined by `subprocess_test!` macro");
+            let root = config::workspace_root(Path::new(manifest_dir));
+            (
+                postprocess::relative_paths(&output, root),
+                stderr.map(|stderr| postprocess::relative_paths(&stderr, root)),
+            )
+        } else {
+            (output, stderr)
+        };
+        let (output, stderr) = if self.deduplicate_output {
+            (
+                postprocess::deduplicate_lines(&output),
+                stderr.map(|stderr| postprocess::deduplicate_lines(&stderr)),
+            )
+        } else {
+            (output, stderr)
+        };
+        let (output, sections) = split_sections(&output);
+        let (output, stdout, stderr) = match stderr {
+            Some(stderr) => (
+                self.output_trim.apply(format!("{output}{stderr}")),
+                Some(self.output_trim.apply(output)),
+                Some(self.output_trim.apply(stderr)),
+            ),
+            None => (self.output_trim.apply(output), None, None),
+        };
+        let normalize = |output: String| {
+            self.normalizers
+                .iter()
+                .fold(output, |output, normalize| normalize(output))
+        };
+        let (output, stdout, stderr) = (
+            normalize(output),
+            stdout.map(normalize),
+            stderr.map(normalize),
+        );
+
+        // Without exit code, subprocess was killed by signal
+        let succeeded = match status.code() {
+            Some(code) => self.success_codes.contains(&code),
+            None => {
+                exit_signal(status).is_some_and(|signal| self.success_signals.contains(&signal))
+            }
+        };
+        let success = match self.expect_failure {
+            ExpectFailure::No => succeeded,
+            ExpectFailure::Any => !succeeded,
+            ExpectFailure::ExitCode(code) => status.code() == Some(code),
+        };
+        let mut result = SubprocessResult {
+            success,
+            // Code of process killed on timeout is chosen by killer rather than subprocess
+            exit_code: status.code().filter(|_| !timed_out),
+            signal: exit_signal(status),
+            timed_out,
+            truncated,
+            output,
+            output_bytes,
+            stdout,
+            stderr,
+            sections: if self.capture_sections {
+                sections
+            } else {
+                Vec::new()
+            },
+            boundary_sections,
+            duration,
+            pid: Some(pid),
+            subtests: subtest::collect(&records),
+            io_read_bytes,
+            io_write_bytes,
+            warnings,
+            diagnostics,
+            syscall_trace: None,
+            temp_dir: PathBuf::new(),
+            alloc_count,
+            dealloc_count,
+            dry_run: false,
+            snapshot_updated: false,
+            phases: Vec::new(),
+        };
+        self.plugins.post_wait(&mut result);
+
+        // Output which can't be streamed is echoed after subprocess exits
+        if verbose && !streamed {
+            result.output.lines().for_each(echo_line);
+        }
+
+        result
+    }
+}
+
+/// Outcome of running same subprocess test several times in flakiness detection mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FlakinessResult {
+    /// Total number of runs performed
+    pub runs: usize,
+    /// Number of runs where subprocess succeeded
+    pub successes: usize,
+    /// Number of runs where subprocess failed
+    pub failures: usize,
+    /// Results of all runs, in order of execution
+    pub outputs: Vec<SubprocessResult>,
+}
+
+impl FlakinessResult {
+    fn detect(runs: usize, mut run_fn: impl FnMut() -> SubprocessResult) -> Self {
+        let outputs: Vec<_> = (0..runs).map(|_| run_fn()).collect();
+        let successes = outputs.iter().filter(|result| result.success).count();
+
+        Self {
+            runs,
+            successes,
+            failures: runs - successes,
+            outputs,
+        }
+    }
+
+    /// Returns `true` if some runs succeeded while others failed
+    pub fn is_flaky(&self) -> bool {
+        self.successes != 0 && self.failures != 0
+    }
+    /// Panics if test is flaky, otherwise returns result of last run
+    fn into_result(mut self) -> SubprocessResult {
+        if self.is_flaky() {
+            for (index, result) in self.outputs.iter().enumerate() {
+                if !result.success {
+                    eprintln!(
+                        "Run {} of {} failed:\n{}",
+                        index + 1,
+                        self.runs,
+                        result.output
+                    );
+                }
+            }
+            panic!(
+                "Test is flaky: {}/{} runs succeeded",
+                self.successes, self.runs
+            );
+        }
+
+        self.outputs
+            .pop()
+            .expect("Flakiness detection performs at least one run")
+    }
+}
+
+/// Number of runs requested through flakiness detection variable, if any
+fn flakiness_detect_runs() -> Option<usize> {
+    let runs = var(FLAKINESS_DETECT_ENV_VAR_NAME).ok()?;
+    let runs: usize = runs.parse().unwrap_or_else(|_| {
+        panic!("{FLAKINESS_DETECT_ENV_VAR_NAME} must be number of runs, got {runs:?}")
+    });
+
+    Some(runs.max(1))
+}
+
+fn exit_status_description(exit_code: Option<i32>, signal: Option<i32>) -> String {
+    match (exit_code, signal) {
+        (Some(code), _) => format!("exit code {code}"),
+        (None, Some(signal)) => format!("signal {signal}"),
+        (None, None) => "no exit code".to_owned(),
+    }
+}
+
+/// Splits output by section marks. Returns output with marks removed,
+/// and list of named sections
+fn split_sections(output: &str) -> (String, Vec<(String, String)>) {
+    let mut pieces = output.split(SECTION_MARK_PREFIX);
+    let mut plain = pieces.next().unwrap_or_default().to_owned();
+    let mut sections = Vec::new();
+
+    for piece in pieces {
+        let (name, content) = piece.split_once('\n').unwrap_or((piece, ""));
+        plain.push_str(content);
+        sections.push((name.to_owned(), content.to_owned()));
+    }
+
+    (plain, sections)
+}
+
+/// Adjusts OOM killer score of spawned subprocess. Subprocess is killed if adjustment fails
+#[cfg(target_os = "linux")]
+fn set_oom_score_adj(child: &mut Child, score_adj: i32) {
+    let path = format!("/proc/{}/oom_score_adj", child.id());
+    if let Err(err) = std::fs::write(&path, score_adj.to_string()) {
+        let _ = child.kill();
+        let _ = child.wait();
+        panic!("Failed to write {score_adj} into {path}: {err}");
+    }
+}
+
+/// OOM score adjustment is supported only on Linux
+#[cfg(not(target_os = "linux"))]
+fn set_oom_score_adj(_child: &mut Child, _score_adj: i32) {}
+
+const IO_BYTES_RECORD: &str = "io-bytes";
+
+/// Sends I/O counters of current process to parent.
+///
+/// Uses `rchar` and `wchar` counters, which include all bytes passed through read and write
+/// syscalls. Counters `read_bytes` and `write_bytes` count only bytes actually fetched from
+/// or sent to storage, so reads served from page cache don't show up there
+#[cfg(target_os = "linux")]
+fn report_io_bytes() {
+    let Ok(stats) = std::fs::read_to_string("/proc/self/io") else {
+        return;
     };
-    (
-        @tokens_or_default { $($tokens:tt)+ } or { $($_:tt)* }
-    ) => {
-        $($tokens)+
+    let counter = |name: &str| {
+        stats.lines().find_map(|line| {
+            line.strip_prefix(name)?
+                .strip_prefix(':')?
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
     };
-    (
-        @tokens_or_default { } or { $($tokens:tt)* }
-    ) => {
-        $($tokens)*
+    if let (Some(read), Some(written)) = (counter("rchar"), counter("wchar")) {
+        side_channel::send(IO_BYTES_RECORD, &format!("{read}\t{written}"));
+    }
+}
+
+/// I/O counters are supported only on Linux
+#[cfg(not(target_os = "linux"))]
+fn report_io_bytes() {}
+
+/// Checks whether boolean flag is set through environment variable.
+/// Any value except empty one and `0` is considered as set
+fn env_flag(name: &str) -> bool {
+    var_os(name).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn command_line(command: &Command) -> String {
+    let mut buf = format!("{:?}", command.get_program());
+    for arg in command.get_args() {
+        write!(buf, " {arg:?}").unwrap();
+    }
+
+    buf
+}
+
+/// Source of subprocess output collected after subprocess exits
+enum OutputCapture {
+    /// Stdout and stderr redirected into single pipe
+    Stdio(PipeReader),
+    /// Stdout and stderr redirected into separate pipes
+    Split(PipeReader, PipeReader),
+    /// File written by subprocess itself, whose path was set externally,
+    /// see `output_from_env` parameter
+    File(PathBuf),
+    /// Same as `File`, but file is created by runner and removed afterwards
+    TempFile(TempPath),
+}
+
+impl OutputCapture {
+    /// Uses output file path from parent process environment, if it's set externally.
+    /// Otherwise, creates temporary file and passes its path to subprocess
+    fn from_env(var_name: &str, command: &mut Command) -> Self {
+        match var_os(var_name) {
+            Some(path) => Self::File(path.into()),
+            None => {
+                let path = NamedTempFile::new()
+                    .expect("Failed to create temporary file for subprocess output")
+                    .into_temp_path();
+                command.env(var_name, &path);
+                Self::TempFile(path)
+            }
+        }
+    }
+}
+
+/// Asks subprocess to exit with `SIGTERM`, so it can flush its output. Windows has
+/// no such request for console processes, so there subprocess is just killed
+#[cfg(unix)]
+fn request_termination(child: &Child) -> bool {
+    use nix::sys::signal::{Signal, kill};
+    use nix::unistd::Pid;
+
+    let Ok(pid) = i32::try_from(child.id()) else {
+        return false;
     };
+    kill(Pid::from_raw(pid), Signal::SIGTERM).is_ok()
 }
 
-#[doc(hidden)]
-pub fn run_subprocess_test<R>(
-    full_test_name: &str,
-    var_name: Option<&str>,
-    boundary: Option<&str>,
-    test_fn: impl FnOnce() -> R,
-    verify_fn: impl FnOnce(bool, String) -> R,
-) -> R {
-    const DEFAULT_SUBPROCESS_ENV_VAR_NAME: &str = "__TEST_RUN_SUBPROCESS__";
-    const DEFAULT_OUTPUT_BOUNDARY: &str = "\n========================================\n";
-
-    let full_test_name = &full_test_name[full_test_name
-        .find("::")
-        .expect("Full test path is expected to include crate name")
-        + 2..];
-    let var_name = var_name.unwrap_or(DEFAULT_SUBPROCESS_ENV_VAR_NAME);
-    let boundary: Cow<'static, str> = if let Some(boundary) = boundary {
-        format!("\n{boundary}\n").into()
-    } else {
-        DEFAULT_OUTPUT_BOUNDARY.into()
+#[cfg(not(unix))]
+fn request_termination(_child: &Child) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn exit_signal(status: std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Output is read as bytes, since subprocess isn't obliged to print valid UTF-8.
+/// At most `limit` bytes are read, so runaway subprocess output doesn't exhaust memory
+/// of test runner; returned flag tells whether file had more than that
+fn read_output_file(path: &Path, limit: Option<u64>) -> (Vec<u8>, bool) {
+    let file = File::open(path).unwrap_or_else(|err| {
+        panic!(
+            "Failed to read subprocess output from {}: {err}",
+            path.display()
+        )
+    });
+    let mut buffer = Vec::new();
+    let truncated = match limit {
+        Some(limit) => {
+            (&file)
+                .take(limit)
+                .read_to_end(&mut buffer)
+                .expect("Failed to read file into buffer");
+            file.metadata()
+                .expect("Failed to query output file size")
+                .len()
+                > limit
+        }
+        None => {
+            (&file)
+                .read_to_end(&mut buffer)
+                .expect("Failed to read file into buffer");
+            false
+        }
     };
-    // If test phase is requested, execute it and bail immediately
-    if var_os(var_name).is_some() {
-        print!("{boundary}");
-        // We expect that in case of panic we'll get test harness footer,
-        // but in case of abort we won't get it, so finisher won't be needed
-        defer! { print!("{boundary}") };
-        return test_fn();
-    }
-    // Otherwise, perform main runner phase.
-    // Just run same executable but with different options
-    let (tmpfile, stdout, stderr) = tmpfile_buffer();
-    let exe_path = args_os().next().expect("Test executable path not found");
-
-    let success = Command::new(exe_path)
-        .args([
-            "--include-ignored",
-            "--nocapture",
-            "--quiet",
-            "--exact",
-            "--test",
-        ])
-        .arg(full_test_name)
-        .env(var_name, "")
-        .stdin(Stdio::null())
-        .stdout(stdout)
-        .stderr(stderr)
-        .status()
-        .expect("Failed to execute test as subprocess")
-        .success();
-
-    let mut output = read_file(tmpfile);
-    let boundary_at = output
-        .find(&*boundary)
-        .expect("Subprocess output should always include at least one boundary");
-
-    output.replace_range(..(boundary_at + boundary.len()), "");
-
-    if let Some(boundary_at) = output.find(&*boundary) {
-        output.truncate(boundary_at);
-    }
 
-    verify_fn(success, output)
+    (buffer, truncated)
+}
+
+/// Creates pipe for both stdout and stderr of subprocess, so their output is interleaved
+/// in order it's written
+fn merged_pipe(limit: Option<u64>) -> (PipeReader, File, File) {
+    let (reader, stdout) = PipeReader::new(limit);
+    let stderr = stdout.try_clone().expect("Failed to clone pipe descriptor");
+
+    (reader, stdout, stderr)
 }
 
-fn tmpfile_buffer() -> (File, File, File) {
-    let file = tempfile().expect("Failed to create temporary file for subprocess output");
-    let stdout = file
-        .try_clone()
-        .expect("Failed to clone tmpfile descriptor");
-    let stderr = file
-        .try_clone()
-        .expect("Failed to clone tmpfile descriptor");
+/// Leaves only part of subprocess output between first two boundaries.
+/// Missing closing boundary means subprocess aborted, so output is taken till the end.
+/// Subprocess killed on timeout might not print even first boundary, then output is empty.
+/// Same applies to output truncated by `max_output_bytes`. Subprocess which failed before
+/// reaching test, like when test harness rejected `cargo_args`, has no boundaries either,
+/// then whole output is kept to explain failure. Single boundary printed by test itself
+/// ends output early, but panics if there are more of them, since it's unclear where
+/// test output ends. With `multi`, there may be more boundaries
+/// printed by checkpoints, so output ends at last doubled boundary instead
+fn cut_boundaries(
+    mut output: Vec<u8>,
+    boundary: &str,
+    multi: bool,
+    incomplete: bool,
+    failed: bool,
+) -> Vec<u8> {
+    let boundary = boundary.as_bytes();
+    let Some(boundary_at) = find_bytes(&output, boundary) else {
+        if incomplete {
+            return Vec::new();
+        }
+        assert!(
+            failed,
+            "Subprocess output should always include at least one boundary, \
+            probably test wasn't run:\n{}",
+            String::from_utf8_lossy(&output)
+        );
+        return output;
+    };
 
-    (file, stdout, stderr)
+    output.drain(..(boundary_at + boundary.len()));
+
+    let closing_at = if multi {
+        rfind_bytes(&output, &boundary.repeat(2))
+    } else {
+        let closing_at = find_bytes(&output, boundary);
+        // First boundary after opening one is treated as closing, and only one more of them,
+        // the actual closing one, is expected after it
+        if let Some(closing_at) = closing_at {
+            let rest = &output[(closing_at + boundary.len())..];
+            let extra = find_bytes(rest, boundary).map(|at| &rest[(at + boundary.len())..]);
+            assert!(
+                extra.and_then(|rest| find_bytes(rest, boundary)).is_none(),
+                "Subprocess output contains the boundary string {:?} at unexpected positions; \
+                use output_boundary parameter to specify a unique delimiter",
+                String::from_utf8_lossy(boundary).trim(),
+            );
+        }
+        closing_at
+    };
+    if let Some(boundary_at) = closing_at {
+        output.truncate(boundary_at);
+    }
+    output
 }
 
-fn read_file(mut file: File) -> String {
-    file.seek(SeekFrom::Start(0))
-        .expect("Rewind to start failed");
+/// Splits output by boundaries printed by checkpoints
+fn split_boundaries<'a>(output: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    let mut rest = output;
+    while let Some(boundary_at) = find_bytes(rest, boundary) {
+        parts.push(&rest[..boundary_at]);
+        rest = &rest[(boundary_at + boundary.len())..];
+    }
+    parts.push(rest);
+    parts
+}
 
-    let mut buffer = String::new();
-    file.read_to_string(&mut buffer)
-        .expect("Failed to read file into buffer");
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
 
-    buffer
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
 }
 
+#[cfg(test)]
+const CONST_BOUNDARY: &str = concat!(env!("CARGO_PKG_NAME"), "::boundary");
+
 subprocess_test! {
+    // Executable is re-invoked with libtest arguments under both harnesses
+    #[test]
+    fn runner_args() {
+        let config = SubprocessTestConfig::new("subprocess_test::module::test");
+        for nextest_version in [None, Some("0.9.0")] {
+            // SAFETY: subprocess runs only this test, so no other threads access environment
+            unsafe {
+                match nextest_version {
+                    Some(version) => std::env::set_var("NEXTEST_VERSION", version),
+                    None => std::env::remove_var("NEXTEST_VERSION"),
+                }
+            }
+            let command = config.test_command(None);
+            let args: Vec<_> = command.get_args().collect();
+            assert_eq!(
+                args,
+                ["--include-ignored", "--nocapture", "--quiet", "--exact", "--test", "module::test"],
+                "{nextest_version:?}"
+            );
+        }
+    }
+
     #[test]
     fn name_collision() {
         println!("One");
@@ -292,6 +4146,23 @@ subprocess_test! {
         assert_eq!(output, "One\n");
     }
 
+    #[test]
+    fn env_snapshot_restored() {
+        use std::env::{set_var, var, var_os};
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe { set_var("SNAPSHOT_SET", "before") };
+        let snapshot =
+            env_snapshot::EnvSnapshot::take(&["SNAPSHOT_SET".into(), "SNAPSHOT_UNSET".into()]);
+        // SAFETY: same as above
+        unsafe {
+            set_var("SNAPSHOT_SET", "after");
+            set_var("SNAPSHOT_UNSET", "set");
+        }
+        drop(snapshot);
+        assert_eq!(var("SNAPSHOT_SET").as_deref(), Ok("before"));
+        assert_eq!(var_os("SNAPSHOT_UNSET"), None);
+    }
+
     #[test]
     fn simple_success() {
         let value = 1;
@@ -338,6 +4209,182 @@ subprocess_test! {
         assert_eq!(output, "One\nTwo\n");
     }
 
+    // Default boundary is plain output when custom one is specified
+    #[test(
+        output_boundary = "!!!!!!!!!!!!!!!!"
+    )]
+    fn custom_boundary_keeps_default() {
+        println!("One");
+        println!("\n========================================\n");
+        println!("\n========================================\n");
+        println!("Two");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(
+            output,
+            "One\n\n========================================\n\n\n========================================\n\nTwo\n"
+        );
+    }
+
+    #[test(
+        output_boundary = CONST_BOUNDARY,
+        env_var_name = "__CONST_BOUNDARY_SUBPROCESS__",
+    )]
+    fn const_boundary() {
+        println!("One");
+        println!("\n{CONST_BOUNDARY}\n");
+        println!("Two");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "One\n");
+    }
+
+    #[test]
+    fn marks_stripped_without_sections() {
+        println!("One");
+        subprocess_mark!("two");
+        println!("Two");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert_eq!(result.output, "One\nTwo\n");
+        assert!(result.sections.is_empty());
+    }
+
+    #[test]
+    fn duration_assertion_message() {
+        assert_subprocess_duration!(Duration::from_millis(10), 50, 200);
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Subprocess took 10ms, expected between 50ms and 200ms"));
+    }
+
+    #[test]
+    fn float_assertion_message() {
+        assert_subprocess_output_float!("result: 1.375.", "result: ", 1.25, 0.001);
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Expected result ≈ 1.25 (±0.001) but found 1.375\n"));
+    }
+
+    #[test]
+    fn lines_in_order_message() {
+        assert_subprocess_lines_in_order!("first\nthird\nsecond\n", ["first", "second", "third"]);
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Expected 'third' after 'second' but it was not found. Output:\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "xml")]
+    fn xml_assertion_message() {
+        assert_subprocess_output_xml!("<status><code>1</code></status>", "/status/code/text()", "0");
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains(
+            "XPath '/status/code/text()' returned '1' but expected '0'. Full output:\n<status>"
+        ));
+    }
+
+    #[test]
+    fn char_diff_message() {
+        assert_subprocess_eq_char_diff!("same\nfoo\r\n\u{1b}tail", "same\nfoo\n\ttail\nextra");
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains(concat!(
+            "Output differs from expected (actual vs expected):\n",
+            "Line 2 differs: 'foo\\r\\n' vs 'foo\\n'\n",
+            "Line 3 differs: '\\x1Btail' vs '\\ttail\\n'\n",
+            "Line 4 differs: <missing> vs 'extra'\n",
+        )), "{output}");
+    }
+
+    #[test]
+    fn matches_result_message() {
+        let result = SubprocessResult {
+            success: false,
+            exit_code: Some(101),
+            signal: None,
+            timed_out: false,
+            truncated: false,
+            output: "actual\n".to_owned(),
+            output_bytes: b"actual\n".to_vec(),
+            stdout: None,
+            stderr: None,
+            sections: Vec::new(),
+            boundary_sections: Vec::new(),
+            duration: Duration::ZERO,
+            pid: None,
+            subtests: Vec::new(),
+            io_read_bytes: None,
+            io_write_bytes: None,
+            warnings: Vec::new(),
+            diagnostics: String::new(),
+            syscall_trace: None,
+            temp_dir: PathBuf::new(),
+            alloc_count: None,
+            dealloc_count: None,
+            dry_run: false,
+            snapshot_updated: false,
+            phases: Vec::new(),
+        };
+        matches_result!(result, { success: true, output: "actual\n" });
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains(
+            "Subprocess result doesn't match:\n  success: expected true but found false\nOutput:\nactual\n"
+        ), "{output}");
+    }
+
+    #[test(split_output = true, output_trim = "end")]
+    fn split_streams() {
+        println!("To stdout");
+        eprintln!("To stderr");
+        println!("To stdout again");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert_eq!(result.output, "To stdout\nTo stdout again\nTo stderr");
+        assert_eq!(result.stdout.as_deref(), Some("To stdout\nTo stdout again"));
+        assert_eq!(result.stderr.as_deref(), Some("To stderr"));
+    }
+
+    #[test]
+    fn panic_location_parsed() {
+        println!("Before panic");
+        panic!("Multiline\npanic message");
+    }
+    verify |success, output| {
+        assert!(!success);
+        let (location, message) = crate::subprocess_panic(&output).expect("Panic not found");
+        assert!(location.starts_with("src/lib.rs:"), "{location}");
+        assert_eq!(message, "Multiline\npanic message");
+    }
+
+    #[test]
+    fn tempdir_with_prefix() {
+        let dir = subprocess_tempdir!(prefix = "subprocess_tempdir_");
+        let name = dir.path().file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("subprocess_tempdir_"), "{name}");
+        std::fs::write(dir.path().join("file"), "data").unwrap();
+        print!("{}", std::fs::read_to_string(dir.path().join("file")).unwrap());
+        let path = dir.path().to_owned();
+        drop(dir);
+        assert!(!path.exists());
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "data");
+    }
+
     #[test]
     #[should_panic]
     fn should_panic_test() {
@@ -358,6 +4405,26 @@ subprocess_test! {
         assert_eq!(output, "Banana\nMango\n");
     }
 
+    #[test(success_signals = [6])]
+    #[cfg(unix)]
+    fn test_abort_signal_success() {
+        std::process::abort();
+    }
+    verify |result| {
+        assert!(result.success, "{result:?}");
+        assert_eq!(result.signal, Some(6));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_aborts_with_signal() {
+        std::process::abort();
+    }
+    verify_unix |exit_code, signal, output| {
+        assert_eq!(exit_code, None);
+        assert_eq!(signal, Some(6), "{output}");
+    }
+
     /// Checks that positive test with result works as intended
     #[test]
     fn positive_test_result() -> Result<(), ()> {
@@ -450,3 +4517,105 @@ mod submodule_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod test_123 {
+    subprocess_test! {
+        #[test]
+        fn numeric_module_name() {
+            print!("Numeric");
+        }
+        verify |success, output| {
+            assert!(success);
+            assert_eq!(output, "Numeric");
+        }
+
+        #[test]
+        fn r#match() {
+            print!("Raw identifier");
+        }
+        verify |success, output| {
+            assert!(success);
+            assert_eq!(output, "Raw identifier");
+        }
+    }
+}
+
+#[cfg(test)]
+mod level_1 {
+    mod level_2 {
+        mod level_3 {
+            mod level_4 {
+                mod level_5 {
+                    mod level_6 {
+                        subprocess_test! {
+                            #[test]
+                            fn deeply_nested() {
+                                print!("Deep");
+                            }
+                            verify |success, output| {
+                                assert!(success);
+                                assert_eq!(output, "Deep");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod long_common_module_prefix {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    // Used to check that only single test is run per subprocess
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    subprocess_test! {
+        #[test]
+        fn same_name() {
+            print!("Short");
+            COUNTER.fetch_add(1, Ordering::Relaxed);
+            assert_eq!(COUNTER.load(Ordering::Relaxed), 1);
+        }
+        verify |success, output| {
+            assert!(success);
+            assert_eq!(output, "Short");
+        }
+    }
+
+    mod long_common_module {
+        subprocess_test! {
+            #[test]
+            fn same_name() {
+                print!("Nested");
+                super::COUNTER.fetch_add(1, super::Ordering::Relaxed);
+                assert_eq!(super::COUNTER.load(super::Ordering::Relaxed), 1);
+            }
+            verify |success, output| {
+                assert!(success);
+                assert_eq!(output, "Nested");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod long_common_module_prefix_extended {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    // Used to check that only single test is run per subprocess
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    subprocess_test! {
+        #[test]
+        fn same_name() {
+            print!("Extended");
+            COUNTER.fetch_add(1, Ordering::Relaxed);
+            assert_eq!(COUNTER.load(Ordering::Relaxed), 1);
+        }
+        verify |success, output| {
+            assert!(success);
+            assert_eq!(output, "Extended");
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! Resource limits applied to subprocess on Unix. On Windows, memory limit
+//! is put on job object instead, see `job` module
+use std::process::Command;
+
+/// Limits virtual address space of subprocess. Limit is set in forked child right before `exec`
+pub(crate) fn set_address_space_limit(command: &mut Command, limit_bytes: u64) {
+    use nix::sys::resource::{Resource, setrlimit};
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: closure only performs `setrlimit` syscall, which is async-signal-safe,
+    // and doesn't allocate or touch any locks
+    unsafe {
+        command.pre_exec(move || {
+            setrlimit(Resource::RLIMIT_AS, limit_bytes, limit_bytes).map_err(Into::into)
+        });
+    }
+}
@@ -0,0 +1,31 @@
+/* Preload library which makes `malloc` fail after specified number of calls,
+ * see `inject_fault` parameter of `subprocess_test!` */
+#define _GNU_SOURCE
+#include <dlfcn.h>
+#include <stddef.h>
+
+/* Number of calls which still succeed on current thread; negative means failures
+ * aren't armed. Counted per thread, so allocations made concurrently by test harness
+ * threads neither fail nor consume calls. Initial-exec model is used since library
+ * is preloaded, and it keeps TLS access itself from calling `malloc` */
+static __thread long remaining __attribute__((tls_model("initial-exec"))) = -1;
+
+void subprocess_test_malloc_fail_after(long calls) {
+    remaining = calls;
+}
+
+void *malloc(size_t size) {
+    static void *(*real_malloc)(size_t) = NULL;
+    if (!real_malloc) {
+        real_malloc = (void *(*)(size_t))dlsym(RTLD_NEXT, "malloc");
+    }
+
+    if (remaining == 0) {
+        return NULL;
+    }
+    if (remaining > 0) {
+        remaining--;
+    }
+
+    return real_malloc(size);
+}
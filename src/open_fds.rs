@@ -0,0 +1,58 @@
+//! Detection of file descriptors left open by subprocess, see `max_open_fds` parameter
+use std::fmt::Write;
+
+const OPEN_FD_RECORD: &str = "open-fd";
+
+/// Sends list of file descriptors currently open in subprocess to parent.
+///
+/// File descriptors are listed from inside subprocess right before test body ends,
+/// because they're closed once subprocess exits
+#[cfg(unix)]
+pub(crate) fn report() {
+    // Directory handle used for listing is open too, so it's recognized by its path
+    let (dir, own_dir) = if cfg!(target_os = "linux") {
+        ("/proc/self/fd", std::fs::read_link("/proc/self").ok())
+    } else {
+        ("/dev/fd", None)
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let own_dir = own_dir.map(|pid| std::path::Path::new("/proc").join(pid).join("fd"));
+
+    for entry in entries.flatten() {
+        let target = std::fs::read_link(entry.path()).ok();
+        if target.is_some() && target == own_dir {
+            continue;
+        }
+        let target = target.map_or("<unknown>".into(), |path| path.display().to_string());
+        let fd = entry.file_name().to_string_lossy().into_owned();
+        crate::side_channel::send(OPEN_FD_RECORD, &format!("{fd}\t{target}"));
+    }
+}
+
+/// Listing file descriptors is supported only on Unix
+#[cfg(not(unix))]
+pub(crate) fn report() {}
+
+/// Returns warning which lists open file descriptors, if there are more of them than `max_fds`
+pub(crate) fn check(records: &[(String, String)], max_fds: usize) -> Option<String> {
+    let fds: Vec<_> = records
+        .iter()
+        .filter(|(kind, _)| kind == OPEN_FD_RECORD)
+        .filter_map(|(_, payload)| payload.split_once('\t'))
+        .collect();
+    if fds.len() <= max_fds {
+        return None;
+    }
+
+    let mut warning = format!(
+        "Subprocess has {} open file descriptors, more than limit of {max_fds}:",
+        fds.len()
+    );
+    for (fd, target) in fds {
+        write!(warning, "\n  {fd} -> {target}").unwrap();
+    }
+
+    Some(warning)
+}
@@ -0,0 +1,40 @@
+//! Line-oriented view of subprocess output, passed to `verify_lines` block
+
+/// Iterator over lines of subprocess output, without line terminators.
+///
+/// Besides usual iterator methods like `count`, `any` or `nth`, provides [`OutputLines::find`]
+/// to look up line by substring. Mostly useful when output lines come in unpredictable order
+/// and only presence of certain lines matters
+#[derive(Debug, Clone)]
+pub struct OutputLines<'a> {
+    lines: std::str::Lines<'a>,
+}
+
+impl<'a> OutputLines<'a> {
+    pub fn new(output: &'a str) -> Self {
+        Self {
+            lines: output.lines(),
+        }
+    }
+    /// Returns next line which contains `needle`, skipping lines before it.
+    ///
+    /// Shadows [`Iterator::find`]; use `Iterator::find(&mut lines, predicate)`
+    /// to find line by predicate
+    pub fn find(&mut self, needle: &str) -> Option<&'a str> {
+        self.lines.find(|line| line.contains(needle))
+    }
+}
+
+impl<'a> Iterator for OutputLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lines.next()
+    }
+}
+
+impl DoubleEndedIterator for OutputLines<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.lines.next_back()
+    }
+}
@@ -0,0 +1,167 @@
+//! In-memory capture of subprocess output through OS pipes
+use std::fs::File;
+use std::io::{self, Read};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// How long to wait for end of output after subprocess exits. Output stays open
+/// only if subprocess left descendant processes which inherited it, and their output
+/// isn't waited for, same as with output of any other process after it exits
+const EOF_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Serializes creation of pipes with spawning of subprocesses on platforms where pipe
+/// can't be created with `FD_CLOEXEC` set atomically. Otherwise subprocess spawned by other
+/// test between `pipe` and `fcntl` would inherit write end, which keeps output open
+/// after subprocess exits. Processes spawned bypassing this crate aren't serialized
+#[cfg(target_vendor = "apple")]
+static CLOEXEC_LOCK: Mutex<()> = Mutex::new(());
+
+/// Output read from pipe so far
+#[derive(Default)]
+struct PipeData {
+    bytes: Vec<u8>,
+    truncated: bool,
+    eof: bool,
+}
+
+#[derive(Default)]
+struct PipeBuffer {
+    data: Mutex<PipeData>,
+    eof: Condvar,
+}
+
+impl PipeBuffer {
+    fn lock(&self) -> MutexGuard<'_, PipeData> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Read end of pipe connected to subprocess output, which is read by background thread.
+/// Thread is started before subprocess, so subprocess never blocks on full pipe buffer
+#[derive(Clone)]
+pub(crate) struct PipeReader(Arc<PipeBuffer>);
+
+impl PipeReader {
+    /// Creates pipe, returning its reader and write end for subprocess's stdout or stderr.
+    /// Only first `limit` bytes of output are kept, if specified
+    pub(crate) fn new(limit: Option<u64>) -> (Self, File) {
+        let (read_end, write_end) = os_pipe().expect("Failed to create pipe for subprocess output");
+        let buffer = Arc::<PipeBuffer>::default();
+        let reader = Arc::clone(&buffer);
+        thread::spawn(move || {
+            read_pipe(read_end, &reader, limit);
+            reader.lock().eof = true;
+            reader.eof.notify_all();
+        });
+
+        (Self(buffer), write_end)
+    }
+    /// Calls `f` with output read so far
+    pub(crate) fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.0.lock().bytes)
+    }
+    /// Waits for end of output after subprocess exits, for at most `EOF_GRACE_PERIOD`
+    pub(crate) fn wait(&self) {
+        let data = self.0.lock();
+        let _ = self
+            .0
+            .eof
+            .wait_timeout_while(data, EOF_GRACE_PERIOD, |data| !data.eof);
+    }
+    /// Waits for end of output and returns it, along with flag which tells
+    /// whether output was truncated by limit
+    pub(crate) fn finish(self) -> (Vec<u8>, bool) {
+        self.wait();
+        let mut data = self.0.lock();
+        (std::mem::take(&mut data.bytes), data.truncated)
+    }
+}
+
+/// Reads pipe till end into buffer. Bytes beyond `limit` are read too, so subprocess
+/// doesn't block on writing them, but they're discarded
+fn read_pipe(mut read_end: impl Read, buffer: &PipeBuffer, limit: Option<u64>) {
+    let limit = limit.map_or(usize::MAX, |limit| {
+        usize::try_from(limit).unwrap_or(usize::MAX)
+    });
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let len = match read_end.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            // Any other error means output can't be read anymore
+            Err(_) => break,
+        };
+        let mut data = buffer.lock();
+        let kept = len.min(limit - data.bytes.len());
+        data.bytes.extend_from_slice(&chunk[..kept]);
+        data.truncated |= kept < len;
+    }
+}
+
+/// Creates pipe whose ends aren't inherited by child processes. They get write end only
+/// as their stdout or stderr, so pipe is closed as soon as subprocess exits
+#[cfg(unix)]
+fn os_pipe() -> io::Result<(File, File)> {
+    #[cfg(not(target_vendor = "apple"))]
+    let (read_end, write_end) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC)?;
+    // There's no `pipe2` on Apple platforms, so `FD_CLOEXEC` is set separately
+    #[cfg(target_vendor = "apple")]
+    let (read_end, write_end) = {
+        use nix::fcntl::{FcntlArg, FdFlag, fcntl};
+
+        let _guard = CLOEXEC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (read_end, write_end) = nix::unistd::pipe()?;
+        fcntl(&read_end, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+        fcntl(&write_end, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+        (read_end, write_end)
+    };
+
+    Ok((read_end.into(), write_end.into()))
+}
+
+/// Must be held while subprocess is spawned, see `CLOEXEC_LOCK`
+#[cfg(target_vendor = "apple")]
+pub(crate) fn spawn_guard() -> Option<MutexGuard<'static, ()>> {
+    Some(CLOEXEC_LOCK.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Pipes are created with `FD_CLOEXEC` set atomically, so spawning needs no guard
+#[cfg(not(target_vendor = "apple"))]
+pub(crate) fn spawn_guard() -> Option<MutexGuard<'static, ()>> {
+    None
+}
+
+/// Creates pipe whose handles aren't inheritable. Standard library makes inheritable
+/// duplicate of write end for subprocess when it's passed as stdout or stderr
+#[cfg(windows)]
+fn os_pipe() -> io::Result<(File, File)> {
+    use std::os::windows::io::{FromRawHandle, OwnedHandle};
+    use std::ptr::null;
+
+    use windows_sys::Win32::System::Pipes::CreatePipe;
+
+    let mut read_end = std::ptr::null_mut();
+    let mut write_end = std::ptr::null_mut();
+    // SAFETY: pointers to handles are valid, and null security attributes
+    // mean handles aren't inheritable
+    if unsafe { CreatePipe(&mut read_end, &mut write_end, null(), 64 * 1024) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: handles were just created, and are owned by nothing else
+    let (read_end, write_end) = unsafe {
+        (
+            OwnedHandle::from_raw_handle(read_end),
+            OwnedHandle::from_raw_handle(write_end),
+        )
+    };
+    Ok((read_end.into(), write_end.into()))
+}
+
+/// Processes can't be spawned on other platforms, like WebAssembly ones,
+/// so there's nothing to connect pipe to
+#[cfg(not(any(unix, windows)))]
+fn os_pipe() -> io::Result<(File, File)> {
+    Err(io::ErrorKind::Unsupported.into())
+}
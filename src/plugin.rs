@@ -0,0 +1,164 @@
+//! Extension points around subprocess execution, see [`SubprocessTestPlugin`]
+use std::process::{Child, Command};
+
+use crate::{SubprocessResult, SubprocessTestConfig, command_line};
+
+/// Custom processing around subprocess execution, specified through `plugin` parameter.
+///
+/// All methods are called in parent process and do nothing by default.
+/// `pre_spawn` is called once per test, before subprocess command is prepared.
+/// When test is run several times, like in flakiness detection mode, other spawn-related
+/// methods are called for each run.
+///
+/// ```rust
+/// use subprocess_test::{SubprocessResult, SubprocessTestPlugin};
+///
+/// struct Shout;
+///
+/// impl SubprocessTestPlugin for Shout {
+///     fn post_wait(&self, result: &mut SubprocessResult) {
+///         result.output = result.output.to_uppercase();
+///     }
+/// }
+///
+/// subprocess_test::subprocess_test! {
+///     #[test(plugin = Shout)]
+///     fn shout() {
+///         print!("hello");
+///     }
+///     verify |success, output| {
+///         assert!(success);
+///         assert_eq!(output, "HELLO");
+///     }
+/// }
+/// ```
+pub trait SubprocessTestPlugin {
+    /// Called before subprocess command is prepared, allows to adjust any test parameters
+    /// by returning config with them changed, e.g. `config.timeout_secs(10)`.
+    /// Plugins added here are appended to chain, but their `pre_spawn` isn't called
+    fn pre_spawn(&self, config: SubprocessTestConfig) -> SubprocessTestConfig {
+        config
+    }
+    /// Called right before each subprocess run is spawned, allows to adjust its command
+    fn pre_spawn_command(&self, _command: &mut Command) {}
+    /// Called right after subprocess is spawned
+    fn post_spawn(&self, _child: &Child) {}
+    /// Called after subprocess exits and its result is collected, allows to adjust result
+    fn post_wait(&self, _result: &mut SubprocessResult) {}
+    /// Called after `verify` block completes without panic
+    fn post_verify(&self) {}
+}
+
+/// Sequence of plugins, each of which is called in order it was added
+#[derive(Default)]
+pub struct PluginChain(Vec<Box<dyn SubprocessTestPlugin>>);
+
+impl PluginChain {
+    /// Creates empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds plugin to the end of chain
+    pub fn push(&mut self, plugin: impl SubprocessTestPlugin + 'static) {
+        self.0.push(Box::new(plugin));
+    }
+    /// Number of plugins in chain
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Checks whether chain has no plugins
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Moves all plugins of `other` to the end of chain
+    pub(crate) fn append(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+}
+
+impl SubprocessTestPlugin for PluginChain {
+    fn pre_spawn(&self, config: SubprocessTestConfig) -> SubprocessTestConfig {
+        self.0
+            .iter()
+            .fold(config, |config, plugin| plugin.pre_spawn(config))
+    }
+
+    fn pre_spawn_command(&self, command: &mut Command) {
+        self.0
+            .iter()
+            .for_each(|plugin| plugin.pre_spawn_command(command));
+    }
+
+    fn post_spawn(&self, child: &Child) {
+        self.0.iter().for_each(|plugin| plugin.post_spawn(child));
+    }
+
+    fn post_wait(&self, result: &mut SubprocessResult) {
+        self.0.iter().for_each(|plugin| plugin.post_wait(result));
+    }
+
+    fn post_verify(&self) {
+        self.0.iter().for_each(|plugin| plugin.post_verify());
+    }
+}
+
+/// Prints duration of each subprocess run to `stderr`
+pub struct TimingPlugin;
+
+impl SubprocessTestPlugin for TimingPlugin {
+    fn post_wait(&self, result: &mut SubprocessResult) {
+        eprintln!("[timing] Subprocess took {}ms", result.duration.as_millis());
+    }
+}
+
+/// Prints command line, process ID and outcome of each subprocess run to `stderr`
+pub struct LoggingPlugin;
+
+impl SubprocessTestPlugin for LoggingPlugin {
+    fn pre_spawn_command(&self, command: &mut Command) {
+        eprintln!("[subprocess] Spawning: {}", command_line(command));
+    }
+
+    fn post_spawn(&self, child: &Child) {
+        eprintln!("[subprocess] Spawned process {}", child.id());
+    }
+
+    fn post_wait(&self, result: &mut SubprocessResult) {
+        let outcome = if result.success {
+            "succeeded"
+        } else {
+            "failed"
+        };
+        eprintln!(
+            "[subprocess] Subprocess {outcome}, {} bytes of output",
+            result.output.len()
+        );
+    }
+}
+
+/// Limits memory subprocess can use, same as `rlimit_as` parameter.
+/// Requires `rlimit` feature
+#[cfg(feature = "rlimit")]
+pub struct ResourceLimitPlugin {
+    memory_bytes: u64,
+}
+
+#[cfg(feature = "rlimit")]
+impl ResourceLimitPlugin {
+    /// Limits memory to specified number of bytes
+    pub fn memory_bytes(memory_bytes: u64) -> Self {
+        Self { memory_bytes }
+    }
+    /// Limits memory to specified number of megabytes, same as `memory_limit_mb` parameter
+    pub fn memory_mb(memory_mb: u64) -> Self {
+        Self::memory_bytes(memory_mb.saturating_mul(1024 * 1024))
+    }
+}
+
+#[cfg(feature = "rlimit")]
+impl SubprocessTestPlugin for ResourceLimitPlugin {
+    fn pre_spawn(&self, mut config: SubprocessTestConfig) -> SubprocessTestConfig {
+        config.rlimit_as = Some(self.memory_bytes);
+        config
+    }
+}
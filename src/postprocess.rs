@@ -0,0 +1,131 @@
+//! Post-processing of captured output, applied after boundaries are cut
+use std::path::Path;
+
+#[cfg(feature = "strip-ansi")]
+const ESC: char = '\x1b';
+#[cfg(feature = "strip-ansi")]
+const BEL: char = '\x07';
+
+/// Replaces Windows line endings with Unix ones
+pub(crate) fn normalize_line_endings(s: String) -> String {
+    if s.contains("\r\n") {
+        s.replace("\r\n", "\n")
+    } else {
+        s
+    }
+}
+
+/// Makes absolute paths inside `root` directory relative to it, by removing `root`
+/// prefix followed by path separator
+pub(crate) fn relative_paths(s: &str, root: &Path) -> String {
+    let root = root.to_string_lossy();
+    let root = root.trim_end_matches(['/', '\\']);
+    if root.is_empty() {
+        return s.to_owned();
+    }
+
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(at) = rest.find(root) {
+        let after = &rest[at + root.len()..];
+        match after.strip_prefix(['/', '\\']) {
+            Some(relative) => {
+                result.push_str(&rest[..at]);
+                rest = relative;
+            }
+            // Just some path which starts with same prefix
+            None => {
+                result.push_str(&rest[..at + root.len()]);
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Replaces runs of consecutive identical lines with single line, like `uniq` does.
+/// Lines which repeat non-consecutively are kept
+pub(crate) fn deduplicate_lines(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut previous = None;
+    for line in s.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if previous != Some(content) {
+            result.push_str(line);
+        }
+        previous = Some(content);
+    }
+    result
+}
+
+/// Removes panic backtraces, i.e. `stack backtrace:` line with indented frame lines after it,
+/// and notes which suggest setting `RUST_BACKTRACE`. Backtrace ends at first line
+/// which is blank or isn't indented
+pub(crate) fn strip_backtrace(s: &str) -> String {
+    const BACKTRACE_START: &str = "stack backtrace:";
+    const NOTE_PREFIXES: [&str; 2] = [
+        "note: run with `RUST_BACKTRACE=1`",
+        "note: Some details are omitted, run with `RUST_BACKTRACE=full`",
+    ];
+
+    let mut result = String::with_capacity(s.len());
+    let mut in_backtrace = false;
+    for line in s.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if in_backtrace {
+            if content.starts_with(char::is_whitespace) && !content.trim().is_empty() {
+                continue;
+            }
+            in_backtrace = false;
+        }
+        if content == BACKTRACE_START {
+            in_backtrace = true;
+        } else if !NOTE_PREFIXES
+            .iter()
+            .any(|prefix| content.starts_with(prefix))
+        {
+            result.push_str(line);
+        }
+    }
+    result
+}
+
+/// Removes ANSI escape sequences, i.e. CSI sequences like color codes `ESC [ 1;31 m`,
+/// OSC sequences like hyperlinks and window titles, terminated by `BEL` or `ESC \`,
+/// and two-character sequences like `ESC c`
+#[cfg(feature = "strip-ansi")]
+pub(crate) fn strip_ansi_escapes(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != ESC {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            // Parameter and intermediate bytes, then single final byte
+            Some('[') => {
+                for ch in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(ch) = chars.next() {
+                    if ch == BEL {
+                        break;
+                    }
+                    if ch == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Lone escape at the very end is dropped too
+            Some(_) | None => {}
+        }
+    }
+    result
+}
@@ -0,0 +1,68 @@
+//! JSON report of subprocess results, see `SUBPROCESS_TEST_JSON_REPORT` variable
+use std::env::var_os;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::SubprocessResult;
+
+const JSON_REPORT_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_JSON_REPORT";
+
+const JSON_REPORT_MAX_OUTPUT_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_JSON_REPORT_MAX_OUTPUT";
+
+const DEFAULT_MAX_OUTPUT: usize = 4096;
+
+/// Serializes appends from parallel tests of current process
+static REPORT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Appends record with subprocess result to report file, if report is requested
+pub(crate) fn append(test_path: &str, result: &SubprocessResult) {
+    let Some(path) = var_os(JSON_REPORT_ENV_VAR_NAME).filter(|path| !path.is_empty()) else {
+        return;
+    };
+    let max_output = std::env::var(JSON_REPORT_MAX_OUTPUT_ENV_VAR_NAME)
+        .ok()
+        .map(|max| {
+            max.parse().unwrap_or_else(|_| {
+                panic!("{JSON_REPORT_MAX_OUTPUT_ENV_VAR_NAME} must be number of bytes, got {max:?}")
+            })
+        })
+        .unwrap_or(DEFAULT_MAX_OUTPUT);
+
+    let record = serde_json::json!({
+        "test": test_path,
+        "success": result.success,
+        "duration_ms": result.duration.as_millis() as u64,
+        "output": truncate(&result.output, max_output),
+    });
+    let mut line = record.to_string();
+    line.push('\n');
+
+    let _guard = REPORT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    // Whole line is written at once in append mode, so records written
+    // by nested tests in other processes aren't interleaved with it
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to write JSON report to {}: {e}",
+                Path::new(&path).display()
+            )
+        });
+}
+
+/// Cuts string to at most `max_len` bytes, without splitting characters
+fn truncate(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let end = (0..=max_len)
+        .rev()
+        .find(|&end| s.is_char_boundary(end))
+        .unwrap_or(0);
+    &s[..end]
+}
@@ -0,0 +1,44 @@
+//! Detection of test harness which runs current test executable, and arguments used
+//! to re-invoke executable for single test
+use std::env::var_os;
+
+/// Set by cargo-nextest for every test process it spawns
+const NEXTEST_ENV_VAR_NAME: &str = "NEXTEST_VERSION";
+
+/// Test harness which launched current process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Runner {
+    /// `cargo test`, which runs all tests of executable inside single process
+    Libtest,
+    /// cargo-nextest, which runs each test in its own process
+    Nextest,
+}
+
+impl Runner {
+    pub(crate) fn detect() -> Self {
+        if var_os(NEXTEST_ENV_VAR_NAME).is_some() {
+            Self::Nextest
+        } else {
+            Self::Libtest
+        }
+    }
+
+    /// Arguments which make test executable run only specified test, even if it's ignored.
+    ///
+    /// Nextest requires test executables to accept libtest arguments, and invokes them
+    /// with same ones, so they work under both harnesses. Invoking `cargo nextest run`
+    /// instead would rebuild and re-list all tests, and report exit code of nextest
+    /// rather than of test itself
+    pub(crate) fn args(self, test_name: &str) -> Vec<&str> {
+        match self {
+            Self::Libtest | Self::Nextest => vec![
+                "--include-ignored",
+                "--nocapture",
+                "--quiet",
+                "--exact",
+                "--test",
+                test_name,
+            ],
+        }
+    }
+}
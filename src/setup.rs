@@ -0,0 +1,52 @@
+//! Parent-side preparation of subprocess, see `setup` block
+use std::any::Any;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Handle passed to `setup` block, which runs in parent process before subprocess is spawned.
+///
+/// Overrides subprocess configuration built from test parameters, and keeps fixtures
+/// created by `setup` block alive until test ends. Context is also available
+/// to `verify` block under same name, so fixtures can be accessed there through `get`
+#[derive(Default)]
+pub struct SubprocessContext {
+    pub(crate) envs: Vec<(OsString, OsString)>,
+    pub(crate) working_dir: Option<PathBuf>,
+    fixtures: Vec<Box<dyn Any>>,
+}
+
+impl SubprocessContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets environment variable for subprocess. Applied after environment is filtered
+    /// by `clear_env` or `inherit_env_prefix`, so variable is always passed
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.envs
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+    /// Overrides `working_dir` parameter
+    pub fn working_dir(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.working_dir = Some(path.as_ref().to_owned());
+        self
+    }
+    /// Keeps value alive until test ends, like `TempDir` with fixture files
+    pub fn keep<T: Any>(&mut self, fixture: T) -> &mut Self {
+        self.fixtures.push(Box::new(fixture));
+        self
+    }
+    /// Returns first fixture of specified type kept by `setup` block.
+    /// Panics if there's no such fixture
+    pub fn get<T: Any>(&self) -> &T {
+        self.fixtures
+            .iter()
+            .find_map(|fixture| fixture.downcast_ref())
+            .unwrap_or_else(|| {
+                panic!(
+                    "No fixture of type {} kept in setup context",
+                    std::any::type_name::<T>()
+                )
+            })
+    }
+}
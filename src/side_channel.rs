@@ -0,0 +1,76 @@
+//! Side channel used by subprocess to report structured data back to parent.
+//!
+//! Parent creates temporary file and passes its path to subprocess through environment variable.
+//! Subprocess appends records to that file, one per line, each consisting of record kind
+//! and payload separated by tab. Payload is escaped so it never contains newlines
+use std::env::var_os;
+use std::ffi::OsStr;
+use std::fs::{OpenOptions, read_to_string};
+use std::io::Write;
+
+use tempfile::NamedTempFile;
+
+pub(crate) const SIDE_CHANNEL_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_SIDE_CHANNEL__";
+
+/// Parent's end of side channel
+pub(crate) struct SideChannel(NamedTempFile);
+
+impl SideChannel {
+    pub(crate) fn new() -> Self {
+        Self(NamedTempFile::new().expect("Failed to create temporary file for side channel"))
+    }
+
+    pub(crate) fn path(&self) -> &OsStr {
+        self.0.path().as_os_str()
+    }
+    /// Reads all records sent by subprocess, as pairs of kind and payload
+    pub(crate) fn records(&self) -> Vec<(String, String)> {
+        read_to_string(self.0.path())
+            .expect("Failed to read side channel file")
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(kind, payload)| (kind.to_owned(), unescape(payload)))
+            .collect()
+    }
+}
+
+/// Sends record to parent. Returns `false` if there's no side channel,
+/// i.e. function is called outside of subprocess
+pub(crate) fn send(kind: &str, payload: &str) -> bool {
+    let Some(path) = var_os(SIDE_CHANNEL_ENV_VAR_NAME) else {
+        return false;
+    };
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .expect("Failed to open side channel file");
+    // Whole record is written at once, so concurrent writers don't interleave
+    file.write_all(format!("{kind}\t{}\n", escape(payload)).as_bytes())
+        .expect("Failed to write into side channel file");
+
+    true
+}
+
+fn escape(payload: &str) -> String {
+    payload.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(payload: &str) -> String {
+    let mut result = String::with_capacity(payload.len());
+    let mut chars = payload.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                result.push('\\');
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
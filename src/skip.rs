@@ -0,0 +1,65 @@
+//! Support for `skip_if` block, which skips test without spawning subprocess
+use std::env::var_os;
+use std::fs::OpenOptions;
+use std::io::{Write, stderr, stdout};
+use std::process::ExitCode;
+
+use crate::runner::Runner;
+
+/// Path of file to which names of skipped tests are appended under nextest
+const NEXTEST_SKIP_ENV_VAR_NAME: &str = "NEXTEST_SKIP";
+/// Set by nextest to identifier of test binary, e.g. `crate::test_file`
+const NEXTEST_BINARY_ID_ENV_VAR_NAME: &str = "NEXTEST_BINARY_ID";
+
+/// Value returned by test function when test is skipped
+pub trait Skipped {
+    fn skipped() -> Self;
+}
+
+impl Skipped for () {
+    fn skipped() -> Self {}
+}
+
+impl Skipped for ExitCode {
+    fn skipped() -> Self {
+        Self::SUCCESS
+    }
+}
+
+impl<T: Skipped, E> Skipped for Result<T, E> {
+    fn skipped() -> Self {
+        Ok(T::skipped())
+    }
+}
+
+/// Prints skip message in same format as test harness prints test status.
+///
+/// Nextest has no skip status either, and captures output of each test process, so skip
+/// line in nextest format goes to `stdout`, where it's shown with test output. If `NEXTEST_SKIP`
+/// is set, test name is also appended to file it names, so skips can be collected after run
+pub fn report(test_name: &str) {
+    match Runner::detect() {
+        Runner::Libtest => {
+            let _ = writeln!(stderr(), "test {test_name} ... SKIPPED");
+        }
+        Runner::Nextest => {
+            let binary_id = var_os(NEXTEST_BINARY_ID_ENV_VAR_NAME)
+                .map(|id| format!("{} ", id.to_string_lossy()))
+                .unwrap_or_default();
+            let _ = writeln!(stdout(), "        SKIP [         ] {binary_id}{test_name}");
+            if let Some(path) = var_os(NEXTEST_SKIP_ENV_VAR_NAME) {
+                let recorded = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| writeln!(file, "{test_name}"));
+                if let Err(err) = recorded {
+                    eprintln!(
+                        "Failed to record skipped test into {}: {err}",
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+}
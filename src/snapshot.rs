@@ -0,0 +1,83 @@
+//! Output snapshots compared through `snapshot` parameter, see `SUBPROCESS_TEST_UPDATE` variable,
+//! and golden files compared through `verify_golden` parameter
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tempfile::NamedTempFile;
+
+use crate::SNAPSHOT_UPDATE_ENV_VAR_NAME;
+#[cfg(feature = "diff-output")]
+use crate::env_flag;
+
+/// Compares output with snapshot of test, which must exist
+pub(crate) fn check(manifest_dir: &str, test_path: &str, output: &str) -> Result<(), String> {
+    let path = snapshot_path(manifest_dir, test_path);
+    match fs::read_to_string(&path) {
+        Ok(expected) if expected == output => Ok(()),
+        Ok(expected) => Err(format!(
+            "Subprocess output doesn't match snapshot {}\nExpected:\n{expected}\nActual:\n{output}\n\
+            Run with {SNAPSHOT_UPDATE_ENV_VAR_NAME}=1 to update snapshot",
+            path.display()
+        )),
+        Err(err) => Err(format!(
+            "Subprocess output can't be compared with snapshot {}: {err}\n\
+            Run with {SNAPSHOT_UPDATE_ENV_VAR_NAME}=1 to create it",
+            path.display()
+        )),
+    }
+}
+
+/// Overwrites snapshot of test with output, creating it if needed
+pub(crate) fn update(manifest_dir: &str, test_path: &str, output: &str) {
+    write_snapshot(&snapshot_path(manifest_dir, test_path), output);
+}
+
+/// Compares output with golden file, which must exist unless update is requested.
+/// When golden file update is requested, it's overwritten with output instead
+#[cfg(feature = "diff-output")]
+pub(crate) fn check_golden(path: &Path, output: &str) -> Result<(), String> {
+    use crate::GOLDEN_UPDATE_ENV_VAR_NAME;
+
+    if env_flag(GOLDEN_UPDATE_ENV_VAR_NAME) {
+        write_snapshot(path, output);
+        return Ok(());
+    }
+
+    let expected = fs::read_to_string(path).map_err(|err| {
+        format!(
+            "can't be compared with golden file {}: {err}\n\
+            Run with {GOLDEN_UPDATE_ENV_VAR_NAME}=1 to create it",
+            path.display()
+        )
+    })?;
+    // Golden files may be checked out with CRLF on Windows, while `println!` output has LF
+    let expected = expected.replace("\r\n", "\n");
+    match crate::assertions::line_diff(output, &expected) {
+        None => Ok(()),
+        Some(diff) => Err(format!(
+            "differs from golden file {} (-expected +actual):\n{diff}\n\
+            Run with {GOLDEN_UPDATE_ENV_VAR_NAME}=1 to update it",
+            path.display()
+        )),
+    }
+}
+
+fn snapshot_path(manifest_dir: &str, test_path: &str) -> PathBuf {
+    Path::new(manifest_dir)
+        .join("tests")
+        .join("subprocess_snapshots")
+        .join(format!("{}.txt", test_path.replace("::", ".")))
+}
+
+/// Snapshot is replaced atomically, so concurrent readers never see partially written file
+fn write_snapshot(path: &Path, output: &str) {
+    let dir = path.parent().expect("Snapshot path always has parent");
+    fs::create_dir_all(dir).expect("Failed to create snapshot directory");
+
+    let mut file = NamedTempFile::new_in(dir).expect("Failed to create snapshot file");
+    file.write_all(output.as_bytes())
+        .expect("Failed to write snapshot file");
+    file.persist(path)
+        .unwrap_or_else(|err| panic!("Failed to write snapshot {}: {err}", path.display()));
+}
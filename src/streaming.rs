@@ -0,0 +1,91 @@
+//! Line-by-line delivery of subprocess output while it runs, see `on_output_line` parameter
+use crate::SECTION_MARK_PREFIX;
+use crate::pipe::PipeReader;
+
+/// Callback which receives output lines, without line terminators
+pub(crate) type LineCallback<'a> = Box<dyn Fn(&str) + 'a>;
+
+/// Reader of output which is being written by subprocess.
+///
+/// Output is taken from pipe reader, which keeps it as a whole for later processing,
+/// so stream tracks how much of it was already consumed. Only lines between boundaries
+/// are delivered; boundaries, section marks and line breaks printed before them
+/// aren't part of output, so they're skipped
+pub(crate) struct LineStream {
+    reader: PipeReader,
+    // Length of output already moved into `buffer`
+    offset: usize,
+    buffer: Vec<u8>,
+    boundary: String,
+    inside: bool,
+    finished: bool,
+    // Empty line is delivered only after next line turns out to be neither boundary nor mark
+    pending_empty: bool,
+}
+
+impl LineStream {
+    pub(crate) fn new(boundary: &str, reader: PipeReader) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            buffer: Vec::new(),
+            boundary: boundary.trim_matches('\n').to_owned(),
+            inside: false,
+            finished: false,
+            pending_empty: false,
+        }
+    }
+    /// Delivers all complete lines written since previous call
+    pub(crate) fn poll(&mut self, callback: &dyn Fn(&str)) {
+        self.reader.with_bytes(|bytes| {
+            self.buffer.extend_from_slice(&bytes[self.offset..]);
+            self.offset = bytes.len();
+        });
+
+        while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<_> = self.buffer.drain(..=end).collect();
+            self.process(&String::from_utf8_lossy(&line[..end]), callback);
+        }
+    }
+    /// Delivers rest of output after subprocess exits, in case it didn't print closing boundary
+    pub(crate) fn finish(mut self, callback: &dyn Fn(&str)) {
+        self.reader.wait();
+        self.poll(callback);
+        if self.inside && !self.finished {
+            if self.pending_empty {
+                callback("");
+            }
+            if !self.buffer.is_empty() {
+                callback(&String::from_utf8_lossy(&self.buffer));
+            }
+        }
+    }
+
+    fn process(&mut self, line: &str, callback: &dyn Fn(&str)) {
+        if self.finished {
+            return;
+        }
+        if line == self.boundary {
+            self.finished = self.inside;
+            self.inside = true;
+            self.pending_empty = false;
+            return;
+        }
+        if !self.inside {
+            return;
+        }
+        if line.starts_with(SECTION_MARK_PREFIX.trim_start_matches('\n')) {
+            self.pending_empty = false;
+            return;
+        }
+        if self.pending_empty {
+            callback("");
+            self.pending_empty = false;
+        }
+        if line.is_empty() {
+            self.pending_empty = true;
+        } else {
+            callback(line);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Named steps within single subprocess test body, see [`subtest!`](crate::subtest)
+use std::any::Any;
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+
+use crate::side_channel;
+
+const SUBTEST_PASSED: &str = "subtest-passed";
+const SUBTEST_FAILED: &str = "subtest-failed";
+
+/// Runs named step of subprocess test body and reports its outcome to parent.
+///
+/// Panic inside subtest doesn't stop subprocess test body, so all following subtests
+/// are still run. After subprocess exits, parent fails test with list of all failed subtests,
+/// each with its name and panic message. Outcomes of all subtests are available through
+/// [`SubprocessResult::subtests`](crate::SubprocessResult::subtests).
+///
+/// When called outside of subprocess, just runs closure as is.
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test]
+///     fn steps() {
+///         subprocess_test::subtest!("first", || assert_eq!(1 + 1, 2));
+///         subprocess_test::subtest!("second", || assert_eq!(2 + 2, 4));
+///     }
+///     verify |result| {
+///         assert_eq!(result.subtests.len(), 2);
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subtest {
+    ($subtest_name:expr, $subtest_fn:expr $(,)?) => {
+        $crate::subtest::run_subtest($subtest_name, $subtest_fn)
+    };
+}
+
+/// Outcome of single [`subtest!`](crate::subtest) invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SubtestResult {
+    /// Name of subtest
+    pub name: String,
+    /// Panic message if subtest failed, `None` if it passed
+    pub failure: Option<String>,
+}
+
+#[doc(hidden)]
+pub fn run_subtest(name: &str, subtest_fn: impl FnOnce()) {
+    match catch_unwind(AssertUnwindSafe(subtest_fn)) {
+        Ok(()) => {
+            side_channel::send(SUBTEST_PASSED, name);
+        }
+        Err(payload) => {
+            let message = panic_message(&*payload);
+            if !side_channel::send(SUBTEST_FAILED, &format!("{name}\t{message}")) {
+                resume_unwind(payload);
+            }
+        }
+    }
+}
+
+/// Collects subtest outcomes from side channel records
+pub(crate) fn collect(records: &[(String, String)]) -> Vec<SubtestResult> {
+    records
+        .iter()
+        .filter_map(|(kind, payload)| match kind.as_str() {
+            SUBTEST_PASSED => Some(SubtestResult {
+                name: payload.clone(),
+                failure: None,
+            }),
+            SUBTEST_FAILED => {
+                let (name, message) = payload.split_once('\t').unwrap_or((payload, ""));
+                Some(SubtestResult {
+                    name: name.to_owned(),
+                    failure: Some(message.to_owned()),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Panics with list of failed subtests, if there are any
+pub(crate) fn check(subtests: &[SubtestResult]) {
+    let failures: Vec<_> = subtests
+        .iter()
+        .filter_map(|subtest| {
+            let failure = subtest.failure.as_ref()?;
+            Some(format!("Subtest '{}' failed: {failure}", subtest.name))
+        })
+        .collect();
+
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} subtests failed:\n{}",
+            failures.len(),
+            subtests.len(),
+            failures.join("\n")
+        );
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -0,0 +1,82 @@
+//! Tracing of system calls made by subprocess, see `trace_syscalls` parameter
+//!
+//! Tracer writes trace into separate file rather than subprocess output,
+//! so `verify` block sees same output as without tracing
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+#[cfg(target_os = "linux")]
+pub(crate) const TRACER: Option<&str> = Some("strace");
+/// `dtruss` prints trace into `stderr` of traced process, so `dtrace` it's built on
+/// is used directly, with similar script
+#[cfg(target_vendor = "apple")]
+pub(crate) const TRACER: Option<&str> = Some("dtrace");
+#[cfg(not(any(target_os = "linux", target_vendor = "apple")))]
+pub(crate) const TRACER: Option<&str> = None;
+
+#[cfg(target_vendor = "apple")]
+const DTRACE_SCRIPT: &str = r#"syscall:::entry /pid == $target/ { printf("%s\n", probefunc); }"#;
+
+/// Trace file shared by all runs of subprocess, so it holds trace of last one
+pub(crate) struct SyscallTrace {
+    tracer: PathBuf,
+    dir: TempDir,
+}
+
+impl SyscallTrace {
+    pub(crate) fn new(tracer: PathBuf) -> Self {
+        let dir = TempDir::new().expect("Failed to create temporary directory for syscall trace");
+        Self { tracer, dir }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.path().join("trace.txt")
+    }
+
+    /// Command line which runs `program` with its arguments under tracer.
+    /// Test harness runs test body in separate thread, so all threads are traced
+    #[cfg(not(target_vendor = "apple"))]
+    pub(crate) fn wrap(&self, program: Vec<OsString>) -> Vec<OsString> {
+        let mut command_line = vec![
+            self.tracer.clone().into(),
+            "-f".into(),
+            "-o".into(),
+            self.path().into(),
+        ];
+        command_line.extend(program);
+        command_line
+    }
+
+    /// Command line which runs `program` with its arguments under tracer. `dtrace` splits
+    /// command by whitespace on its own, so arguments can't contain whitespace
+    #[cfg(target_vendor = "apple")]
+    pub(crate) fn wrap(&self, program: Vec<OsString>) -> Vec<OsString> {
+        let mut command = OsString::new();
+        for (index, arg) in program.iter().enumerate() {
+            if index > 0 {
+                command.push(" ");
+            }
+            command.push(arg);
+        }
+        vec![
+            self.tracer.clone().into(),
+            "-q".into(),
+            "-o".into(),
+            self.path().into(),
+            "-n".into(),
+            DTRACE_SCRIPT.into(),
+            "-c".into(),
+            command,
+        ]
+    }
+
+    /// Trace of last run, empty if tracer didn't write anything
+    pub(crate) fn read(&self) -> String {
+        fs::read(self.path())
+            .map(|trace| String::from_utf8_lossy(&trace).into_owned())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,59 @@
+//! Registry of threads spawned by test body, which are joined before closing boundary
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle, Thread};
+
+/// Process-wide rather than thread-local, so threads spawned by other registered threads
+/// are joined too. Subprocess runs single test, so there's no interference between tests
+static THREADS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Spawns thread whose output is guaranteed to be captured, for tests with
+/// `join_threads = true`. Such tests join all threads spawned this way after test body
+/// finishes and before closing output boundary is printed, so output of threads which outlive
+/// test body isn't cut off or mixed with boundary. Test fails if any of such threads panics.
+///
+/// Returns handle of spawned thread; thread can't be joined by caller, since its join handle
+/// is kept by registry. Outside of `join_threads` tests, it's same as `std::thread::spawn`
+/// with result discarded
+///
+/// ```rust
+/// subprocess_test::subprocess_test! {
+///     #[test(join_threads = true)]
+///     fn escaping_thread() {
+///         subprocess_test::subprocess_spawn(|| {
+///             std::thread::sleep(std::time::Duration::from_millis(50));
+///             println!("Late");
+///         });
+///     }
+///     verify |success, output| {
+///         assert!(success);
+///         assert_eq!(output, "Late\n");
+///     }
+/// }
+/// ```
+pub fn subprocess_spawn(f: impl FnOnce() + Send + 'static) -> Thread {
+    let handle = thread::spawn(f);
+    let thread = handle.thread().clone();
+    lock().push(handle);
+    thread
+}
+
+/// Joins all registered threads, including ones spawned while joining.
+/// Returns number of threads which panicked
+pub(crate) fn join_all() -> usize {
+    let mut panicked = 0;
+    // Lock isn't held while joining, so threads can spawn more threads
+    loop {
+        let Some(handle) = lock().pop() else {
+            break;
+        };
+        if handle.join().is_err() {
+            panicked += 1;
+        }
+    }
+    panicked
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<JoinHandle<()>>> {
+    // Registry contents stay valid even if some thread panicked while holding lock
+    THREADS.lock().unwrap_or_else(|e| e.into_inner())
+}
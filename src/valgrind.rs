@@ -0,0 +1,47 @@
+//! Running subprocess under Valgrind, see `SUBPROCESS_TEST_VALGRIND` variable
+//!
+//! Valgrind writes its report into separate log file rather than subprocess output,
+//! so it doesn't interfere with `verify` block. Command is prepared once for all runs
+//! of subprocess, so log path is passed through environment variable, which Valgrind
+//! expands in `--log-file` option itself
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+pub(crate) const VALGRIND_ENV_VAR_NAME: &str = "SUBPROCESS_TEST_VALGRIND";
+
+const LOG_ENV_VAR_NAME: &str = "__SUBPROCESS_TEST_VALGRIND_LOG__";
+
+/// Command line which runs `program` with its arguments under Valgrind,
+/// failing if it detects any errors or leaks
+pub(crate) fn wrap(valgrind: PathBuf, program: Vec<OsString>) -> Vec<OsString> {
+    let mut command_line = vec![
+        valgrind.into(),
+        "--error-exitcode=1".into(),
+        "--leak-check=full".into(),
+        format!("--log-file=%q{{{LOG_ENV_VAR_NAME}}}").into(),
+    ];
+    command_line.extend(program);
+    command_line
+}
+
+/// Log file of single subprocess run
+pub(crate) struct ValgrindLog(TempDir);
+
+impl ValgrindLog {
+    pub(crate) fn new(command: &mut Command) -> Self {
+        let dir = TempDir::new().expect("Failed to create temporary directory for Valgrind log");
+        command.env(LOG_ENV_VAR_NAME, dir.path().join("valgrind.log"));
+        Self(dir)
+    }
+
+    /// Valgrind report, empty if Valgrind didn't write anything
+    pub(crate) fn read(&self) -> String {
+        fs::read(self.0.path().join("valgrind.log"))
+            .map(|log| String::from_utf8_lossy(&log).into_owned())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "count-allocations")]
+
+subprocess_test::subprocess_test! {
+    #[test(capture_allocations = true)]
+    fn vec_push_allocates_once() {
+        let mut values = std::hint::black_box(Vec::new());
+        values.push(1u32);
+        std::hint::black_box(&values);
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.alloc_count, Some(1));
+        assert_eq!(result.dealloc_count, Some(1));
+    }
+
+    #[test(capture_allocations = true)]
+    fn boxes_are_counted() {
+        let boxes: Vec<_> = (0..10).map(Box::new).collect();
+        std::mem::forget(std::hint::black_box(boxes));
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.alloc_count, Some(11));
+        assert_eq!(result.dealloc_count, Some(0));
+    }
+
+    #[test]
+    fn allocations_not_captured_by_default() {
+        let _ = std::hint::black_box(Box::new(1));
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.alloc_count, None);
+        assert_eq!(result.dealloc_count, None);
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(any(feature = "tokio", feature = "async-std"))]
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn sync_before_async() {
+        println!("Sync");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Sync\n");
+    }
+
+    #[test(runtime = "tokio")]
+    #[cfg(feature = "tokio")]
+    async fn tokio_body() {
+        let flavor = tokio::runtime::Handle::current().runtime_flavor();
+        assert_eq!(flavor, tokio::runtime::RuntimeFlavor::MultiThread);
+        let answer = tokio::spawn(async { 40 + 2 }).await.unwrap();
+        println!("{answer}");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "42\n");
+    }
+
+    /// Tokio is default runtime
+    #[test]
+    #[cfg(feature = "tokio")]
+    async fn default_runtime_result() -> Result<(), String> {
+        tokio::task::yield_now().await;
+        print!("Yielded");
+        Ok(())
+    }
+    verify |success, output| {
+        if success && output == "Yielded" {
+            Ok(())
+        } else {
+            Err(output)
+        }
+    }
+
+    #[test(runtime = "async-std")]
+    #[cfg(feature = "async-std")]
+    async fn async_std_body() {
+        let answer = async_std::task::spawn(async { 40 + 2 }).await;
+        println!("{answer}");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "42\n");
+    }
+
+    #[test]
+    fn sync_after_async() {
+        println!("Sync");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Sync\n");
+    }
+}
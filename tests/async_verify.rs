@@ -0,0 +1,68 @@
+#![cfg(feature = "tokio")]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Echoes single message through local TCP connection
+async fn echo(message: &str) -> std::io::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).await?;
+        stream.write_all(&buffer).await?;
+        std::io::Result::Ok(())
+    });
+
+    let mut stream = TcpStream::connect(address).await?;
+    stream.write_all(message.as_bytes()).await?;
+    stream.shutdown().await?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).await?;
+    server.await.unwrap()?;
+
+    Ok(reply)
+}
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn async_verify_destructured() {
+        print!("Ping");
+    }
+    verify async |success, output| {
+        assert!(success);
+        assert_eq!(echo(&output).await.unwrap(), "Ping");
+    }
+
+    #[test]
+    fn async_verify_whole_result() -> std::io::Result<()> {
+        print!("Pong");
+        Ok(())
+    }
+    verify async |result| {
+        assert!(result.success);
+        assert_eq!(echo(&result.output).await?, "Pong");
+        Ok(())
+    }
+
+    #[test]
+    fn async_verify_question_mark() {
+        print!("42");
+    }
+    verify async |success, output| {
+        assert!(success);
+        let echoed: u32 = echo(&output).await?.parse()?;
+        assert_eq!(echoed, 42);
+    }
+
+    #[test]
+    fn async_verify_exit_code() {
+        print!("Exit");
+        std::process::exit(2);
+    }
+    verify async |exit_code, output| {
+        assert_eq!(exit_code, Some(2));
+        assert_eq!(echo(&output).await.unwrap(), "Exit");
+    }
+}
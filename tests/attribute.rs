@@ -0,0 +1,68 @@
+#![cfg(feature = "attribute")]
+
+// `subprocess_verify` is consumed by `subprocess_test` and so doesn't need import
+use subprocess_test::attr::subprocess_test;
+
+#[subprocess_test]
+fn attribute_success() {
+    let value = 1;
+    assert_eq!(value + 1, 2);
+}
+
+/// Other attributes can go in any order, and `verify` closure can be anywhere
+/// after main attribute
+#[subprocess_test(output_boundary = "<><><>", timeout_secs = 10)]
+#[cfg_attr(miri, ignore)]
+#[subprocess_verify(|success, output| {
+    assert!(success);
+    assert_eq!(output, "Foo\nBar\n");
+})]
+#[allow(unused_mut)]
+fn attribute_verify() {
+    println!("Foo");
+    eprintln!("Bar");
+}
+
+#[subprocess_test]
+#[subprocess_test::attr::subprocess_verify(|result| assert_eq!(result.exit_code, Some(101)))]
+fn attribute_panic() {
+    panic!("Fails on purpose");
+}
+
+#[subprocess_test]
+#[subprocess_verify(|success, output| {
+    if success && output == "Ok" { Ok(()) } else { Err(output) }
+})]
+fn attribute_result() -> Result<(), String> {
+    print!("Ok");
+    Ok(())
+}
+
+// Both forms can be mixed in same module
+subprocess_test::subprocess_test! {
+    #[test]
+    fn macro_alongside_attribute() {
+        print!("Macro");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Macro");
+    }
+}
+
+mod renamed {
+    extern crate subprocess_test as renamed_subprocess_test;
+
+    /// Renamed dependency is passed through `crate` parameter
+    #[renamed_subprocess_test::attr::subprocess_test(
+        crate = "renamed_subprocess_test",
+        timeout_secs = 10
+    )]
+    #[subprocess_verify(|success, output| {
+        assert!(success);
+        assert_eq!(output, "Renamed");
+    })]
+    fn attribute_renamed_crate() {
+        print!("Renamed");
+    }
+}
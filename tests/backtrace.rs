@@ -0,0 +1,40 @@
+subprocess_test::subprocess_test! {
+    #[test(strip_backtrace = true, expect_failure = 101)]
+    fn backtrace_panic() {
+        panic!("oops");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.contains("panicked at"), "{output}");
+        assert!(output.contains("oops\n"), "{output}");
+        assert!(!output.contains("RUST_BACKTRACE"), "{output}");
+    }
+
+    #[test(strip_backtrace = true)]
+    fn backtrace_stripped() {
+        assert_eq!(std::env::var("RUST_BACKTRACE").as_deref(), Ok("0"));
+        println!("Before");
+        println!("stack backtrace:");
+        println!("   0: rust_begin_unwind");
+        println!("             at /rustc/library/std/src/panicking.rs:665:5");
+        println!("   1: backtrace::main");
+        println!("note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.");
+        println!("note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace");
+        println!("  Indented");
+        println!("After");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Before\n  Indented\nAfter\n");
+    }
+
+    #[test]
+    fn backtrace_kept() {
+        println!("stack backtrace:");
+        println!("   0: rust_begin_unwind");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "stack backtrace:\n   0: rust_begin_unwind\n");
+    }
+}
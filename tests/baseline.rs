@@ -0,0 +1,29 @@
+use std::fs;
+use std::path::PathBuf;
+
+fn baseline_file(test_name: &str) -> PathBuf {
+    std::env::current_exe()
+        .unwrap()
+        .ancestors()
+        .nth(3)
+        .unwrap()
+        .join("subprocess-test-baseline")
+        .join(format!("{test_name}.json"))
+}
+
+subprocess_test::subprocess_test! {
+    #[test(regression_threshold_pct = 1000, baseline_runs = 3)]
+    fn baseline_recorded() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    verify |result| {
+        assert!(result.success);
+        let baseline = fs::read_to_string(baseline_file("baseline_recorded")).unwrap();
+        let (_, durations) = baseline.split_once("\"durations_ms\": [").unwrap();
+        let (durations, _) = durations.split_once(']').unwrap();
+        let durations: Vec<u64> = durations.split(", ").map(|d| d.parse().unwrap()).collect();
+        assert!((1..=3).contains(&durations.len()), "{baseline}");
+        assert_eq!(*durations.last().unwrap(), result.duration.as_millis() as u64);
+        assert!(baseline.contains("\"mean_duration_ms\": "), "{baseline}");
+    }
+}
@@ -0,0 +1,60 @@
+#![cfg(all(unix, feature = "chaos-testing"))]
+
+use std::time::Duration;
+
+subprocess_test::subprocess_test! {
+    #[test(inject_fault = "malloc_fail_after = 50")]
+    #[cfg(target_os = "linux")]
+    fn malloc_failure_recovered() {
+        let mut kept = Vec::with_capacity(100);
+        let mut failures = 0;
+        for _ in 0..100 {
+            let mut buffer = Vec::<u8>::new();
+            match buffer.try_reserve(64) {
+                Ok(()) => kept.push(buffer),
+                Err(_) => failures += 1,
+            }
+        }
+        println!("Allocated {}, failed {failures}", kept.len());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        // Exact numbers depend on allocations made by test harness around body
+        let (allocated, failed) = output
+            .trim_end()
+            .strip_prefix("Allocated ")
+            .and_then(|rest| rest.split_once(", failed "))
+            .unwrap();
+        assert!(allocated.parse::<u32>().unwrap() < 50, "{output}");
+        assert!(failed.parse::<u32>().unwrap() > 50, "{output}");
+    }
+
+    #[test(inject_fault = "malloc_fail_after = 0")]
+    #[cfg(target_os = "linux")]
+    fn malloc_failure_aborts() {
+        let buffer = Vec::<u8>::with_capacity(1024);
+        println!("Allocated {}", buffer.capacity());
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("memory allocation of 1024 bytes failed"), "{output}");
+    }
+
+    #[test(inject_fault = "sigsegv_after_ms = 50")]
+    fn delayed_sigsegv() {
+        std::thread::sleep(Duration::from_secs(10));
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert!(result.duration < Duration::from_secs(10), "{result:?}");
+    }
+
+    #[test(inject_fault = "sigfpe")]
+    fn immediate_sigfpe() {
+        std::thread::sleep(Duration::from_secs(10));
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert!(result.duration < Duration::from_secs(10), "{result:?}");
+    }
+}
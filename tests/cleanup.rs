@@ -0,0 +1,49 @@
+#![cfg(all(target_os = "linux", feature = "windows-cleanup"))]
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+fn pid_path() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("cleanup_orphan_pid.txt")
+}
+
+/// Zombie isn't running anymore, it's just not reaped yet by its new parent
+fn is_running(pid: &str) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat.rsplit_once(") ")
+            .is_some_and(|(_, rest)| !rest.starts_with('Z'))
+    })
+}
+
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `orphan_killed_with_parent` below and never finishes
+    #[test]
+    #[ignore = "never finishes, invoked by orphan_killed_with_parent"]
+    fn orphan_target() {
+        std::fs::write(pid_path(), std::process::id().to_string()).unwrap();
+        std::thread::sleep(Duration::from_secs(60));
+    }
+
+    #[test]
+    fn orphan_killed_with_parent() {
+        let _ = std::fs::remove_file(pid_path());
+        std::thread::spawn(orphan_target);
+        while !pid_path().exists() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // Dies while its own subprocess is still running
+        std::process::exit(0);
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        let pid = std::fs::read_to_string(pid_path()).unwrap();
+        let started_at = Instant::now();
+        while is_running(&pid) {
+            assert!(
+                started_at.elapsed() < Duration::from_secs(10),
+                "Orphaned subprocess {pid} is still running"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "context")]
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct ServerContext {
+    port: u16,
+}
+
+impl ServerContext {
+    fn new() -> Self {
+        // Runs only in parent process
+        assert!(std::env::var_os("__TEST_RUN_SUBPROCESS__").is_none());
+        Self { port: 8080 }
+    }
+}
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn context_received(ctx: ServerContext = ServerContext::new()) {
+        println!("Listening on port {}", ctx.port);
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Listening on port 8080\n");
+    }
+
+    // Context value is arbitrary expression, and works together with other parameters
+    #[test(env_var_name = "__CONTEXT_SUBPROCESS__")]
+    fn context_with_custom_var(port: u16 = 9000 + 1) {
+        assert_eq!(port, 9001);
+    }
+}
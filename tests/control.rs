@@ -0,0 +1,55 @@
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+subprocess_test::subprocess_test! {
+    // Subprocess keeps running after test body returns, until it's stopped
+    #[test]
+    fn control_stop() {
+        spawn(|| loop {
+            println!("Tick");
+            sleep(Duration::from_millis(20));
+        });
+    }
+    control |ctl| {
+        assert_ne!(ctl.pid(), 0);
+        sleep(Duration::from_millis(200));
+        ctl.stop();
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert!(result.duration >= Duration::from_millis(200), "{result:?}");
+        assert!(result.output.matches("Tick\n").count() > 1, "{}", result.output);
+    }
+
+    // Subprocess is stopped once block returns
+    #[test]
+    fn control_implicit_stop() {
+        println!("Started");
+    }
+    control |_ctl| {}
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Started\n");
+    }
+
+    #[test]
+    #[ignore = "fails on purpose, invoked by control_panic_kills_subprocess"]
+    fn control_panic_target() {
+        spawn(|| loop {
+            sleep(Duration::from_millis(20));
+        });
+    }
+    control |_ctl| {
+        panic!("Control failed");
+    }
+
+    // Subprocess of target would run forever if it weren't killed
+    #[test]
+    fn control_panic_kills_subprocess() {
+        control_panic_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Control failed"), "{output}");
+    }
+}
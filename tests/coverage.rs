@@ -0,0 +1,86 @@
+#![cfg(feature = "llvm-cov")]
+
+use std::path::Path;
+
+#[cfg(unix)]
+const FAKE_PROFDATA_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/fake_llvm_profdata");
+
+subprocess_test::subprocess_test! {
+    #[test]
+    #[ignore = "prints its profile path, invoked by coverage_profile_path"]
+    fn coverage_profile_target() {
+        print!("{}", std::env::var("LLVM_PROFILE_FILE").unwrap());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        print!("{output}");
+    }
+
+    // Parent of target test is run with profile path set, like it's run by coverage tool
+    #[test]
+    fn coverage_profile_path() {
+        coverage_profile_target();
+    }
+    setup |ctx| {
+        ctx.env("LLVM_PROFILE_FILE", Path::new("cov").join("default_%m.profraw"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        let path = Path::new(&output);
+        assert_eq!(path.parent(), Some(Path::new("cov")));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("default_%m-coverage_profile_target-"), "{name}");
+        assert!(name.ends_with(".profraw"), "{name}");
+    }
+
+    // Profile path set by caller's body is replaced too
+    #[test]
+    fn coverage_profile_path_nested() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe { std::env::set_var("LLVM_PROFILE_FILE", "nested.profraw") };
+        coverage_profile_target();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.starts_with("nested-coverage_profile_target-"), "{output}");
+        assert!(output.ends_with(".profraw"), "{output}");
+    }
+
+    #[test]
+    #[ignore = "writes fake profile, invoked by coverage_profile_merged"]
+    fn coverage_merge_target() {
+        let profile = std::env::var("LLVM_PROFILE_FILE").unwrap().replace("%m", "123");
+        std::fs::write(profile, "Raw").unwrap();
+    }
+
+    // Fake records its arguments and writes merged profile, like `llvm-profdata merge`
+    #[test]
+    #[cfg(unix)]
+    fn coverage_profile_merged() {
+        coverage_merge_target();
+        print!("{}", std::fs::read_to_string(format!("{FAKE_PROFDATA_DIR}/args")).unwrap());
+    }
+    setup |ctx| {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = format!("{FAKE_PROFDATA_DIR}/llvm-profdata");
+        let _ = std::fs::remove_dir_all(FAKE_PROFDATA_DIR);
+        std::fs::create_dir_all(FAKE_PROFDATA_DIR).unwrap();
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho \"$@\" > {FAKE_PROFDATA_DIR}/args\nfor out; do :; done\necho Merged > \"$out\"\n"),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        ctx.env("PATH", FAKE_PROFDATA_DIR);
+        ctx.env("LLVM_PROFILE_FILE", format!("{FAKE_PROFDATA_DIR}/default_%m.profraw"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        let raw_profile = format!("merge -sparse {FAKE_PROFDATA_DIR}/default_123-coverage_merge_target-");
+        assert!(output.starts_with(&raw_profile), "{output}");
+        let merged = format!("{FAKE_PROFDATA_DIR}/default-subprocesses.profdata");
+        assert!(output.contains(&format!(".profraw -o {merged}.")), "{output}");
+        assert_eq!(std::fs::read_to_string(merged).unwrap(), "Merged\n");
+    }
+}
@@ -0,0 +1,12 @@
+#![cfg(feature = "cpu-affinity")]
+
+subprocess_test::subprocess_test! {
+    #[test(cpu_affinity = [0])]
+    fn pinned_to_single_core() {
+        assert_eq!(std::env::var("SUBPROCESS_TEST_CPU_AFFINITY").unwrap(), "0");
+        // Available parallelism respects affinity mask on Linux
+        if cfg!(target_os = "linux") {
+            assert_eq!(std::thread::available_parallelism().unwrap().get(), 1);
+        }
+    }
+}
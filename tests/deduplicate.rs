@@ -0,0 +1,47 @@
+subprocess_test::subprocess_test! {
+    #[test(deduplicate_output = true)]
+    fn deduplicate_repeated_line() {
+        for _ in 0..10000 {
+            println!("Progress");
+        }
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Progress\n");
+    }
+
+    #[test(deduplicate_output = true)]
+    fn deduplicate_consecutive_only() {
+        println!("First");
+        println!("First");
+        println!("Second");
+        println!("First");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "First\nSecond\nFirst\n");
+    }
+
+    #[test(deduplicate_output = true)]
+    fn deduplicate_split_output() {
+        println!("Out");
+        println!("Out");
+        eprintln!("Err");
+        eprintln!("Err");
+    }
+    verify |success, stdout, stderr| {
+        assert!(success);
+        assert_eq!(stdout, "Out\n");
+        assert_eq!(stderr, "Err\n");
+    }
+
+    #[test]
+    fn deduplicate_disabled() {
+        println!("Same");
+        println!("Same");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Same\nSame\n");
+    }
+}
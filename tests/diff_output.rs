@@ -0,0 +1,52 @@
+#![cfg(feature = "diff-output")]
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn diff_output_equal() {
+        println!("First");
+        println!("Second");
+    }
+    verify |success, output| {
+        assert!(success);
+        subprocess_test::assert_subprocess_output_eq!(output, "First\nSecond\n");
+    }
+
+    #[test]
+    fn diff_output_mismatch() {
+        for number in 1..=11 {
+            println!("Line {number}");
+        }
+        print!("Last");
+    }
+    verify |success, output| {
+        assert!(success);
+        let failure = std::panic::catch_unwind(|| {
+            let expected: String = (1..=12)
+                .map(|number| match number {
+                    2 => "Changed\n".to_owned(),
+                    12 => "Last\n".to_owned(),
+                    number => format!("Line {number}\n"),
+                })
+                .collect();
+            subprocess_test::assert_subprocess_output_eq!(output, expected);
+        })
+        .unwrap_err();
+        let message = failure.downcast_ref::<String>().unwrap();
+        assert_eq!(
+            message,
+            "Output differs from expected (-expected +actual):\n\
+             \x20   1    1 | Line 1\n\
+             -   2      | Changed\n\
+             +        2 | Line 2\n\
+             \x20   3    3 | Line 3\n\
+             \x20   4    4 | Line 4\n\
+             \x20   5    5 | Line 5\n\
+             ...\n\
+             \x20   9    9 | Line 9\n\
+             \x20  10   10 | Line 10\n\
+             \x20  11   11 | Line 11\n\
+             -  12      | Last\n\
+             +       12 | Last (no newline at end)"
+        );
+    }
+}
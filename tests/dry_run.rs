@@ -0,0 +1,35 @@
+use std::env::var_os;
+
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `dry_run` below. It fails only when spawned
+    // with dry-run variable inherited, which is exactly what must not happen
+    #[test]
+    fn dry_run_target() {
+        assert!(
+            var_os("SUBPROCESS_TEST_DRY_RUN").is_none(),
+            "Subprocess must not be spawned in dry-run mode"
+        );
+        println!("Spawned");
+    }
+    verify |success, output| {
+        assert!(success);
+        if var_os("SUBPROCESS_TEST_DRY_RUN").is_some() {
+            assert_eq!(output, "");
+        } else {
+            assert_eq!(output, "Spawned\n");
+        }
+    }
+
+    #[test]
+    fn dry_run() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe { std::env::set_var("SUBPROCESS_TEST_DRY_RUN", "1") };
+        dry_run_target();
+    }
+    verify |success, output| {
+        assert!(success);
+        assert!(output.contains("[dry-run] Would execute: "), "{output}");
+        assert!(output.contains("\"--exact\" \"--test\" \"dry_run_target\""), "{output}");
+        assert!(!output.contains("Spawned"), "{output}");
+    }
+}
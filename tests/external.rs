@@ -0,0 +1,27 @@
+subprocess_test::subprocess_test_external! {
+    // Test executable itself serves as external binary, since package has no binaries
+    #[test(binary = std::env::current_exe().unwrap())]
+    fn external_lists_tests() args { ["--list", "--exact", "external_lists_tests"] }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.contains("external_lists_tests: test"), "{output}");
+    }
+
+    #[test(binary = std::env::current_exe().unwrap())]
+    fn external_default_verify() args { ["--list"] }
+
+    #[test(binary = std::env::current_exe().unwrap(), working_dir = env!("CARGO_TARGET_TMPDIR"))]
+    fn external_rejects_args() args { ["--no-such-flag"] }
+    verify |result| {
+        assert!(!result.success);
+        assert!(result.output.contains("no-such-flag"), "{}", result.output);
+    }
+
+    #[test(binary = std::env::current_exe().unwrap())]
+    fn external_split_output() args { ["--no-such-flag"] }
+    verify |success, stdout, stderr| {
+        assert!(!success);
+        assert!(stdout.is_empty(), "{stdout}");
+        assert!(stderr.contains("no-such-flag"), "{stderr}");
+    }
+}
@@ -0,0 +1,30 @@
+use std::process::Command;
+
+// On Windows, command lookup doesn't consider `.bat` files without extension
+const FAKE_BINARY: &str = if cfg!(windows) {
+    "mycommand.bat"
+} else {
+    "mycommand"
+};
+
+subprocess_test::subprocess_test! {
+    #[test(
+        fake_binary = "mycommand",
+        fake_binary_output = "Fake 'quoted' $output\n",
+    )]
+    fn fake_binary_output() {
+        let output = Command::new(FAKE_BINARY).output().unwrap();
+        assert!(output.status.success());
+        print!("{}", String::from_utf8(output.stdout).unwrap());
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Fake 'quoted' $output\n");
+    }
+
+    #[test(fake_binary = "mycommand", fake_binary_exit_code = 3)]
+    fn fake_binary_exit_code() {
+        let status = Command::new(FAKE_BINARY).status().unwrap();
+        assert_eq!(status.code(), Some(3));
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(unix)]
+
+fn leak_files(dir: &std::path::Path, count: usize) {
+    for index in 0..count {
+        let file = std::fs::File::create(dir.join(format!("leaked_{index}"))).unwrap();
+        std::mem::forget(file);
+    }
+}
+
+subprocess_test::subprocess_test! {
+    #[test(max_open_fds = 10)]
+    fn fd_leak_detected() {
+        let dir = subprocess_test::subprocess_tempdir!();
+        leak_files(dir.path(), 20);
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.warnings.len(), 1, "{result:?}");
+        let warning = &result.warnings[0];
+        assert!(warning.contains("more than limit of 10"), "{warning}");
+        assert!(warning.contains("leaked_19"), "{warning}");
+    }
+
+    #[test(max_open_fds = 100)]
+    fn fd_count_within_limit() {
+        let dir = subprocess_test::subprocess_tempdir!();
+        leak_files(dir.path(), 1);
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert!(result.warnings.is_empty(), "{result:?}");
+    }
+
+    // Target test which is invoked by `fd_leak_fails_test` below, since it's supposed to fail
+    #[test(
+        max_open_fds = 10,
+        fail_on_fd_leak = true
+    )]
+    #[ignore = "fails on purpose, invoked by fd_leak_fails_test"]
+    fn fd_leak_target() {
+        let dir = subprocess_test::subprocess_tempdir!();
+        leak_files(dir.path(), 20);
+    }
+
+    #[test]
+    fn fd_leak_fails_test() {
+        fd_leak_target();
+    }
+    verify |success, output| {
+        assert!(!success, "{output}");
+        assert!(output.contains("more than limit of 10"), "{output}");
+        assert!(output.contains("leaked_19"), "{output}");
+    }
+}
@@ -0,0 +1,44 @@
+use std::fs;
+
+use subprocess_test::SubprocessFixture;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn fixture_dir() {
+        let dir = SubprocessFixture::path_from_env();
+        let input = fs::read_to_string(dir.join("input.txt")).unwrap();
+        fs::write(dir.join("output.txt"), input.to_uppercase()).unwrap();
+        print!("{input}");
+    }
+    fixture |fix| {
+        fs::write(fix.path().join("input.txt"), "fixture").unwrap();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "fixture");
+        assert_eq!(fs::read_to_string(fix.path().join("output.txt")).unwrap(), "FIXTURE");
+    }
+
+    // Each test gets its own directory, which is removed after test ends
+    #[test]
+    fn fixture_fresh() {
+        let dir = SubprocessFixture::path_from_env();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        print!("{}", dir.display());
+    }
+    fixture |fix| {}
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, fix.path().display().to_string());
+    }
+
+    #[test]
+    fn fixture_removed() {
+        let dir = {
+            let fixture = SubprocessFixture::new();
+            assert!(fixture.path().is_dir());
+            fixture.path().to_owned()
+        };
+        assert!(!dir.exists());
+    }
+}
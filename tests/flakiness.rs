@@ -0,0 +1,33 @@
+use std::env::var_os;
+use std::fs;
+
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `flakiness_detected` below. Uses counter file
+    // to fail on every second run
+    #[test]
+    fn flakiness_target() {
+        if let Some(path) = var_os("FLAKINESS_COUNTER_FILE") {
+            let counter: usize = fs::read_to_string(&path)
+                .map(|text| text.parse().unwrap())
+                .unwrap_or(0);
+            fs::write(&path, (counter + 1).to_string()).unwrap();
+            assert_eq!(counter % 2, 0, "Odd run fails");
+        }
+    }
+
+    #[test]
+    fn flakiness_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe {
+            std::env::set_var("SUBPROCESS_TEST_FLAKINESS_DETECT", "4");
+            std::env::set_var("FLAKINESS_COUNTER_FILE", dir.path().join("counter"));
+        }
+        flakiness_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Test is flaky: 2/4 runs succeeded"), "{output}");
+        assert!(output.contains("Odd run fails"), "{output}");
+    }
+}
@@ -0,0 +1,63 @@
+use std::fs;
+
+use subprocess_test::config::GlobalConfig;
+use tempfile::TempDir;
+
+const PACKAGE_MANIFEST: &str = r#"
+[package]
+name = "member"
+
+[package.metadata.subprocess-test]
+output_boundary = "PACKAGE"
+"#;
+
+const WORKSPACE_MANIFEST: &str = r#"
+[workspace]
+members = ["member"]
+
+[workspace.metadata.subprocess-test]
+env_var_name = "WORKSPACE_VAR"
+output_boundary = "WORKSPACE"
+"#;
+
+#[test]
+fn global_config_missing() {
+    let dir = TempDir::new().unwrap();
+    let config = GlobalConfig::load("[package]\nname = \"test\"\n", dir.path());
+    assert_eq!(config, GlobalConfig::default());
+}
+
+#[test]
+fn global_config_package_overrides_workspace() {
+    let dir = TempDir::new().unwrap();
+    let member_dir = dir.path().join("member");
+    fs::create_dir(&member_dir).unwrap();
+    fs::write(dir.path().join("Cargo.toml"), WORKSPACE_MANIFEST).unwrap();
+
+    let config = GlobalConfig::load(PACKAGE_MANIFEST, &member_dir);
+    assert_eq!(config.env_var_name.as_deref(), Some("WORKSPACE_VAR"));
+    assert_eq!(config.output_boundary.as_deref(), Some("PACKAGE"));
+}
+
+#[test]
+fn global_config_workspace_root_package() {
+    let dir = TempDir::new().unwrap();
+    let manifest = format!("{WORKSPACE_MANIFEST}{PACKAGE_MANIFEST}");
+
+    let config = GlobalConfig::load(&manifest, dir.path());
+    assert_eq!(config.env_var_name.as_deref(), Some("WORKSPACE_VAR"));
+    assert_eq!(config.output_boundary.as_deref(), Some("PACKAGE"));
+}
+
+subprocess_test::subprocess_test! {
+    // This crate doesn't specify defaults, so hardcoded ones are used
+    #[test]
+    fn global_config_hardcoded_defaults() {
+        assert!(std::env::var_os("__TEST_RUN_SUBPROCESS__").is_some());
+        print!("Defaults");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Defaults");
+    }
+}
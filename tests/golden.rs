@@ -0,0 +1,49 @@
+#![cfg(feature = "diff-output")]
+
+const UPDATED_GOLDEN: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/golden_update.txt");
+
+subprocess_test::subprocess_test! {
+    #[test(verify_golden = "tests/expected/golden_match.txt")]
+    fn golden_match() {
+        println!("First line");
+        println!("Second line");
+    }
+
+    #[test(verify_golden = "tests/expected/golden_mismatch.txt")]
+    #[ignore = "fails on purpose, invoked by golden_mismatch"]
+    fn golden_mismatch_target() {
+        println!("First line");
+        println!("Second line");
+    }
+
+    #[test]
+    fn golden_mismatch() {
+        golden_mismatch_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("differs from golden file"), "{output}");
+        assert!(output.contains("-   2      | Other line"), "{output}");
+        assert!(output.contains("+        2 | Second line"), "{output}");
+        assert!(output.contains("SUBPROCESS_TEST_UPDATE_GOLDEN=1"), "{output}");
+    }
+
+    #[test(verify_golden = UPDATED_GOLDEN)]
+    #[ignore = "writes golden file, invoked by golden_update"]
+    fn golden_update_target() {
+        println!("Updated");
+    }
+
+    #[test]
+    fn golden_update() {
+        golden_update_target();
+    }
+    setup |ctx| {
+        let _ = std::fs::remove_file(UPDATED_GOLDEN);
+        ctx.env("SUBPROCESS_TEST_UPDATE_GOLDEN", "1");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(std::fs::read_to_string(UPDATED_GOLDEN).unwrap(), "Updated\n");
+    }
+}
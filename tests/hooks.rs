@@ -0,0 +1,42 @@
+use std::fs;
+use std::path::PathBuf;
+
+use subprocess_test::SubprocessTestError;
+
+fn hook_dir() -> PathBuf {
+    std::env::temp_dir().join(concat!(env!("CARGO_CRATE_NAME"), "_pre_spawn_hook"))
+}
+
+fn create_hook_dir() -> Result<(), SubprocessTestError> {
+    fs::create_dir_all(hook_dir())?;
+    Ok(())
+}
+
+fn remove_hook_dir() -> Result<(), SubprocessTestError> {
+    fs::remove_dir_all(hook_dir())?;
+    Ok(())
+}
+
+fn failing_hook() -> Result<(), SubprocessTestError> {
+    Err(SubprocessTestError::new("No privileges"))
+}
+
+subprocess_test::subprocess_test! {
+    #[test(
+        pre_spawn_hook = create_hook_dir,
+        post_spawn_hook = remove_hook_dir,
+    )]
+    fn pre_spawn_hook_creates_dir() {
+        assert!(hook_dir().is_dir());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(!hook_dir().exists());
+    }
+
+    #[test(pre_spawn_hook = failing_hook)]
+    #[should_panic(expected = "Pre-spawn hook failed: No privileges")]
+    fn failing_pre_spawn_hook() {
+        unreachable!("Subprocess must not be spawned");
+    }
+}
@@ -0,0 +1,140 @@
+use std::env::var_os;
+
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `inherit_env_prefix` below with prefixed variables set
+    #[test(inherit_env_prefix = "MY_APP_")]
+    fn inherit_env_prefix_target() {
+        assert!(var_os("HOME").is_none());
+        assert_eq!(var_os("__TEST_RUN_SUBPROCESS__").unwrap(), "inherit_env_prefix_target");
+        print!("{:?} {:?}", var_os("MY_APP_COLOR"), var_os("MY_APP_TIMEOUT"));
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(
+            output,
+            format!("{:?} {:?}", var_os("MY_APP_COLOR"), var_os("MY_APP_TIMEOUT"))
+        );
+    }
+
+    #[test]
+    fn inherit_env_prefix() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe {
+            std::env::set_var("MY_APP_COLOR", "red");
+            std::env::set_var("MY_APP_TIMEOUT", "30");
+            std::env::set_var("HOME", "/home/nobody");
+        }
+        inherit_env_prefix_target();
+        print!("Checked");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Checked");
+    }
+
+    // Target test which is invoked by `cargo_features_forwarded` below with variables set
+    #[test(clear_env = true)]
+    fn cargo_features_forwarded_target() {
+        assert!(var_os("MY_APP_SECRET").is_none());
+        print!("{:?}", var_os("CARGO_FEATURE_FOO"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, format!("{:?}", var_os("CARGO_FEATURE_FOO")));
+    }
+
+    #[test]
+    fn cargo_features_forwarded() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe {
+            std::env::set_var("CARGO_FEATURE_FOO", "1");
+            std::env::set_var("MY_APP_SECRET", "hidden");
+        }
+        cargo_features_forwarded_target();
+        print!("Checked");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Checked");
+    }
+
+    // Target test which is invoked by `clear_env` below with variables set
+    #[test(
+        clear_env = true,
+        inherit_env = ["PATH", "MY_APP_KEPT"],
+    )]
+    fn clear_env_target() {
+        assert!(var_os("HOME").is_none());
+        assert!(var_os("MY_APP_SECRET").is_none());
+        // Marker is set anew for this test, rather than inherited from caller's subprocess
+        assert_eq!(var_os("__TEST_RUN_SUBPROCESS__").unwrap(), "clear_env_target");
+        print!("{:?} {:?}", var_os("PATH"), var_os("MY_APP_KEPT"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, format!("{:?} {:?}", var_os("PATH"), var_os("MY_APP_KEPT")));
+    }
+
+    #[test]
+    fn clear_env() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe {
+            std::env::set_var("MY_APP_KEPT", "kept");
+            std::env::set_var("MY_APP_SECRET", "secret");
+            std::env::set_var("HOME", "/home/nobody");
+        }
+        clear_env_target();
+        print!("Checked");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Checked");
+    }
+
+    #[test(env = [("MY_APP_SET", "set"), ("HOME", "/home/explicit")])]
+    fn explicit_env() {
+        assert!(var_os("PATH").is_some());
+        print!("{:?} {:?}", var_os("MY_APP_SET"), var_os("HOME"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, r#"Some("set") Some("/home/explicit")"#);
+    }
+
+    // Target test which is invoked by `explicit_env_composed` below with variables set
+    #[test(
+        clear_env = true,
+        forward_env = ["MY_APP_FORWARDED", "MY_APP_OVERRIDDEN"],
+        env = [("MY_APP_SET", "set"), ("MY_APP_OVERRIDDEN", "explicit")],
+    )]
+    #[ignore = "checks environment set by caller, invoked by explicit_env_composed"]
+    fn explicit_env_composed_target() {
+        assert!(var_os("MY_APP_CLEARED").is_none());
+        print!(
+            "{:?} {:?} {:?}",
+            var_os("MY_APP_FORWARDED"),
+            var_os("MY_APP_OVERRIDDEN"),
+            var_os("MY_APP_SET")
+        );
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, r#"Some("forwarded") Some("explicit") Some("set")"#);
+    }
+
+    #[test]
+    fn explicit_env_composed() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe {
+            std::env::set_var("MY_APP_FORWARDED", "forwarded");
+            std::env::set_var("MY_APP_OVERRIDDEN", "parent");
+            std::env::set_var("MY_APP_CLEARED", "cleared");
+        }
+        explicit_env_composed_target();
+        print!("Checked");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Checked");
+    }
+}
@@ -0,0 +1,20 @@
+use std::env::var_os;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn insta_snapshot() {
+        println!("Usage: tool [OPTIONS]");
+        println!("  --verbose  Print more");
+    }
+    verify_snapshot |output| {
+        insta::assert_snapshot!(output);
+    }
+
+    // Snapshot is asserted in subprocess, which has its environment cleared,
+    // so insta settings have to be forwarded
+    #[test(clear_env = true, forward_env_prefix = "INSTA_", forward_env = ["CI"])]
+    fn insta_in_subprocess() {
+        assert!(var_os("HOME").is_none());
+        insta::assert_snapshot!("Produced in subprocess");
+    }
+}
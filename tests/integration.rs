@@ -1,3 +1,42 @@
+use std::time::Duration;
+
+use subprocess_test::{SubprocessResult, SubprocessTestConfig};
+
+// Same builder which macro uses internally, called directly
+#[test]
+fn integration_builder() {
+    SubprocessTestConfig::new(concat!(module_path!(), "::integration_builder"))
+        .timeout(Duration::from_secs(30))
+        .output_trim("end")
+        .run(
+            || println!("Built"),
+            |result| {
+                assert!(result.success, "{}", result.output);
+                assert_eq!(result.output, "Built");
+            },
+        )
+}
+
+#[test]
+fn integration_builder_aliases() {
+    SubprocessTestConfig::new(concat!(module_path!(), "::integration_builder_aliases"))
+        .env_var("__INTEGRATION_ALIASES_SUBPROCESS__")
+        .boundary("<aliases>")
+        .clear_env(true)
+        .run(
+            || {
+                print!(
+                    "{}",
+                    std::env::var("__INTEGRATION_ALIASES_SUBPROCESS__").unwrap()
+                )
+            },
+            |result| {
+                assert!(result.success, "{}", result.output);
+                assert_eq!(result.output, "integration_builder_aliases");
+            },
+        )
+}
+
 subprocess_test::subprocess_test! {
     #[test]
     fn integration_simple_success() {
@@ -45,6 +84,33 @@ subprocess_test::subprocess_test! {
         assert_eq!(output, "One\nTwo\n");
     }
 
+    // Target test which is invoked by `boundary_in_output_detected` below,
+    // since it's supposed to fail
+    #[test(output_boundary = "!!!!!!!!!!!!!!!!")]
+    #[ignore = "fails on purpose, invoked by boundary_in_output_detected"]
+    fn boundary_in_output_target() {
+        println!("One");
+        println!("\n!!!!!!!!!!!!!!!!\n");
+        println!("Two");
+        println!("\n!!!!!!!!!!!!!!!!\n");
+        println!("Three");
+    }
+
+    #[test]
+    fn boundary_in_output_detected() {
+        boundary_in_output_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(
+            output.contains(
+                "Subprocess output contains the boundary string \"!!!!!!!!!!!!!!!!\" \
+                at unexpected positions; use output_boundary parameter to specify a unique delimiter"
+            ),
+            "{output}"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn integration_should_panic_test() {
@@ -53,6 +119,632 @@ subprocess_test::subprocess_test! {
     verify |success, _output| {
         assert!(!success, "Correct result should cause panic");
     }
+
+    #[test]
+    fn integration_duration() {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    verify |result| {
+        assert!(result.success);
+        subprocess_test::assert_subprocess_duration!(result.duration, 40, 200);
+        subprocess_test::assert_subprocess_slower_than!(result.duration, 40);
+        subprocess_test::assert_subprocess_faster_than!(result.duration, 200);
+    }
+
+    #[test(network_timeout_ms = 200)]
+    fn integration_network_timeout() {
+        let timeout = subprocess_test::subprocess_network_timeout_ms().unwrap();
+        assert_eq!(timeout, 200);
+        // Non-routable address, connection attempt either hangs or fails immediately
+        let address = "10.255.255.1:80".parse().unwrap();
+        let _ = std::net::TcpStream::connect_timeout(
+            &address,
+            std::time::Duration::from_millis(timeout),
+        );
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        subprocess_test::assert_subprocess_faster_than!(result.duration, 5000);
+    }
+
+    #[test(oom_score_adj = 500)]
+    fn integration_oom_score_adj() {
+        #[cfg(target_os = "linux")]
+        {
+            // Score is adjusted by parent right after spawn, so give it a moment
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            loop {
+                let score_adj = std::fs::read_to_string("/proc/self/oom_score_adj").unwrap();
+                if score_adj.trim() == "500" {
+                    break;
+                }
+                assert!(std::time::Instant::now() < deadline, "Got {score_adj}");
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+    }
+
+    #[test]
+    fn integration_output_float() {
+        // Leibniz series converges to pi slowly, so result has many noisy digits
+        let pi: f64 = (0..100_000)
+            .map(|k| if k % 2 == 0 { 4.0 } else { -4.0 } / (2 * k + 1) as f64)
+            .sum();
+        println!("result: {pi}");
+    }
+    verify |success, output| {
+        assert!(success);
+        subprocess_test::assert_subprocess_output_float!(output, "result: ", std::f64::consts::PI, 0.001);
+    }
+
+    #[test(output_trim = "end")]
+    fn integration_output_trim() {
+        println!("  hello");
+        println!();
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "  hello");
+    }
+
+    #[test(output_trim = "both")]
+    fn integration_output_trim_both() {
+        println!("  hello");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "hello");
+    }
+
+    #[test]
+    fn integration_lines_in_order() {
+        let workers: Vec<_> = (0..3)
+            .map(|index| std::thread::spawn(move || println!("worker {index} done")))
+            .collect();
+        println!("started");
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        println!("finished");
+    }
+    verify |success, output| {
+        assert!(success);
+        // Workers' lines are interleaved in unpredictable order
+        subprocess_test::assert_subprocess_lines_in_order!(output, ["worker 1 done", "finished"]);
+        subprocess_test::assert_subprocess_lines_in_order!(output, ["worker 2 done", "finished"]);
+    }
+
+    #[test(capture_sections = true)]
+    fn integration_sections() {
+        print!("Preamble");
+        subprocess_test::subprocess_mark!("section: database");
+        println!("Connected");
+        println!("Migrated");
+        subprocess_test::subprocess_mark!("section: server");
+        eprintln!("Listening");
+        subprocess_test::subprocess_mark!("section: empty");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert_eq!(result.output, "PreambleConnected\nMigrated\nListening\n");
+        assert_eq!(
+            result.sections,
+            [
+                ("section: database".to_owned(), "Connected\nMigrated\n".to_owned()),
+                ("section: server".to_owned(), "Listening\n".to_owned()),
+                ("section: empty".to_owned(), "".to_owned()),
+            ]
+        );
+    }
+
+    #[test(output_from_env = "TEST_OUTPUT_FILE")]
+    fn integration_output_from_env() {
+        let path = std::env::var("TEST_OUTPUT_FILE").unwrap();
+        std::fs::write(path, "Written to file\n").unwrap();
+        println!("Written to stdout");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Written to file\n");
+    }
+
+    #[test]
+    fn integration_destructuring_verify() {
+        subprocess_test::subprocess_mark!("only");
+        println!("Destructured");
+    }
+    verify |result| {
+        let SubprocessResult { success, output, sections, duration, subtests, .. } = &result;
+        assert!(success);
+        assert_eq!(output, "Destructured\n");
+        assert!(sections.is_empty());
+        assert!(*duration > Duration::ZERO);
+        assert!(subtests.is_empty());
+        subprocess_test::matches_result!(result, {
+            success: true,
+            output: "Destructured\n",
+            subtests: Vec::new(),
+        });
+    }
+
+    #[test(measure_io_bytes = true)]
+    fn integration_measure_io_bytes() {
+        let dir = subprocess_test::subprocess_tempdir!();
+        let path = dir.path().join("data");
+        std::fs::write(&path, vec![0u8; 100_000]).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), 100_000);
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        if cfg!(target_os = "linux") {
+            assert!(result.io_read_bytes.unwrap() >= 100_000, "{result:?}");
+            assert!(result.io_write_bytes.unwrap() >= 100_000, "{result:?}");
+            assert!(result.io_read_bytes.unwrap() < 1024 * 1024, "{result:?}");
+        } else {
+            assert_eq!(result.io_read_bytes, None);
+            assert_eq!(result.io_write_bytes, None);
+        }
+    }
+
+    #[test]
+    fn integration_exit_code() {
+        std::process::exit(3);
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert_eq!(result.exit_code, Some(3));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn integration_exit_code_on_abort() {
+        std::process::abort();
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert_eq!(result.exit_code, None);
+    }
+
+    // First of two parameters named `exit_code` receives exit code rather than success flag
+    #[test]
+    fn integration_verify_exit_code() {
+        print!("Exiting");
+        std::process::exit(2);
+    }
+    verify |exit_code, output| {
+        assert_eq!(exit_code, Some(2));
+        assert_eq!(output, "Exiting");
+    }
+
+    #[test(timeout_secs = 1)]
+    fn integration_verify_exit_code_on_timeout() {
+        std::thread::sleep(Duration::from_secs(30));
+    }
+    verify |exit_code, _output| {
+        assert_eq!(exit_code, None);
+    }
+
+    #[test(timeout_secs = 1)]
+    fn integration_timeout() {
+        println!("Started");
+        std::thread::sleep(Duration::from_secs(30));
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert!(result.timed_out);
+        assert!(result.duration < Duration::from_secs(10), "{result:?}");
+        assert_eq!(result.output, "Started\n");
+    }
+
+    #[test(timeout = Duration::from_millis(500), graceful_shutdown_ms = 5000)]
+    #[cfg(unix)]
+    fn integration_graceful_shutdown() {
+        use nix::sys::signal::{SigHandler, Signal, signal};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        static TERMINATED: AtomicBool = AtomicBool::new(false);
+        extern "C" fn on_sigterm(_: i32) {
+            TERMINATED.store(true, Ordering::SeqCst);
+        }
+        // SAFETY: handler only stores into atomic, which is async-signal-safe
+        unsafe { signal(Signal::SIGTERM, SigHandler::Handler(on_sigterm)) }.unwrap();
+
+        println!("Started");
+        while !TERMINATED.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        println!("Cleaned up");
+    }
+    verify |result| {
+        assert!(result.timed_out, "{result:?}");
+        assert!(result.duration < Duration::from_secs(5), "{result:?}");
+        assert_eq!(result.output, "Started\nCleaned up\n");
+    }
+
+    #[test(timeout_secs = 30)]
+    fn integration_within_timeout() {
+        println!("Finished");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert!(!result.timed_out);
+        assert_eq!(result.output, "Finished\n");
+    }
+
+    #[test]
+    fn integration_stdin_bytes() {
+        for line in std::io::stdin().lines() {
+            println!("Got {}", line.unwrap());
+        }
+    }
+    stdin { b"hello\nworld\n" }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Got hello\nGot world\n");
+    }
+
+    #[test]
+    fn integration_stdin_large_expression() {
+        let mut input = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut input).unwrap();
+        print!("{}", input.len());
+    }
+    stdin { "x".repeat(1024 * 1024) }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "1048576");
+    }
+
+    #[test]
+    fn integration_stdin_not_read() {
+        println!("Ignoring input");
+    }
+    stdin { vec![0u8; 1024 * 1024] }
+
+    #[test(phases = 3)]
+    fn integration_phases() {
+        let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("integration_phases.txt");
+        let phase = subprocess_test::subprocess_phase();
+        if phase == 0 {
+            std::fs::write(&path, "0").unwrap();
+        } else {
+            let state = std::fs::read_to_string(&path).unwrap();
+            std::fs::write(&path, format!("{state}{phase}")).unwrap();
+        }
+        print!("{}", std::fs::read_to_string(&path).unwrap());
+    }
+    verify |result| {
+        assert!(result.success, "{result:?}");
+        let outputs: Vec<_> = result.phases.iter().map(|phase| phase.output.as_str()).collect();
+        assert_eq!(outputs, ["0", "01", "012"]);
+        assert_eq!(result.output, "012");
+    }
+
+    #[test(phases = 2)]
+    fn integration_failed_phase() {
+        assert_eq!(subprocess_test::subprocess_phase(), 1, "Failed on purpose");
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert!(!result.phases[0].success);
+        assert!(result.phases[1].success);
+    }
+
+    #[test]
+    fn integration_verify_lines() {
+        let handles: Vec<_> = (0..4)
+            .map(|index| std::thread::spawn(move || println!("Worker {index} done")))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+    verify_lines |success, lines| {
+        assert!(success);
+        assert_eq!(lines.clone().count(), 4);
+        assert!(lines.clone().any(|line| line == "Worker 3 done"));
+        assert_eq!(lines.find("Worker 0"), Some("Worker 0 done"));
+        assert_eq!(lines.find("Worker 0"), None);
+    }
+
+    #[test(snapshot_env = ["INTEGRATION_SNAPSHOT"])]
+    fn integration_snapshot_env() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe { std::env::set_var("INTEGRATION_SNAPSHOT", "changed") };
+        print!("{}", std::env::var("INTEGRATION_SNAPSHOT").unwrap());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "changed");
+    }
+
+    #[test(success_codes = [0, 1])]
+    fn integration_success_codes() {
+        print!("Expected failure");
+        std::process::exit(1);
+    }
+    verify |result| {
+        assert!(result.success, "{result:?}");
+        assert_eq!(result.exit_code, Some(1));
+        assert_eq!(result.output, "Expected failure");
+    }
+
+    #[test(success_codes = [1])]
+    fn integration_zero_not_in_success_codes() {}
+    verify |result| {
+        assert!(!result.success, "{result:?}");
+        assert_eq!(result.exit_code, Some(0));
+    }
+
+    #[test(cargo_args = ["--test-threads=1"])]
+    fn integration_cargo_args() {
+        print!("Single thread");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Single thread");
+    }
+
+    #[test(cargo_args = ["--no-such-flag"])]
+    fn integration_cargo_args_rejected() {
+        print!("Never run");
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert!(result.output.contains("no-such-flag"), "{}", result.output);
+    }
+
+    #[test(retry = 2, retry_delay_ms = 10)]
+    fn integration_retry_succeeds() {
+        let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("integration_retry.txt");
+        // Fails on first attempt only, marker is removed so next test run starts over
+        if std::fs::remove_file(&path).is_err() {
+            std::fs::write(&path, "").unwrap();
+            panic!("Failed on purpose");
+        }
+        print!("Retried");
+    }
+    verify |result| {
+        assert!(result.success, "{result:?}");
+        assert_eq!(result.output, "Retried");
+    }
+
+    #[test(retry = 2)]
+    fn integration_retry_exhausted() {
+        println!("Attempt");
+        panic!("Failed on purpose");
+    }
+    verify |result| {
+        assert!(!result.success);
+        assert!(result.output.starts_with("Attempt\n"), "{}", result.output);
+    }
+
+    #[test]
+    fn integration_binary_output() {
+        use std::io::Write;
+        std::io::stdout().write_all(b"Binary \xFF\xFE output").unwrap();
+    }
+    verify_bytes |success, output_bytes| {
+        assert!(success);
+        assert_eq!(output_bytes, b"Binary \xFF\xFE output");
+    }
+
+    #[test]
+    fn integration_binary_output_lossy() {
+        use std::io::Write;
+        std::io::stdout().write_all(b"Lossy \xFF").unwrap();
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Lossy \u{FFFD}");
+    }
+
+    #[test(max_output_bytes = 4096)]
+    fn integration_output_truncated() {
+        for index in 0..100_000 {
+            println!("Line {index}");
+            eprintln!("Error {index}");
+        }
+    }
+    verify |result| {
+        assert!(result.success);
+        assert!(result.truncated);
+        assert!(result.output.len() <= 4096, "{}", result.output.len());
+        assert!(result.output.starts_with("Line 0\nError 0\n"), "{}", result.output);
+    }
+
+    // Output is much larger than pipe buffer, so it's read while subprocess runs
+    #[test]
+    fn integration_large_output() {
+        let line = "x".repeat(1023);
+        for _ in 0..4096 {
+            println!("{line}");
+        }
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output.len(), 4096 * 1024);
+    }
+
+    // Descendant process keeps output pipe open after subprocess exits,
+    // which shouldn't delay test until descendant exits
+    #[test]
+    #[cfg(unix)]
+    fn integration_lingering_descendant() {
+        // Left running on purpose, it's reaped by init after subprocess exits
+        #[allow(clippy::zombie_processes)]
+        let _descendant = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        print!("Spawned");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Spawned");
+    }
+
+    #[test(max_output_bytes = 4096)]
+    fn integration_output_within_limit() {
+        println!("Short");
+        eprintln!("Output");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert!(!result.truncated);
+        assert_eq!(result.output, "Short\nOutput\n");
+    }
+
+    #[test]
+    fn integration_split_streams() {
+        println!("Result line");
+        eprintln!("Diagnostic line");
+        panic!("Failure");
+    }
+    verify |success, stdout, stderr| {
+        assert!(!success);
+        assert_eq!(stdout, "Result line\n");
+        assert!(stderr.starts_with("Diagnostic line\n"), "{stderr}");
+        assert!(stderr.contains("Failure"), "{stderr}");
+    }
+
+    #[test(expect_lines = 3)]
+    fn integration_expect_lines() {
+        println!("One");
+        println!("Two");
+        print!("Three");
+    }
+
+    #[test(expect_lines = 0)]
+    fn integration_expect_no_lines() {}
+
+    #[test(expect_lines = 1)]
+    fn integration_expect_lines_verify() {
+        println!("Checked twice");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Checked twice\n");
+    }
+
+    // Target test which is invoked by `integration_expect_lines_mismatch` below,
+    // since it's supposed to fail
+    #[test(expect_lines = 0)]
+    #[ignore = "fails on purpose, invoked by integration_expect_lines_mismatch"]
+    fn expect_lines_target() {
+        println!("Unexpected");
+    }
+
+    #[test]
+    fn integration_expect_lines_mismatch() {
+        expect_lines_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(
+            output.contains("Test expect_lines_target subprocess printed unexpected number of lines"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn integration_pid() {
+        print!("{}", std::process::id());
+    }
+    verify_with_pid |success, output, pid| {
+        assert!(success);
+        let pid = pid.expect("Subprocess ID is known when subprocess is spawned");
+        assert_ne!(pid, std::process::id());
+        assert_eq!(output, pid.to_string());
+    }
+
+    #[test(expect_failure = true)]
+    fn integration_expect_failure() {
+        panic!("Fails on purpose");
+    }
+
+    #[test(expect_failure = 3)]
+    fn integration_expect_failure_code() {
+        std::process::exit(3);
+    }
+
+    #[test(expect_failure = true)]
+    fn integration_expect_failure_verify() {
+        print!("Failing");
+        std::process::exit(1);
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Failing");
+    }
+
+    // Target tests which are invoked by `integration_expect_failure_unmet` below,
+    // since they're supposed to fail
+    #[test(expect_failure = true)]
+    #[ignore = "fails on purpose, invoked by integration_expect_failure_unmet"]
+    fn expect_failure_succeeded_target() {}
+
+    #[test(expect_failure = 3)]
+    #[ignore = "fails on purpose, invoked by integration_expect_failure_unmet"]
+    fn expect_failure_wrong_code_target() {
+        std::process::exit(4);
+    }
+
+    #[test]
+    fn integration_expect_failure_unmet() {
+        let succeeded = std::panic::catch_unwind(expect_failure_succeeded_target).unwrap_err();
+        let wrong_code = std::panic::catch_unwind(expect_failure_wrong_code_target).unwrap_err();
+        println!("{}", succeeded.downcast_ref::<String>().unwrap());
+        println!("{}", wrong_code.downcast_ref::<String>().unwrap());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(
+            output.contains("Expected subprocess of test expect_failure_succeeded_target to fail but it succeeded"),
+            "{output}"
+        );
+        assert!(
+            output.contains("Expected subprocess of test expect_failure_wrong_code_target to exit with code 3, got exit code 4"),
+            "{output}"
+        );
+    }
+
+    #[test(normalize_line_endings = false)]
+    fn integration_char_diff() {
+        print!("Windows line\r\n");
+    }
+    verify |success, output| {
+        assert!(success);
+        let failure = std::panic::catch_unwind(|| {
+            subprocess_test::assert_subprocess_eq_char_diff!(output, "Windows line\n");
+        })
+        .unwrap_err();
+        let message = failure.downcast_ref::<String>().unwrap();
+        assert!(
+            message.ends_with("Line 1 differs: 'Windows line\\r\\n' vs 'Windows line\\n'"),
+            "{message}"
+        );
+    }
+
+    #[test(expect_failure = 101)]
+    fn integration_panic_message() {
+        panic!("Multiline\nmessage with 'quotes', and commas");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(
+            subprocess_test::extract_panic_message(&output),
+            Some("Multiline\nmessage with 'quotes', and commas")
+        );
+        subprocess_test::assert_panic_message!(output, "Multiline\nmessage with 'quotes', and commas");
+    }
+
+    #[test]
+    fn integration_no_panic_message() {
+        println!("Nothing panicked at all");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(subprocess_test::extract_panic_message(&output), None);
+    }
 }
 
 mod submodule_tests {
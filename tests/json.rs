@@ -0,0 +1,58 @@
+#![cfg(feature = "serde-json")]
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+enum Outcome {
+    Passed { checks: u32 },
+    Failed { reason: String },
+}
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn json_single_value() {
+        let result = Outcome::Passed { checks: 3 };
+        println!("{}", serde_json::to_string(&result).unwrap());
+    }
+    verify_json |success, outcome: Outcome| {
+        assert!(success);
+        match outcome {
+            Outcome::Passed { checks } => assert_eq!(checks, 3),
+            Outcome::Failed { reason } => panic!("Unexpected failure: {reason}"),
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        for checks in 1..=2 {
+            println!("{}", serde_json::to_string(&Outcome::Passed { checks }).unwrap());
+        }
+        println!("{}", serde_json::to_string(&Outcome::Failed { reason: "Timeout".into() }).unwrap());
+    }
+    verify_json |success, outcomes: Vec<Outcome>| {
+        assert!(success);
+        assert_eq!(outcomes, [
+            Outcome::Passed { checks: 1 },
+            Outcome::Passed { checks: 2 },
+            Outcome::Failed { reason: "Timeout".into() },
+        ]);
+    }
+
+    // Target test which is invoked by `json_parse_failure` below, since it's supposed to fail
+    #[test]
+    #[ignore = "fails on purpose, invoked by json_parse_failure"]
+    fn json_target() {
+        println!("Not JSON");
+    }
+    verify_json |_success, _outcome: Outcome| {}
+
+    #[test]
+    fn json_parse_failure() {
+        json_target();
+    }
+    verify |success, output| {
+        assert!(!success, "{output}");
+        assert!(output.contains("Not JSON"), "{output}");
+        assert!(output.contains("Failed to parse subprocess output as JSON"), "{output}");
+    }
+}
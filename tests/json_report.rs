@@ -0,0 +1,54 @@
+#![cfg(feature = "json-report")]
+
+use subprocess_test::TempDir;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    #[ignore = "reported to JSON file, invoked by json_report_records"]
+    fn json_report_success_target() {
+        print!("Hello, world!");
+    }
+    verify |success, _output| {
+        assert!(success);
+    }
+
+    #[test]
+    #[ignore = "fails on purpose, invoked by json_report_records"]
+    fn json_report_failure_target() {
+        panic!("Failed");
+    }
+    verify |success, _output| {
+        assert!(!success);
+    }
+
+    // Targets are run by subprocess of this test, which is given report path
+    #[test]
+    fn json_report_records() {
+        json_report_success_target();
+        json_report_failure_target();
+    }
+    setup |ctx| {
+        let dir = TempDir::new().unwrap();
+        ctx.env("SUBPROCESS_TEST_JSON_REPORT", dir.path().join("report.jsonl"))
+            .env("SUBPROCESS_TEST_JSON_REPORT_MAX_OUTPUT", "5")
+            .keep(dir);
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        let path = ctx.get::<TempDir>().path().join("report.jsonl");
+        let report = std::fs::read_to_string(path).unwrap();
+        let records: Vec<serde_json::Value> = report
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2, "{report}");
+
+        assert_eq!(records[0]["test"], "json_report::json_report_success_target");
+        assert_eq!(records[0]["success"], true);
+        assert_eq!(records[0]["output"], "Hello");
+        assert!(records[0]["duration_ms"].is_u64());
+
+        assert_eq!(records[1]["test"], "json_report::json_report_failure_target");
+        assert_eq!(records[1]["success"], false);
+    }
+}
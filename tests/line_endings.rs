@@ -0,0 +1,33 @@
+subprocess_test::subprocess_test! {
+    #[test(normalize_line_endings = true)]
+    fn line_endings_normalized() {
+        print!("First\r\nSecond\r\nBare\rCarriage\n");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "First\nSecond\nBare\rCarriage\n");
+    }
+
+    #[test(normalize_line_endings = false)]
+    fn line_endings_kept() {
+        print!("First\r\nSecond\r\n");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "First\r\nSecond\r\n");
+    }
+
+    // Normalization is enabled by default only on Windows
+    #[test]
+    fn line_endings_default() {
+        print!("Line\r\n");
+    }
+    verify |success, output| {
+        assert!(success);
+        if cfg!(windows) {
+            assert_eq!(output, "Line\n");
+        } else {
+            assert_eq!(output, "Line\r\n");
+        }
+    }
+}
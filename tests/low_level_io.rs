@@ -0,0 +1,43 @@
+#![cfg(all(unix, feature = "low-level-io"))]
+
+use std::fs::File;
+use std::io::Write;
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
+
+subprocess_test::subprocess_test! {
+    #[test(capture_fd = 3, capture_fd = 4)]
+    fn capture_write_in_order() {
+        println!("Before");
+        subprocess_test::subprocess_capture_write!(3, b"Fd 3\n");
+        println!("Between");
+        subprocess_test::subprocess_capture_write!(4, "Fd 4\n");
+        println!("After");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Before\nFd 3\nBetween\nFd 4\nAfter\n");
+    }
+
+    #[test(capture_fd = 5)]
+    fn direct_fd_write_captured() {
+        // SAFETY: descriptor is connected by parent and isn't closed on drop
+        let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(5) });
+        file.write_all(b"Direct write\n").unwrap();
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Direct write\n");
+    }
+
+    #[test(capture_fd = 3)]
+    fn uncaptured_fd_rejected() {
+        subprocess_test::subprocess_capture_write!(4, b"Lost");
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains(
+            "File descriptor 4 is not captured, add `capture_fd = 4` to test parameters"
+        ));
+    }
+}
@@ -0,0 +1,31 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+subprocess_test::subprocess_test! {
+    #[test(max_duration_secs = 30.0)]
+    fn max_duration_not_exceeded() {
+        println!("Fast");
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert!(result.duration < Duration::from_secs(30), "{result:?}");
+    }
+
+    #[test(max_duration_secs = 0.1)]
+    #[ignore = "fails on purpose, invoked by max_duration_exceeded"]
+    fn max_duration_exceeded_target() {
+        sleep(Duration::from_millis(300));
+        println!("Slow");
+    }
+
+    // Subprocess isn't killed, so its whole output is reported
+    #[test]
+    fn max_duration_exceeded() {
+        max_duration_exceeded_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("which exceeds maximum duration 100ms"), "{output}");
+        assert!(output.contains("Slow"), "{output}");
+    }
+}
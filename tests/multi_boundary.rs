@@ -0,0 +1,67 @@
+use subprocess_test::subprocess_checkpoint;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn checkpoint_sections() {
+        println!("First");
+        subprocess_checkpoint!();
+        println!("Second");
+        subprocess_checkpoint!();
+        print!("Third");
+    }
+    verify_sections |success, sections| {
+        assert!(success);
+        assert_eq!(sections, ["First\n", "Second\n", "Third"]);
+    }
+
+    // Adjacent checkpoints, or checkpoint right before test end, produce empty parts
+    #[test(output_boundary = "<><><>")]
+    fn checkpoint_empty_sections() {
+        subprocess_checkpoint!();
+        subprocess_checkpoint!();
+        print!("Middle");
+        subprocess_checkpoint!();
+    }
+    verify_sections |success, sections| {
+        assert!(success);
+        assert_eq!(sections, ["", "", "Middle", ""]);
+    }
+
+    #[test(multi_boundary = true, output_trim = "end")]
+    fn checkpoint_joined_output() {
+        println!("Before");
+        subprocess_checkpoint!();
+        println!("After");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert_eq!(result.output, "Before\nAfter");
+        assert_eq!(result.output_bytes, b"Before\nAfter\n");
+        assert_eq!(result.boundary_sections, ["Before\n", "After\n"]);
+    }
+
+    #[test]
+    fn checkpoint_ignored_by_default() {
+        println!("Before");
+        subprocess_checkpoint!();
+        println!("After");
+    }
+    verify |result| {
+        assert!(result.success);
+        assert_eq!(result.output, "Before\nAfter\n");
+        assert!(result.boundary_sections.is_empty());
+    }
+
+    #[test]
+    fn checkpoint_before_panic() {
+        println!("Before");
+        subprocess_checkpoint!();
+        panic!("Fails on purpose");
+    }
+    verify_sections |success, sections| {
+        assert!(!success);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0], "Before\n");
+        assert!(sections[1].contains("Fails on purpose"), "{sections:?}");
+    }
+}
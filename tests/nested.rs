@@ -0,0 +1,39 @@
+use std::process;
+
+subprocess_test::subprocess_test! {
+    // Innermost level, invoked by nested_middle
+    #[test]
+    #[ignore = "invoked by nested_middle"]
+    fn nested_inner() {
+        println!("Inner: {}", process::id());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_ne!(output, format!("Inner: {}\n", process::id()));
+        assert!(output.starts_with("Inner: "), "{output}");
+        println!("Inner verified");
+    }
+
+    // Middle level, invoked by nested_outer
+    #[test]
+    #[ignore = "invoked by nested_outer"]
+    fn nested_middle() {
+        println!("Middle");
+        nested_inner();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Middle\nInner verified\n");
+        println!("Middle verified");
+    }
+
+    // Each level spawns its own subprocess, even though marker variable is inherited
+    #[test]
+    fn nested_outer() {
+        nested_middle();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Middle verified\n");
+    }
+}
@@ -0,0 +1,22 @@
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `nextest_invocation` below as if it was run by nextest
+    #[test]
+    #[ignore = "invoked by nextest_invocation"]
+    fn nextest_target() {
+        print!("Run by nextest: {}", std::env::var_os("NEXTEST_VERSION").is_some());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Run by nextest: true");
+    }
+
+    #[test]
+    fn nextest_invocation() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe { std::env::set_var("NEXTEST_VERSION", "0.9.0") };
+        nextest_target();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+    }
+}
@@ -0,0 +1,39 @@
+subprocess_test::subprocess_test! {
+    #[test]
+    fn normalize_chained() {
+        println!("Listening on port 40123");
+        eprintln!("Started at 12:34:56");
+    }
+    normalize |output| {
+        output.replace("40123", "<PORT>")
+    }
+    // Applied after first block, so it sees its result
+    normalize |output| {
+        output
+            .lines()
+            .map(|line| match line.split_once(" at ") {
+                Some((head, _)) => format!("{head} at <TIME>\n"),
+                None => format!("{line}\n"),
+            })
+            .collect::<String>()
+            .replace("<PORT>", "<port>")
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Listening on port <port>\nStarted at <TIME>\n");
+    }
+
+    #[test]
+    fn normalize_split_output() {
+        print!("Out 1");
+        eprint!("Err 2");
+    }
+    normalize |output| {
+        output.replace(char::is_numeric, "#")
+    }
+    verify |success, stdout, stderr| {
+        assert!(success);
+        assert_eq!(stdout, "Out #");
+        assert_eq!(stderr, "Err #");
+    }
+}
@@ -0,0 +1,31 @@
+subprocess_test::subprocess_test! {
+    // This crate is its own workspace, so paths become relative to its directory
+    #[test(normalize_paths = true)]
+    fn normalize_paths_relative() {
+        println!("At {}", concat!(env!("CARGO_MANIFEST_DIR"), "/src/lib.rs:42"));
+        eprintln!("Reading {}", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data.txt"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "At src/lib.rs:42\nReading tests/data.txt\n");
+    }
+
+    // Only paths inside workspace directory are changed
+    #[test(normalize_paths = true)]
+    fn normalize_paths_sibling_untouched() {
+        println!("{}", concat!(env!("CARGO_MANIFEST_DIR"), "-sibling/file.rs"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, concat!(env!("CARGO_MANIFEST_DIR"), "-sibling/file.rs\n"));
+    }
+
+    #[test]
+    fn normalize_paths_disabled() {
+        println!("{}", concat!(env!("CARGO_MANIFEST_DIR"), "/src/lib.rs"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, concat!(env!("CARGO_MANIFEST_DIR"), "/src/lib.rs\n"));
+    }
+}
@@ -0,0 +1,25 @@
+#[track_caller]
+fn check_positive(value: i32) {
+    assert!(value > 0, "Value {value} isn't positive");
+}
+
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `panic_location_reported` below, since it's supposed to fail
+    #[test]
+    #[ignore = "fails on purpose, invoked by panic_location_reported"]
+    fn panic_location_target() {
+        check_positive(-1);
+    }
+
+    #[test]
+    fn panic_location_reported() {
+        panic_location_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        // `#[track_caller]` helper reports line where it's called in test body
+        let expected = "Test panic_location_target subprocess failed, \
+            panicked at tests/panic_location.rs:11:9:\nValue -1 isn't positive";
+        assert!(output.contains(expected), "{output}");
+    }
+}
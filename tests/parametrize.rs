@@ -0,0 +1,41 @@
+#![cfg(feature = "context")]
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    path: String,
+    status: u16,
+}
+
+fn handle(request: &Request) -> String {
+    format!("{} -> {}", request.path, request.status)
+}
+
+subprocess_test::subprocess_test_parametrize! {
+    #[test]
+    fn handles_request(request: Request) {
+        print!("{}", handle(&request));
+    }
+    verify |request, success, output| {
+        assert!(success);
+        assert_eq!(output, format!("{} -> {}", request.path, request.status));
+    }
+    cases {
+        ok: Request { path: "/".to_owned(), status: 200 },
+        not_found: Request { path: "/missing".to_owned(), status: 404 },
+    }
+}
+
+subprocess_test::subprocess_test_parametrize! {
+    /// Default `verify` block just checks that each case succeeded
+    #[test(env_var_name = "__PARAMETRIZE_SUBPROCESS__")]
+    fn positive(value: i64) {
+        assert!(value > 0);
+        assert!(std::env::var_os("__PARAMETRIZE_SUBPROCESS__").is_some());
+    }
+    cases {
+        one: 1,
+        large: i64::MAX,
+    }
+}
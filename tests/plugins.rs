@@ -0,0 +1,51 @@
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use subprocess_test::{SubprocessResult, SubprocessTestConfig, SubprocessTestPlugin};
+
+static CONFIGS: AtomicUsize = AtomicUsize::new(0);
+static SPAWNS: AtomicUsize = AtomicUsize::new(0);
+static WAITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts subprocess invocations, and passes count to subprocess
+struct CountingPlugin;
+
+impl SubprocessTestPlugin for CountingPlugin {
+    fn pre_spawn(&self, config: SubprocessTestConfig) -> SubprocessTestConfig {
+        CONFIGS.fetch_add(1, Ordering::Relaxed);
+        config.env([("COUNTING_PLUGIN_CONFIGURED", "1")])
+    }
+
+    fn pre_spawn_command(&self, command: &mut Command) {
+        let count = SPAWNS.fetch_add(1, Ordering::Relaxed) + 1;
+        command.env("COUNTING_PLUGIN_SPAWNS", count.to_string());
+    }
+
+    fn post_spawn(&self, child: &Child) {
+        assert_ne!(child.id(), std::process::id());
+    }
+
+    fn post_wait(&self, result: &mut SubprocessResult) {
+        WAITS.fetch_add(1, Ordering::Relaxed);
+        result.output.push_str("Seen by plugin\n");
+    }
+
+    fn post_verify(&self) {
+        eprintln!("Verified after {} spawns", SPAWNS.load(Ordering::Relaxed));
+    }
+}
+
+subprocess_test::subprocess_test! {
+    #[test(plugin = CountingPlugin, plugin = subprocess_test::TimingPlugin)]
+    fn plugin_counts_invocations() {
+        println!("Spawn {}", std::env::var("COUNTING_PLUGIN_SPAWNS").unwrap());
+        println!("Configured {}", std::env::var("COUNTING_PLUGIN_CONFIGURED").unwrap());
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Spawn 1\nConfigured 1\nSeen by plugin\n");
+        assert_eq!(CONFIGS.load(Ordering::Relaxed), 1);
+        assert_eq!(SPAWNS.load(Ordering::Relaxed), 1);
+        assert_eq!(WAITS.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,28 @@
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `print_to_parent` below, so its parent's stderr
+    // becomes part of `print_to_parent`'s output
+    #[test]
+    fn print_to_parent_target() {
+        for step in 1..=3 {
+            subprocess_test::subprocess_print_to_parent!(format!("step {step} complete"));
+        }
+        println!("Finished");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Finished\n");
+        eprintln!("Verifying");
+    }
+
+    #[test]
+    fn print_to_parent() {
+        print_to_parent_target();
+    }
+    verify |success, output| {
+        assert!(success);
+        subprocess_test::assert_subprocess_lines_in_order!(
+            output,
+            ["step 1 complete", "step 2 complete", "step 3 complete", "Verifying"]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+#![cfg(all(unix, feature = "rlimit"))]
+
+subprocess_test::subprocess_test! {
+    #[test(rlimit_as = 256_000_000)]
+    fn allocation_beyond_limit_fails() {
+        let buffer = vec![1u8; 1_000_000_000];
+        println!("Allocated {} bytes", buffer.len());
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("memory allocation of 1000000000 bytes failed"), "{output}");
+    }
+
+    #[test(memory_limit_mb = 256)]
+    fn allocation_beyond_limit_in_megabytes_fails() {
+        let buffer = vec![1u8; 1_000_000_000];
+        println!("Allocated {} bytes", buffer.len());
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("memory allocation of 1000000000 bytes failed"), "{output}");
+    }
+
+    #[test(rlimit_as = 256_000_000)]
+    fn allocation_within_limit_succeeds() {
+        let buffer = vec![1u8; 1_000_000];
+        println!("Allocated {} bytes", buffer.len());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Allocated 1000000 bytes\n");
+    }
+
+    #[test(with_plugin = subprocess_test::ResourceLimitPlugin::memory_mb(256))]
+    fn allocation_beyond_plugin_limit_fails() {
+        let buffer = vec![1u8; 1_000_000_000];
+        println!("Allocated {} bytes", buffer.len());
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("memory allocation of 1000000000 bytes failed"), "{output}");
+    }
+}
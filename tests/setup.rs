@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use subprocess_test::TempDir;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn setup_fixture() {
+        let dir = std::env::var("SETUP_FIXTURE_DIR").unwrap();
+        let input = std::fs::read_to_string(Path::new(&dir).join("input.txt")).unwrap();
+        std::fs::write(Path::new(&dir).join("output.txt"), input.to_uppercase()).unwrap();
+        print!("{input}");
+    }
+    setup |ctx| {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("input.txt"), "fixture").unwrap();
+        ctx.env("SETUP_FIXTURE_DIR", dir.path()).keep(dir);
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "fixture");
+        let dir = ctx.get::<TempDir>();
+        assert_eq!(std::fs::read_to_string(dir.path().join("output.txt")).unwrap(), "FIXTURE");
+    }
+
+    #[test(working_dir = env!("CARGO_MANIFEST_DIR"), clear_env = true)]
+    fn setup_overrides() {
+        assert_eq!(std::env::var("SETUP_OVERRIDE").unwrap(), "value");
+        print!("{}", std::env::current_dir().unwrap().display());
+    }
+    setup |ctx| {
+        ctx.env("SETUP_OVERRIDE", "value")
+            .working_dir(env!("CARGO_TARGET_TMPDIR"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(Path::new(&output), Path::new(env!("CARGO_TARGET_TMPDIR")).canonicalize().unwrap());
+    }
+
+    // Target test which is invoked by `setup_panic_fails_test` below, since it's supposed to fail
+    #[test]
+    #[ignore = "fails on purpose, invoked by setup_panic_fails_test"]
+    fn setup_target() {
+        println!("Subprocess spawned");
+    }
+    setup |_ctx| {
+        panic!("Setup failed on purpose");
+    }
+
+    #[test]
+    fn setup_panic_fails_test() {
+        setup_target();
+    }
+    verify |success, output| {
+        assert!(!success, "{output}");
+        assert!(output.contains("Setup failed on purpose"), "{output}");
+        assert!(!output.contains("Subprocess spawned"), "{output}");
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+const NEXTEST_SKIP_FILE: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/nextest_skip.txt");
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn skipped() {
+        panic!("Skipped test body must not run");
+    }
+    skip_if { true }
+    verify |_success, _output| {
+        panic!("Skipped test verify block must not run");
+    }
+
+    #[test]
+    fn not_skipped() {
+        println!("Ran");
+    }
+    skip_if { !Path::new(env!("CARGO_MANIFEST_DIR")).exists() }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "Ran\n");
+    }
+
+    #[test]
+    #[ignore = "reports skip in nextest format only under nextest, invoked by skipped_nextest"]
+    fn skipped_nextest_target() {
+        panic!("Skipped test body must not run");
+    }
+    skip_if { true }
+
+    #[test]
+    fn skipped_nextest() {
+        skipped_nextest_target();
+    }
+    setup |ctx| {
+        let _ = std::fs::remove_file(NEXTEST_SKIP_FILE);
+        ctx.env("NEXTEST_VERSION", "0.9.0");
+        ctx.env("NEXTEST_BINARY_ID", "subprocess-test::skip");
+        ctx.env("NEXTEST_SKIP", NEXTEST_SKIP_FILE);
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(
+            output.contains("SKIP [         ] subprocess-test::skip skip::skipped_nextest_target\n"),
+            "{output}"
+        );
+        assert!(!output.contains("... SKIPPED"), "{output}");
+        assert_eq!(
+            std::fs::read_to_string(NEXTEST_SKIP_FILE).unwrap(),
+            "skip::skipped_nextest_target\n"
+        );
+    }
+
+    #[test]
+    fn skipped_with_result() -> Result<(), String> {
+        Err("Skipped test body must not run".to_owned())
+    }
+    skip_if { true }
+    verify |_success, output| {
+        Err(output)
+    }
+}
@@ -0,0 +1,112 @@
+use std::env::var;
+use std::path::Path;
+
+const SNAPSHOT_TARGET: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/subprocess_snapshots/snapshot.snapshot_target.txt"
+);
+
+// Tests which update snapshots use it as crate directory, so committed snapshots aren't touched
+const UPDATED_SNAPSHOT_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/snapshot_update");
+
+subprocess_test::subprocess_test! {
+    // Target test which is invoked by `snapshot_mismatch` below. Its snapshot is committed,
+    // so it passes when run on its own, with output unchanged
+    #[test(snapshot = true)]
+    fn snapshot_target() {
+        print!("{}", var("SNAPSHOT_TARGET_OUTPUT").unwrap_or("Snapshot\n".to_owned()));
+    }
+
+    #[test]
+    fn snapshot_mismatch() {
+        snapshot_target();
+    }
+    setup |ctx| {
+        ctx.env("SNAPSHOT_TARGET_OUTPUT", "Modified\n");
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Subprocess output doesn't match snapshot "), "{output}");
+        assert!(
+            output.contains("Expected:\nSnapshot\n\nActual:\nModified\n\n"),
+            "{output}"
+        );
+    }
+
+    // Committed snapshot stays intact, since dry run has no output to write
+    #[test]
+    fn snapshot_dry_run() {
+        snapshot_target();
+    }
+    setup |ctx| {
+        ctx.env("SUBPROCESS_TEST_DRY_RUN", "1");
+        ctx.env("SUBPROCESS_TEST_UPDATE", "1");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(std::fs::read_to_string(SNAPSHOT_TARGET).unwrap(), "Snapshot\n");
+    }
+
+    #[test(snapshot = true, manifest_dir = UPDATED_SNAPSHOT_DIR)]
+    #[ignore = "requires SUBPROCESS_TEST_UPDATE, invoked by snapshot_update"]
+    fn snapshot_update_target() {
+        println!("Updated");
+    }
+    verify |_success, _output| {
+        panic!("Verify block must not run when snapshot is updated");
+    }
+
+    #[test]
+    fn snapshot_update() {
+        snapshot_update_target();
+    }
+    setup |ctx| {
+        let _ = std::fs::remove_dir_all(UPDATED_SNAPSHOT_DIR);
+        ctx.env("SUBPROCESS_TEST_UPDATE", "1");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        let snapshot = format!(
+            "{UPDATED_SNAPSHOT_DIR}/tests/subprocess_snapshots/snapshot.snapshot_update_target.txt"
+        );
+        assert_eq!(std::fs::read_to_string(snapshot).unwrap(), "Updated\n");
+    }
+
+    #[test(manifest_dir = UPDATED_SNAPSHOT_DIR)]
+    #[ignore = "requires SUBPROCESS_TEST_UPDATE, invoked by snapshot_not_requested"]
+    fn snapshot_not_requested_target() {
+        print!("Not snapshot");
+    }
+
+    // Tests without `snapshot` parameter neither write nor compare snapshots
+    #[test]
+    fn snapshot_not_requested() {
+        snapshot_not_requested_target();
+    }
+    setup |ctx| {
+        ctx.env("SUBPROCESS_TEST_UPDATE", "1");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        let snapshot = format!(
+            "{UPDATED_SNAPSHOT_DIR}/tests/subprocess_snapshots/snapshot.snapshot_not_requested_target.txt"
+        );
+        assert!(!Path::new(&snapshot).exists());
+    }
+
+    #[test(snapshot = true, manifest_dir = UPDATED_SNAPSHOT_DIR)]
+    #[ignore = "has no snapshot, invoked by snapshot_missing"]
+    fn snapshot_missing_target() {
+        print!("Missing");
+    }
+
+    #[test]
+    fn snapshot_missing() {
+        snapshot_missing_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Subprocess output can't be compared with snapshot "), "{output}");
+        assert!(output.contains("Run with SUBPROCESS_TEST_UPDATE=1 to create it"), "{output}");
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Called in parent for each line while subprocess runs.
+/// Connects to server started by subprocess, which waits for this connection before exiting
+fn connect_when_listening(line: &str) {
+    if let Some(port) = line.strip_prefix("Listening on port ") {
+        let mut stream = TcpStream::connect(("127.0.0.1", port.parse::<u16>().unwrap())).unwrap();
+        stream.write_all(b"Hello from parent").unwrap();
+    }
+}
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn collect_line(line: &str) {
+    LINES.lock().unwrap().push(line.to_owned());
+}
+
+subprocess_test::subprocess_test! {
+    #[test(on_output_line = connect_when_listening)]
+    fn line_callback_before_exit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        println!("Listening on port {}", listener.local_addr().unwrap().port());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+                Err(err) => panic!("Parent didn't connect: {err}"),
+            }
+        };
+        stream.set_nonblocking(false).unwrap();
+        let mut message = String::new();
+        stream.read_to_string(&mut message).unwrap();
+        println!("Received: {message}");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.ends_with("Received: Hello from parent\n"), "{output}");
+    }
+
+    #[test(on_output_line = collect_line, capture_sections = true)]
+    fn line_callback_skips_marks() {
+        println!("First");
+        println!();
+        subprocess_test::subprocess_mark!("section");
+        eprintln!("Second");
+        print!("Unterminated");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "First\n\nSecond\nUnterminated");
+        assert_eq!(*LINES.lock().unwrap(), ["First", "", "Second", "Unterminated"]);
+    }
+}
+
+// Legacy function, which accepts mutable callback
+#[test]
+#[allow(deprecated)]
+fn streaming_function() {
+    let lines = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let collected = lines.clone();
+    subprocess_test::run_subprocess_test_streaming(
+        concat!(module_path!(), "::streaming_function"),
+        None,
+        None,
+        None,
+        &[],
+        move |line| collected.borrow_mut().push(line.to_owned()),
+        || {
+            println!("One");
+            println!("Two");
+        },
+        |exit_code, output| {
+            assert_eq!(exit_code, Some(0), "{output}");
+            assert_eq!(output, "One\nTwo\n");
+        },
+    );
+    // Subprocess mode doesn't receive lines
+    if std::env::var_os("__TEST_RUN_SUBPROCESS__").is_none() {
+        assert_eq!(*lines.borrow(), ["One", "Two"]);
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "strip-ansi")]
+
+subprocess_test::subprocess_test! {
+    #[test(strip_ansi = true)]
+    fn strip_ansi_colors() {
+        println!("\x1b[1;31merror\x1b[0m: \x1b[32mok\x1b[m");
+        println!("\x1b]8;;https://example.com\x07link\x1b]8;;\x1b\\ done\x1b[2K");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "error: ok\nlink done\n");
+    }
+
+    #[test(strip_ansi = true, split_output = true)]
+    fn strip_ansi_split_output() {
+        println!("\x1b[33mout\x1b[0m");
+        eprintln!("\x1b[31merr\x1b[0m");
+    }
+    verify |success, stdout, stderr| {
+        assert!(success);
+        assert_eq!(stdout, "out\n");
+        assert_eq!(stderr, "err\n");
+    }
+
+    #[test]
+    fn ansi_kept_by_default() {
+        print!("\x1b[1mbold\x1b[0m");
+    }
+    verify |success, output| {
+        assert!(success);
+        assert_eq!(output, "\x1b[1mbold\x1b[0m");
+    }
+}
@@ -0,0 +1,26 @@
+use subprocess_test::subtest;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn subtests_pass() {
+        subtest!("first", || println!("One"));
+        subtest!("second", || println!("Two"));
+    }
+    verify |result| {
+        assert!(result.success);
+        assert_eq!(result.output, "One\nTwo\n");
+        let names: Vec<_> = result.subtests.iter().map(|subtest| &subtest.name).collect();
+        assert_eq!(names, ["first", "second"]);
+        assert!(result.subtests.iter().all(|subtest| subtest.failure.is_none()));
+    }
+
+    #[test]
+    // Subtest failures are reported by parent, after subprocess exits.
+    // Total count shows that subtest after failed one was run too
+    #[should_panic(expected = "1 of 3 subtests failed:\nSubtest 'middle' failed: Boom")]
+    fn subtest_failure_reported() {
+        subtest!("first", || assert_eq!(1 + 1, 2));
+        subtest!("middle", || panic!("Boom"));
+        subtest!("last", || assert_eq!(2 + 2, 4));
+    }
+}
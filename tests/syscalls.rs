@@ -0,0 +1,69 @@
+#[cfg(target_os = "linux")]
+const FAKE_STRACE_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/fake_strace");
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn trace_disabled() {
+        println!("Body");
+    }
+    verify_syscalls |success, output, trace| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Body\n");
+        assert_eq!(trace, None);
+    }
+
+    #[test(trace_syscalls = true)]
+    #[ignore = "requires syscall tracer, invoked by tracer_missing"]
+    fn tracer_missing_target() {
+        println!("Body");
+    }
+
+    #[test]
+    fn tracer_missing() {
+        tracer_missing_target();
+    }
+    setup |ctx| {
+        ctx.env("PATH", "");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.contains("`trace_syscalls` "), "{output}");
+        assert!(output.contains("tracer_missing_target ... SKIPPED"), "{output}");
+        assert!(!output.contains("Body"), "{output}");
+    }
+
+    #[test(trace_syscalls = true)]
+    #[ignore = "requires fake strace, invoked by trace_captured"]
+    fn trace_captured_target() {
+        println!("Body");
+    }
+    verify_syscalls |success, output, trace| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Body\n");
+        assert_eq!(trace.as_deref(), Some("openat(AT_FDCWD, \"/etc/hosts\", O_RDONLY) = 3\n"));
+    }
+
+    // Fake writes trace into file passed as `-o` option, and runs test
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn trace_captured() {
+        trace_captured_target();
+    }
+    setup |ctx| {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = format!("{FAKE_STRACE_DIR}/strace");
+        std::fs::create_dir_all(FAKE_STRACE_DIR).unwrap();
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nshift 2\necho 'openat(AT_FDCWD, \"/etc/hosts\", O_RDONLY) = 3' > \"$1\"\nshift\nexec \"$@\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        ctx.env("PATH", FAKE_STRACE_DIR);
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(!output.contains("SKIPPED"), "{output}");
+    }
+}
@@ -0,0 +1,71 @@
+subprocess_test::subprocess_test! {
+    // Target tests which are invoked by tests below, since their results are visible
+    // only from outside
+    #[test]
+    #[ignore = "invoked by teardown_after_success"]
+    fn teardown_success_target() {}
+    verify |success, _output| {
+        assert!(success);
+        println!("Verify ran");
+    }
+    teardown {
+        println!("Teardown ran");
+    }
+
+    #[test]
+    #[ignore = "fails on purpose, invoked by teardown_after_verify_panic"]
+    fn teardown_panic_target() {}
+    verify |_success, _output| {
+        panic!("Verify failed on purpose");
+    }
+    teardown {
+        println!("Teardown ran");
+    }
+
+    #[test]
+    #[ignore = "fails on purpose, invoked by teardown_double_panic"]
+    fn teardown_double_panic_target() {}
+    verify |_success, _output| {
+        panic!("Verify failed on purpose");
+    }
+    teardown {
+        panic!("Teardown failed on purpose");
+    }
+
+    #[test]
+    fn teardown_after_success() {
+        teardown_success_target();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Verify ran\nTeardown ran\n");
+    }
+
+    #[test]
+    fn teardown_after_verify_panic() {
+        teardown_panic_target();
+    }
+    verify |success, output| {
+        assert!(!success, "{output}");
+        assert!(output.contains("Verify failed on purpose"), "{output}");
+        assert!(output.contains("Teardown ran"), "{output}");
+    }
+
+    #[test]
+    fn teardown_double_panic() {
+        teardown_double_panic_target();
+    }
+    verify |success, output| {
+        assert!(!success, "{output}");
+        assert!(output.contains("Teardown block panicked too"), "{output}");
+    }
+
+    #[test]
+    fn teardown_double_panic_propagates_verify_panic() {
+        let payload = std::panic::catch_unwind(|| {
+            subprocess_test::run_with_teardown(|| panic!("Verify"), || panic!("Teardown"))
+        })
+        .unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"Verify"));
+    }
+}
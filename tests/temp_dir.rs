@@ -0,0 +1,36 @@
+use subprocess_test::{subprocess_phase, subprocess_temp_dir};
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn temp_dir_shared_with_verify() {
+        std::fs::write(subprocess_temp_dir().join("report.txt"), "Report").unwrap();
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        let report = std::fs::read_to_string(result.temp_dir.join("report.txt")).unwrap();
+        assert_eq!(report, "Report");
+    }
+
+    #[test(phases = 2)]
+    fn temp_dir_shared_by_phases() {
+        let path = subprocess_temp_dir().join("state.txt");
+        match subprocess_phase() {
+            0 => std::fs::write(path, "State").unwrap(),
+            _ => assert_eq!(std::fs::read_to_string(path).unwrap(), "State"),
+        }
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.phases.len(), 2);
+    }
+
+    #[test]
+    fn temp_dir_env_var() {
+        print!("{}", std::env::var("SUBPROCESS_TEST_TEMPDIR").unwrap());
+    }
+    verify |result| {
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.output, result.temp_dir.to_str().unwrap());
+        assert!(result.temp_dir.is_dir());
+    }
+}
@@ -0,0 +1,49 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use subprocess_test::subprocess_spawn;
+
+subprocess_test::subprocess_test! {
+    #[test(join_threads = true)]
+    fn threads_joined() {
+        subprocess_spawn(|| {
+            sleep(Duration::from_millis(100));
+            println!("Thread");
+            // Threads spawned while joining are joined too
+            subprocess_spawn(|| {
+                sleep(Duration::from_millis(100));
+                println!("Nested thread");
+            });
+        });
+        println!("Body");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Body\nThread\nNested thread\n");
+    }
+
+    // Backtrace printing may take longer than thread's delay, so it's disabled
+    #[test(join_threads = true, strip_backtrace = true)]
+    fn threads_joined_after_panic() {
+        subprocess_spawn(|| {
+            sleep(Duration::from_millis(100));
+            println!("Thread");
+        });
+        panic!("Body panicked");
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Body panicked"), "{output}");
+        assert!(output.ends_with("Thread\n"), "{output}");
+    }
+
+    #[test(join_threads = true, expect_failure = 101)]
+    fn threads_panicked() {
+        subprocess_spawn(|| panic!("Thread panicked"));
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.contains("Thread panicked"), "{output}");
+        assert!(output.contains("1 thread(s) spawned by test body panicked"), "{output}");
+    }
+}
@@ -0,0 +1,57 @@
+#[cfg(unix)]
+const FAKE_VALGRIND_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/fake_valgrind");
+
+subprocess_test::subprocess_test! {
+    #[test]
+    #[ignore = "requires SUBPROCESS_TEST_VALGRIND, invoked by valgrind_missing"]
+    fn valgrind_missing_target() {
+        println!("Body");
+    }
+
+    #[test]
+    fn valgrind_missing() {
+        valgrind_missing_target();
+    }
+    setup |ctx| {
+        ctx.env("SUBPROCESS_TEST_VALGRIND", "1");
+        ctx.env("PATH", "");
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert!(output.contains("SUBPROCESS_TEST_VALGRIND is set, but valgrind isn't found in PATH"), "{output}");
+        assert!(output.contains("valgrind_missing_target ... SKIPPED"), "{output}");
+        assert!(!output.contains("Body"), "{output}");
+    }
+
+    #[test]
+    #[ignore = "requires fake valgrind, invoked by valgrind_report"]
+    fn valgrind_report_target() {
+        println!("Body");
+    }
+
+    // Fake runs test and reports error, like Valgrind with `--error-exitcode=1`
+    #[test]
+    #[cfg(unix)]
+    fn valgrind_report() {
+        valgrind_report_target();
+    }
+    setup |ctx| {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = format!("{FAKE_VALGRIND_DIR}/valgrind");
+        std::fs::create_dir_all(FAKE_VALGRIND_DIR).unwrap();
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nshift 3\n\"$@\"\necho '==1== Invalid read' > \"$__SUBPROCESS_TEST_VALGRIND_LOG__\"\nexit 1\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        ctx.env("SUBPROCESS_TEST_VALGRIND", "1");
+        ctx.env("PATH", FAKE_VALGRIND_DIR);
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Body"), "{output}");
+        assert!(output.contains("Valgrind report for test valgrind_report_target:\n==1== Invalid read"), "{output}");
+    }
+}
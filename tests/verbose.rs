@@ -0,0 +1,57 @@
+subprocess_test::subprocess_test! {
+    // Target tests which are invoked by tests below, since echo is printed by parent process
+    // of their subprocesses
+    #[test(verbose = true)]
+    #[ignore = "invoked by verbose_echo"]
+    fn verbose_target() {
+        println!("One");
+        println!("Two");
+    }
+
+    #[test(verbose = true, split_output = true)]
+    #[ignore = "invoked by verbose_echo_split_output"]
+    fn verbose_split_target() {
+        println!("Out");
+        eprintln!("Err");
+    }
+    verify |success, _stdout, _stderr| {
+        assert!(success);
+    }
+
+    #[test]
+    #[ignore = "invoked by verbose_env_override"]
+    fn verbose_env_target() {
+        println!("Overridden");
+    }
+
+    #[test]
+    fn verbose_echo() {
+        verbose_target();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "[verbose_target] One\n[verbose_target] Two\n");
+    }
+
+    #[test]
+    fn verbose_echo_split_output() {
+        verbose_split_target();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "[verbose_split_target] Out\n[verbose_split_target] Err\n");
+    }
+
+    #[test]
+    fn verbose_env_override() {
+        // SAFETY: subprocess runs only this test, so no other threads access environment
+        unsafe {
+            std::env::set_var("SUBPROCESS_TEST_VERBOSE", "1");
+        }
+        verbose_env_target();
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "[verbose_env_target] Overridden\n");
+    }
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "regex")]
+
+subprocess_test::subprocess_test! {
+    #[test(verify_matches = r"^Answer is \d+\n$")]
+    fn verify_matches_output() {
+        println!("Answer is 42");
+    }
+
+    // Target test which is invoked by `verify_matches_mismatch` below, since it's supposed to fail
+    #[test(verify_matches = r"^\d+$")]
+    #[ignore = "fails on purpose, invoked by verify_matches_mismatch"]
+    fn verify_matches_target() {
+        print!("Not a number");
+    }
+
+    #[test]
+    fn verify_matches_mismatch() {
+        verify_matches_target();
+    }
+    verify |success, output| {
+        assert!(!success, "{output}");
+        assert!(output.contains(r"doesn't match pattern ^\d+$"), "{output}");
+        assert!(output.contains("Not a number"), "{output}");
+    }
+}
@@ -0,0 +1,51 @@
+use std::num::ParseIntError;
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn verify_question_mark() {
+        print!("42");
+    }
+    verify |success, output| {
+        assert!(success);
+        let value: u32 = output.parse()?;
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    #[ignore = "fails on purpose, invoked by verify_error_fails"]
+    fn verify_error_target() {
+        print!("Not a number");
+    }
+    verify |success, output| {
+        assert!(success);
+        let _: u32 = output.parse()?;
+    }
+
+    #[test]
+    fn verify_error_fails() {
+        verify_error_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(output.contains("Verify block failed: invalid digit found in string"), "{output}");
+    }
+
+    // Test with result type gets error from `verify` block as its result
+    #[test]
+    #[ignore = "fails on purpose, invoked by verify_error_returned"]
+    fn verify_error_returned_target() -> Result<(), ParseIntError> {
+        print!("Not a number");
+        Ok(())
+    }
+    verify |success, output| {
+        assert!(success);
+        let _: u32 = output.parse()?;
+        Ok(())
+    }
+
+    #[test]
+    fn verify_error_returned() {
+        let error = verify_error_returned_target().unwrap_err();
+        assert_eq!(error.to_string(), "invalid digit found in string");
+    }
+}
@@ -0,0 +1,33 @@
+subprocess_test::subprocess_test! {
+    #[test(working_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/subprocess_snapshots"))]
+    fn fixture_read_relative_to_working_dir() {
+        print!("{}", std::fs::read_to_string("snapshot.snapshot_target.txt").unwrap());
+    }
+    verify |success, output| {
+        assert!(success, "{output}");
+        assert_eq!(output, "Snapshot\n");
+    }
+
+    #[test(working_dir = std::env::temp_dir())]
+    fn working_dir_from_expression() {
+        let current_dir = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(current_dir, std::env::temp_dir().canonicalize().unwrap());
+    }
+
+    // Target test which is invoked by `missing_working_dir_fails` below, since it's supposed to fail
+    #[test(working_dir = "/nonexistent/subprocess_test/dir")]
+    #[ignore = "fails on purpose, invoked by missing_working_dir_fails"]
+    fn missing_working_dir_target() {}
+
+    #[test]
+    fn missing_working_dir_fails() {
+        missing_working_dir_target();
+    }
+    verify |success, output| {
+        assert!(!success);
+        assert!(
+            output.contains("Working directory /nonexistent/subprocess_test/dir for test missing_working_dir_target doesn't exist"),
+            "{output}"
+        );
+    }
+}
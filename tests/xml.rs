@@ -0,0 +1,14 @@
+#![cfg(feature = "xml")]
+
+subprocess_test::subprocess_test! {
+    #[test]
+    fn xml_status_code() {
+        println!(r#"<status><code>0</code><message lang="en">All &amp; everything</message></status>"#);
+    }
+    verify |success, output| {
+        assert!(success);
+        subprocess_test::assert_subprocess_output_xml!(output, "/status/code/text()", "0");
+        subprocess_test::assert_subprocess_output_xml!(output, "/status/message", "All & everything");
+        subprocess_test::assert_subprocess_output_xml!(output, "/status/message/@lang", "en");
+    }
+}